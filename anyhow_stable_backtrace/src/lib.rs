@@ -6,7 +6,7 @@
 // This crate does not honor `RUST_BACKTRACE` and `RUST_LIB_BACKTRACE`.
 
 use core::{
-    fmt::{Debug, Formatter, Result as FmtResult},
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
     result::Result as CoreResult,
 };
 use std::error::Error as StdError;
@@ -34,6 +34,11 @@ impl Error {
             backtrace: Backtrace::new(),
         }
     }
+
+    #[must_use]
+    pub fn downcast_ref<E: Display + Debug + Send + Sync + 'static>(&self) -> Option<&E> {
+        self.anyhow_error.downcast_ref()
+    }
 }
 
 impl<E: StdError + Send + Sync + 'static> From<E> for Error {
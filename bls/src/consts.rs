@@ -1,3 +1,4 @@
+pub const MESSAGE_SIZE: usize = 32;
 pub const PUBLIC_KEY_SIZE: usize = 48;
 pub const SECRET_KEY_SIZE: usize = 32;
 pub const SIGNATURE_SIZE: usize = 96;
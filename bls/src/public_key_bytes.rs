@@ -3,8 +3,7 @@ use std::io::Write;
 use fixed_hash::construct_fixed_hash;
 use impl_serde::impl_fixed_hash_serde;
 use serde_hex::{Error as SerdeHexError, SerHex, Strict};
-use ssz::{SszDecode, SszDecodeError, SszEncode};
-use tree_hash::{Hash256, MerkleHasher, TreeHash, TreeHashType, BYTES_PER_CHUNK};
+use ssz::{merkleization::Merkleized, SszDecode, SszDecodeError, SszEncode, SszHash, H256};
 
 use crate::consts::PUBLIC_KEY_SIZE;
 
@@ -50,27 +49,14 @@ impl SszDecode for PublicKeyBytes {
     }
 }
 
-impl TreeHash for PublicKeyBytes {
-    fn tree_hash_type() -> TreeHashType {
-        TreeHashType::Vector
-    }
-
-    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
-        unreachable!("public keys are not a basic type and thus should never be packed")
-    }
-
-    fn tree_hash_packing_factor() -> usize {
-        unreachable!("public keys are not a basic type and thus should never be packed")
+impl SszHash for PublicKeyBytes {
+    fn tree_hash_root(&self) -> H256 {
+        self.0.tree_hash_root()
     }
+}
 
-    fn tree_hash_root(&self) -> Hash256 {
-        let num_leaves = (PUBLIC_KEY_SIZE + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK;
-        let mut hasher = MerkleHasher::with_leaves(num_leaves);
-        hasher
-            .write(&self.0)
-            .expect("the number of leaves as calculated above should be correct");
-        hasher
-            .finish()
-            .expect("the number of leaves as calculated above should be correct")
+impl Merkleized for PublicKeyBytes {
+    fn root(&self) -> H256 {
+        self.tree_hash_root()
     }
 }
@@ -2,7 +2,10 @@ use core::convert::TryFrom;
 
 use bls_eth_rust::Signature as RawSignature;
 
-use crate::{consts::SIGNATURE_SIZE, Error, PublicKey, SignatureBytes};
+use crate::{
+    consts::{MESSAGE_SIZE, SIGNATURE_SIZE},
+    Error, PublicKey, SignatureBytes,
+};
 
 #[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
 pub struct Signature(pub(crate) RawSignature);
@@ -47,6 +50,63 @@ impl Signature {
         self.0.verify(&public_key.0, message.as_ref())
     }
 
+    /// Verifies an aggregate of signatures over distinct 32 byte messages.
+    ///
+    /// Returns `false` if there are no public keys or if their count does not match that of the
+    /// messages. Uniqueness of messages is not checked because Eth2 relies on proofs of possession.
+    #[must_use]
+    pub fn aggregate_verify(
+        self,
+        public_keys: impl IntoIterator<Item = PublicKey>,
+        messages: impl IntoIterator<Item = impl AsRef<[u8]>>,
+    ) -> bool {
+        let raw_public_keys = public_keys
+            .into_iter()
+            .map(|public_key| public_key.0)
+            .collect::<Vec<_>>();
+
+        let mut message_count = 0;
+        let mut concatenated_messages = Vec::with_capacity(raw_public_keys.len() * MESSAGE_SIZE);
+
+        for message in messages {
+            let message = message.as_ref();
+            if message.len() != MESSAGE_SIZE {
+                return false;
+            }
+            concatenated_messages.extend_from_slice(message);
+            message_count += 1;
+        }
+
+        if raw_public_keys.is_empty() || raw_public_keys.len() != message_count {
+            return false;
+        }
+
+        self.0
+            .aggregate_verify_no_check(raw_public_keys.as_slice(), concatenated_messages.as_slice())
+    }
+
+    /// Verifies an aggregate of signatures over the same message.
+    ///
+    /// Returns `false` if there are no public keys.
+    #[must_use]
+    pub fn fast_aggregate_verify(
+        self,
+        public_keys: impl IntoIterator<Item = PublicKey>,
+        message: impl AsRef<[u8]>,
+    ) -> bool {
+        let raw_public_keys = public_keys
+            .into_iter()
+            .map(|public_key| public_key.0)
+            .collect::<Vec<_>>();
+
+        if raw_public_keys.is_empty() {
+            return false;
+        }
+
+        self.0
+            .fast_aggregate_verify(raw_public_keys.as_slice(), message.as_ref())
+    }
+
     pub fn aggregate_in_place(&mut self, signature: Self) {
         // `RawSignature::add_assign` accepts a `*const Signature` but is not marked unsafe.
         // This is most likely an oversight.
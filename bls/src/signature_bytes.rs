@@ -2,8 +2,7 @@ use core::convert::TryInto as _;
 
 use fixed_hash::construct_fixed_hash;
 use impl_serde::impl_fixed_hash_serde;
-use ssz::{SszDecode, SszDecodeError, SszEncode};
-use tree_hash::{Hash256, MerkleHasher, TreeHash, TreeHashType, BYTES_PER_CHUNK};
+use ssz::{merkleization::Merkleized, SszDecode, SszDecodeError, SszEncode, SszHash, H256};
 
 use crate::{consts::SIGNATURE_SIZE, Error, Signature};
 
@@ -37,28 +36,15 @@ impl SszDecode for SignatureBytes {
     }
 }
 
-impl TreeHash for SignatureBytes {
-    fn tree_hash_type() -> TreeHashType {
-        TreeHashType::Vector
-    }
-
-    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
-        unreachable!("signatures are not a basic type and thus should never be packed")
-    }
-
-    fn tree_hash_packing_factor() -> usize {
-        unreachable!("signatures are not a basic type and thus should never be packed")
+impl SszHash for SignatureBytes {
+    fn tree_hash_root(&self) -> H256 {
+        self.0.tree_hash_root()
     }
+}
 
-    fn tree_hash_root(&self) -> Hash256 {
-        let num_leaves = (SIGNATURE_SIZE + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK;
-        let mut hasher = MerkleHasher::with_leaves(num_leaves);
-        hasher
-            .write(&self.0)
-            .expect("the number of leaves as calculated above should be correct");
-        hasher
-            .finish()
-            .expect("the number of leaves as calculated above should be correct")
+impl Merkleized for SignatureBytes {
+    fn root(&self) -> H256 {
+        self.tree_hash_root()
     }
 }
 
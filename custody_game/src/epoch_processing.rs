@@ -0,0 +1,249 @@
+use core::convert::TryFrom as _;
+use std::collections::HashSet;
+
+use anyhow::Result;
+use helper_functions::{accessors::get_current_epoch, mutators::slash_validator};
+use typenum::Unsigned as _;
+use types::{
+    arc_ext::ArcExt as _, beacon_state::BeaconState, chain_spec::ChainSpec, config::Config,
+    consts::FAR_FUTURE_EPOCH, containers::CustodyChunkChallengeRecord, primitives::ValidatorIndex,
+};
+
+use crate::get_custody_period_for_validator;
+
+pub fn process_reveal_deadlines<C: Config>(
+    state: &mut BeaconState<C>,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let epoch = get_current_epoch(state);
+
+    for index in 0..state.validators.len() {
+        let validator_index = ValidatorIndex::try_from(index)?;
+        let deadline = state.validators[index].next_custody_secret_to_reveal + 1;
+
+        if get_custody_period_for_validator::<C>(validator_index, epoch) > deadline {
+            slash_validator(state, validator_index, None, chain_spec)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn process_challenge_deadlines<C: Config>(
+    state: &mut BeaconState<C>,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let current_epoch = get_current_epoch(state);
+
+    for index in 0..state.custody_chunk_challenge_records.len() {
+        let record = &state.custody_chunk_challenge_records[index];
+
+        if current_epoch > record.inclusion_epoch + C::EPOCHS_PER_CUSTODY_PERIOD {
+            let responder_index = record.responder_index;
+            let challenger_index = record.challenger_index;
+            slash_validator(state, responder_index, Some(challenger_index), chain_spec)?;
+            state.custody_chunk_challenge_records[index] = CustodyChunkChallengeRecord::default();
+        }
+    }
+
+    Ok(())
+}
+
+pub fn process_custody_final_updates<C: Config>(
+    state: &mut BeaconState<C>,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let current_epoch = get_current_epoch(state);
+
+    // Clean up exposed RANDAO key reveals
+    let exposed_index =
+        usize::try_from(current_epoch % C::EarlyDerivedSecretPenaltyMaxFutureEpochs::U64)?;
+    state.exposed_derived_secrets.make_mut()[exposed_index] = Default::default();
+
+    // Reset withdrawable epochs if challenge records are empty
+    let validator_indices_in_records = state
+        .custody_chunk_challenge_records
+        .iter()
+        .map(|record| record.responder_index)
+        .collect::<HashSet<_>>();
+
+    for (index, validator) in state.validators.make_mut().iter_mut().enumerate() {
+        if validator.exit_epoch == FAR_FUTURE_EPOCH {
+            continue;
+        }
+
+        let not_all_secrets_are_revealed =
+            validator.all_custody_secrets_revealed_epoch == FAR_FUTURE_EPOCH;
+
+        if validator_indices_in_records.contains(&ValidatorIndex::try_from(index)?)
+            || not_all_secrets_are_revealed
+        {
+            // Delay withdrawable epochs if challenge records are not empty or not all
+            // custody secrets revealed
+            validator.withdrawable_epoch = FAR_FUTURE_EPOCH;
+        } else if validator.withdrawable_epoch == FAR_FUTURE_EPOCH {
+            // Reset withdrawable epochs if challenge records are empty and all secrets are revealed
+            validator.withdrawable_epoch = validator.all_custody_secrets_revealed_epoch
+                + chain_spec.min_validator_withdrawability_delay;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ssz_new::types::VariableList;
+    use types::{config::MinimalConfig, primitives::H256};
+
+    use crate::tests::{chain_spec, state};
+
+    use super::*;
+
+    #[test]
+    fn test_process_reveal_deadlines() {
+        let mut state = state();
+        // Epoch 20 is in custody period 2 of validator 0 and no later than period 3 of the rest.
+        state.slot = 160;
+        for validator in state.validators.make_mut().iter_mut().skip(1) {
+            validator.next_custody_secret_to_reveal = 2;
+        }
+
+        process_reveal_deadlines(&mut state, &chain_spec()).expect("state has valid validators");
+
+        let slashed = state
+            .validators
+            .iter()
+            .map(|validator| validator.slashed)
+            .collect::<Vec<_>>();
+        assert!(slashed[0]);
+        assert!(slashed[1..].iter().all(|slashed| !slashed));
+    }
+
+    #[test]
+    fn test_process_challenge_deadlines() {
+        let mut state = state();
+        let expired_record = CustodyChunkChallengeRecord {
+            challenge_index: 0,
+            challenger_index: 6,
+            responder_index: 5,
+            inclusion_epoch: 1,
+            data_root: H256::repeat_byte(1),
+            chunk_index: 0,
+        };
+        let pending_record = CustodyChunkChallengeRecord {
+            challenge_index: 1,
+            responder_index: 7,
+            inclusion_epoch: 2,
+            ..expired_record.clone()
+        };
+        state.custody_chunk_challenge_records =
+            VariableList::from(vec![expired_record, pending_record.clone()]);
+
+        process_challenge_deadlines(&mut state, &chain_spec()).expect("state has valid records");
+
+        assert!(state.validators[5].slashed);
+        assert!(!state.validators[7].slashed);
+        assert_eq!(
+            state.custody_chunk_challenge_records[..],
+            [CustodyChunkChallengeRecord::default(), pending_record],
+        );
+    }
+
+    #[test]
+    fn test_process_custody_final_updates() {
+        let mut state = state();
+        state.exposed_derived_secrets.make_mut()[10] = VariableList::from(vec![1, 2]);
+        state.exposed_derived_secrets.make_mut()[11] = VariableList::from(vec![3]);
+        state.custody_chunk_challenge_records =
+            VariableList::from(vec![CustodyChunkChallengeRecord {
+                responder_index: 3,
+                ..CustodyChunkChallengeRecord::default()
+            }]);
+        let validators = state.validators.make_mut();
+        for validator in validators.iter_mut().take(4).skip(1) {
+            validator.exit_epoch = 5;
+            validator.all_custody_secrets_revealed_epoch = 7;
+        }
+        validators[2].all_custody_secrets_revealed_epoch = FAR_FUTURE_EPOCH;
+        validators[4].withdrawable_epoch = 20;
+
+        process_custody_final_updates(&mut state, &chain_spec())
+            .expect("state has valid validators");
+
+        assert!(state.exposed_derived_secrets[10].is_empty());
+        assert_eq!(state.exposed_derived_secrets[11][..], [3]);
+
+        let withdrawable_epochs = state
+            .validators
+            .iter()
+            .take(5)
+            .map(|validator| validator.withdrawable_epoch)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            withdrawable_epochs,
+            [
+                FAR_FUTURE_EPOCH,
+                7 + MinimalConfig::MIN_VALIDATOR_WITHDRAWABILITY_DELAY,
+                FAR_FUTURE_EPOCH,
+                FAR_FUTURE_EPOCH,
+                20,
+            ],
+        );
+    }
+}
+
+#[cfg(test)]
+mod spec_tests {
+    use spec_test_utils::{testing, Case};
+    use test_generator::test_resources;
+    use types::config::{MainnetConfig, MinimalConfig};
+
+    use super::*;
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/reveal_deadlines/*/*"
+    )]
+    fn mainnet_reveal_deadlines(case: Case) {
+        run_case::<MainnetConfig>(case, process_reveal_deadlines);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/reveal_deadlines/*/*"
+    )]
+    fn minimal_reveal_deadlines(case: Case) {
+        run_case::<MinimalConfig>(case, process_reveal_deadlines);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/challenge_deadlines/*/*"
+    )]
+    fn mainnet_challenge_deadlines(case: Case) {
+        run_case::<MainnetConfig>(case, process_challenge_deadlines);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/challenge_deadlines/*/*"
+    )]
+    fn minimal_challenge_deadlines(case: Case) {
+        run_case::<MinimalConfig>(case, process_challenge_deadlines);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/custody_final_updates/*/*"
+    )]
+    fn mainnet_custody_final_updates(case: Case) {
+        run_case::<MainnetConfig>(case, process_custody_final_updates);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/custody_final_updates/*/*"
+    )]
+    fn minimal_custody_final_updates(case: Case) {
+        run_case::<MinimalConfig>(case, process_custody_final_updates);
+    }
+
+    fn run_case<C: Config>(case: Case, process: fn(&mut BeaconState<C>, &ChainSpec) -> Result<()>) {
+        testing::run_case(case, |state| process(state, &ChainSpec::from_config::<C>()));
+    }
+}
@@ -0,0 +1,63 @@
+use thiserror::Error;
+use types::primitives::{Epoch, Root, ValidatorIndex};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unknown validator ({index})")]
+    UnknownValidator { index: ValidatorIndex },
+    #[error("validator {index} is not slashable")]
+    ValidatorNotSlashable { index: ValidatorIndex },
+    #[error("attestation is too old to be challenged ({epoch} > {max_epoch})")]
+    AttestationTooOldToChallenge { epoch: Epoch, max_epoch: Epoch },
+    #[error("responder {index} exited too long ago to be challenged ({epoch} > {max_epoch})")]
+    ResponderTooOldToChallenge {
+        index: ValidatorIndex,
+        epoch: Epoch,
+        max_epoch: Epoch,
+    },
+    #[error("validator {index} did not participate in the attestation")]
+    NotAnAttester { index: ValidatorIndex },
+    #[error("shard transition does not match the attestation ({root} != {expected_root})")]
+    IncorrectShardTransition { root: Root, expected_root: Root },
+    #[error("shard transition has no data at index {data_index}")]
+    UnknownDataIndex { data_index: u64 },
+    #[error("chunk {chunk_index} of data root {data_root} has already been challenged")]
+    DuplicateChunkChallenge { data_root: Root, chunk_index: u64 },
+    #[error("custody chunk challenge record list is full")]
+    ChunkChallengeRecordsFull {},
+    #[error("chunk index is out of range ({chunk_index} >= {chunk_count})")]
+    ChunkIndexOutOfRange { chunk_index: u64, chunk_count: u64 },
+    #[error("no unique chunk challenge with index {challenge_index}")]
+    UnknownChunkChallenge { challenge_index: u64 },
+    #[error("response is for the wrong chunk ({chunk_index} != {expected_chunk_index})")]
+    IncorrectResponseChunkIndex {
+        chunk_index: u64,
+        expected_chunk_index: u64,
+    },
+    #[error("chunk response Merkle branch is invalid")]
+    InvalidChunkResponseBranch {},
+    #[error("custody secret {period} of validator {index} cannot be revealed yet")]
+    CustodyKeyRevealTooEarly { index: ValidatorIndex, period: u64 },
+    #[error("custody key reveal signature is invalid")]
+    InvalidCustodyKeyReveal {},
+    #[error("derived secret for epoch {epoch} is not revealed early enough")]
+    DerivedSecretRevealTooLate { epoch: Epoch },
+    #[error("derived secret for epoch {epoch} is revealed too far in the future")]
+    DerivedSecretRevealTooEarly { epoch: Epoch },
+    #[error("validator {index} is already slashed")]
+    ValidatorAlreadySlashed { index: ValidatorIndex },
+    #[error("derived secret of validator {index} for epoch {epoch} is already exposed")]
+    DerivedSecretAlreadyExposed { index: ValidatorIndex, epoch: Epoch },
+    #[error("exposed derived secret list for epoch {epoch} is full")]
+    ExposedDerivedSecretsFull { epoch: Epoch },
+    #[error("early derived secret reveal signature is invalid")]
+    InvalidEarlyDerivedSecretReveal {},
+    #[error("custody slashing signature is invalid")]
+    InvalidCustodySlashingSignature {},
+    #[error("custody slashing data length is incorrect ({length} != {expected_length})")]
+    IncorrectCustodySlashingDataLength { length: u64, expected_length: u64 },
+    #[error("custody slashing data root is incorrect ({root} != {expected_root})")]
+    IncorrectCustodySlashingDataRoot { root: Root, expected_root: Root },
+    #[error("malefactor custody secret is invalid")]
+    InvalidMalefactorSecret {},
+}
@@ -0,0 +1,1238 @@
+pub mod epoch_processing;
+mod error;
+
+use core::convert::TryFrom as _;
+
+use anyhow::{ensure, Result};
+use error::Error;
+use helper_functions::{
+    accessors::{
+        get_active_validator_indices, get_attesting_indices, get_base_reward, get_beacon_committee,
+        get_beacon_proposer_index, get_current_epoch, get_domain, get_indexed_attestation,
+    },
+    crypto::{bls_verify, optional_aggregate_verify},
+    custody::compute_custody_bit,
+    misc::compute_signing_root,
+    mutators::{decrease_balance, increase_balance, slash_validator},
+    predicates::{is_slashable_validator, is_valid_merkle_branch, validate_indexed_attestation},
+};
+use ssz_new::SszHash as _;
+use typenum::Unsigned as _;
+use types::{
+    arc_ext::ArcExt as _,
+    beacon_state::BeaconState,
+    chain_spec::ChainSpec,
+    config::Config,
+    consts::FAR_FUTURE_EPOCH,
+    containers::{
+        Attestation, BeaconBlockBody, CustodyChunkChallenge, CustodyChunkChallengeRecord,
+        CustodyChunkResponse, CustodyKeyReveal, EarlyDerivedSecretReveal, ShardTransition,
+        SignedCustodySlashing, Validator,
+    },
+    primitives::{Epoch, Gwei, ValidatorIndex},
+};
+
+#[must_use]
+pub fn get_randao_epoch_for_custody_period<C: Config>(
+    period: u64,
+    validator_index: ValidatorIndex,
+) -> Epoch {
+    let next_period_start = (period + 1) * C::EPOCHS_PER_CUSTODY_PERIOD
+        - validator_index % C::EPOCHS_PER_CUSTODY_PERIOD;
+    next_period_start + C::CUSTODY_PERIOD_TO_RANDAO_PADDING
+}
+
+#[must_use]
+pub fn get_custody_period_for_validator<C: Config>(
+    validator_index: ValidatorIndex,
+    epoch: Epoch,
+) -> u64 {
+    // The `validator_index` offset staggers custody periods across validators.
+    (epoch + validator_index % C::EPOCHS_PER_CUSTODY_PERIOD) / C::EPOCHS_PER_CUSTODY_PERIOD
+}
+
+pub fn process_custody_game_operations<C: Config>(
+    state: &mut BeaconState<C>,
+    body: &BeaconBlockBody<C>,
+    verify_signatures: bool,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    for challenge in body.chunk_challenges.iter() {
+        process_chunk_challenge(state, challenge, verify_signatures)?;
+    }
+    for response in body.chunk_challenge_responses.iter() {
+        process_chunk_challenge_response(state, response)?;
+    }
+    for reveal in body.custody_key_reveals.iter() {
+        process_custody_key_reveal(state, reveal, verify_signatures)?;
+    }
+    for reveal in body.early_derived_secret_reveals.iter() {
+        process_early_derived_secret_reveal(state, reveal, verify_signatures, chain_spec)?;
+    }
+    for custody_slashing in body.custody_slashings.iter() {
+        process_custody_slashing(state, custody_slashing, verify_signatures, chain_spec)?;
+    }
+    Ok(())
+}
+
+pub fn process_chunk_challenge<C: Config>(
+    state: &mut BeaconState<C>,
+    challenge: &CustodyChunkChallenge<C>,
+    verify_signatures: bool,
+) -> Result<()> {
+    let current_epoch = get_current_epoch(state);
+
+    // Verify the attestation
+    verify_attestation(state, &challenge.attestation, verify_signatures)?;
+
+    // Verify it is not too late to challenge the attestation
+    let max_attestation_challenge_epoch =
+        challenge.attestation.data.target.epoch + C::MAX_CHUNK_CHALLENGE_DELAY;
+    ensure!(
+        current_epoch <= max_attestation_challenge_epoch,
+        Error::AttestationTooOldToChallenge {
+            epoch: current_epoch,
+            max_epoch: max_attestation_challenge_epoch,
+        }
+    );
+
+    // Verify it is not too late to challenge the responder
+    let responder = validator(state, challenge.responder_index)?;
+    if responder.exit_epoch < FAR_FUTURE_EPOCH {
+        let max_responder_challenge_epoch = responder.exit_epoch + C::MAX_CHUNK_CHALLENGE_DELAY;
+        ensure!(
+            current_epoch <= max_responder_challenge_epoch,
+            Error::ResponderTooOldToChallenge {
+                index: challenge.responder_index,
+                epoch: current_epoch,
+                max_epoch: max_responder_challenge_epoch,
+            }
+        );
+    }
+
+    // Verify responder is slashable
+    ensure!(
+        is_slashable_validator(responder, current_epoch),
+        Error::ValidatorNotSlashable {
+            index: challenge.responder_index,
+        }
+    );
+
+    // Verify the responder participated in the attestation
+    verify_attester(state, &challenge.attestation, challenge.responder_index)?;
+
+    // Verify shard transition is correctly given
+    verify_shard_transition(&challenge.attestation, &challenge.shard_transition)?;
+    let data_index = usize::try_from(challenge.data_index)?;
+    let data_root = *challenge
+        .shard_transition
+        .shard_data_roots
+        .get(data_index)
+        .ok_or(Error::UnknownDataIndex {
+            data_index: challenge.data_index,
+        })?;
+
+    // Verify the challenge is not a duplicate
+    for record in state.custody_chunk_challenge_records.iter() {
+        ensure!(
+            record.data_root != data_root || record.chunk_index != challenge.chunk_index,
+            Error::DuplicateChunkChallenge {
+                data_root,
+                chunk_index: challenge.chunk_index,
+            }
+        );
+    }
+
+    // Verify depth
+    let shard_block_length = *challenge
+        .shard_transition
+        .shard_block_lengths
+        .get(data_index)
+        .ok_or(Error::UnknownDataIndex {
+            data_index: challenge.data_index,
+        })?;
+    let bytes_per_custody_chunk = C::BytesPerCustodyChunk::U64;
+    let transition_chunks = shard_block_length / bytes_per_custody_chunk
+        + u64::from(shard_block_length % bytes_per_custody_chunk != 0);
+    ensure!(
+        challenge.chunk_index < transition_chunks,
+        Error::ChunkIndexOutOfRange {
+            chunk_index: challenge.chunk_index,
+            chunk_count: transition_chunks,
+        }
+    );
+
+    // Add new chunk challenge record
+    let new_record = CustodyChunkChallengeRecord {
+        challenge_index: state.custody_chunk_challenge_index,
+        challenger_index: get_beacon_proposer_index(state)?,
+        responder_index: challenge.responder_index,
+        inclusion_epoch: current_epoch,
+        data_root,
+        chunk_index: challenge.chunk_index,
+    };
+    replace_empty_or_append(state, new_record)?;
+
+    state.custody_chunk_challenge_index += 1;
+
+    // Postpone responder withdrawal
+    state.validators.make_mut()[challenge.responder_index as usize].withdrawable_epoch =
+        FAR_FUTURE_EPOCH;
+
+    Ok(())
+}
+
+pub fn process_chunk_challenge_response<C: Config>(
+    state: &mut BeaconState<C>,
+    response: &CustodyChunkResponse<C>,
+) -> Result<()> {
+    // Get matching challenge (if any) from records
+    let mut matching_challenges = state
+        .custody_chunk_challenge_records
+        .iter()
+        .enumerate()
+        .filter(|(_, record)| record.challenge_index == response.challenge_index)
+        .map(|(index, record)| (index, record.clone()));
+
+    let (index_in_records, challenge) =
+        match (matching_challenges.next(), matching_challenges.next()) {
+            (Some(matching_challenge), None) => matching_challenge,
+            _ => {
+                return Err(Error::UnknownChunkChallenge {
+                    challenge_index: response.challenge_index,
+                }
+                .into())
+            }
+        };
+
+    // Verify chunk index
+    ensure!(
+        response.chunk_index == challenge.chunk_index,
+        Error::IncorrectResponseChunkIndex {
+            chunk_index: response.chunk_index,
+            expected_chunk_index: challenge.chunk_index,
+        }
+    );
+
+    // Verify the chunk matches the crosslink data root.
+    // `CustodyResponseDepthInc` includes the `List` length mix-in.
+    ensure!(
+        is_valid_merkle_branch(
+            response.chunk.tree_hash_root(),
+            &response.branch,
+            C::CustodyResponseDepthInc::USIZE,
+            response.chunk_index,
+            challenge.data_root,
+        )?,
+        Error::InvalidChunkResponseBranch {}
+    );
+
+    // Clear the challenge
+    state.custody_chunk_challenge_records[index_in_records] =
+        CustodyChunkChallengeRecord::default();
+
+    // Reward the proposer
+    let proposer_index = get_beacon_proposer_index(state)?;
+    let reward = get_base_reward(state, proposer_index)? / C::MINOR_REWARD_QUOTIENT;
+    increase_balance(state, proposer_index, reward)
+}
+
+pub fn process_custody_key_reveal<C: Config>(
+    state: &mut BeaconState<C>,
+    reveal: &CustodyKeyReveal,
+    verify_signatures: bool,
+) -> Result<()> {
+    let current_epoch = get_current_epoch(state);
+    let revealer = validator(state, reveal.revealer_index)?;
+    let epoch_to_sign = get_randao_epoch_for_custody_period::<C>(
+        revealer.next_custody_secret_to_reveal,
+        reveal.revealer_index,
+    );
+
+    // Only past custody periods can be revealed, except after exiting the exit period can be revealed
+    let custody_reveal_period =
+        get_custody_period_for_validator::<C>(reveal.revealer_index, current_epoch);
+    let is_past_reveal = revealer.next_custody_secret_to_reveal < custody_reveal_period;
+    let is_exited = revealer.exit_epoch <= current_epoch;
+    // The exit period is only computed for exited validators to avoid overflowing
+    // `FAR_FUTURE_EPOCH`.
+    let is_exited_and_exit_period_reveal = is_exited
+        && revealer.next_custody_secret_to_reveal
+            == get_custody_period_for_validator::<C>(
+                reveal.revealer_index,
+                revealer.exit_epoch.saturating_sub(1),
+            );
+    ensure!(
+        is_past_reveal || is_exited_and_exit_period_reveal,
+        Error::CustodyKeyRevealTooEarly {
+            index: reveal.revealer_index,
+            period: revealer.next_custody_secret_to_reveal,
+        }
+    );
+
+    // Revealed validator is active or exited, but not withdrawn
+    ensure!(
+        is_slashable_validator(revealer, current_epoch),
+        Error::ValidatorNotSlashable {
+            index: reveal.revealer_index,
+        }
+    );
+
+    // Verify signature
+    if verify_signatures {
+        let domain = get_domain(state, C::DOMAIN_RANDAO, Some(epoch_to_sign));
+        let signing_root = compute_signing_root(&epoch_to_sign, domain);
+        ensure!(
+            bls_verify(revealer.pubkey, signing_root.as_bytes(), reveal.reveal)?,
+            Error::InvalidCustodyKeyReveal {}
+        );
+    }
+
+    // Process reveal
+    let revealer = &mut state.validators.make_mut()[reveal.revealer_index as usize];
+    if is_exited_and_exit_period_reveal {
+        revealer.all_custody_secrets_revealed_epoch = current_epoch;
+    }
+    revealer.next_custody_secret_to_reveal += 1;
+
+    // Reward Block Proposer
+    let proposer_index = get_beacon_proposer_index(state)?;
+    let reward = get_base_reward(state, reveal.revealer_index)? / C::MINOR_REWARD_QUOTIENT;
+    increase_balance(state, proposer_index, reward)
+}
+
+pub fn process_early_derived_secret_reveal<C: Config>(
+    state: &mut BeaconState<C>,
+    reveal: &EarlyDerivedSecretReveal,
+    verify_signatures: bool,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let current_epoch = get_current_epoch(state);
+    let revealed_validator = validator(state, reveal.revealed_index)?;
+    let derived_secret_location =
+        usize::try_from(reveal.epoch % C::EarlyDerivedSecretPenaltyMaxFutureEpochs::U64)?;
+
+    ensure!(
+        reveal.epoch >= current_epoch + C::RANDAO_PENALTY_EPOCHS,
+        Error::DerivedSecretRevealTooLate {
+            epoch: reveal.epoch,
+        }
+    );
+    ensure!(
+        reveal.epoch < current_epoch + C::EarlyDerivedSecretPenaltyMaxFutureEpochs::U64,
+        Error::DerivedSecretRevealTooEarly {
+            epoch: reveal.epoch,
+        }
+    );
+    ensure!(
+        !revealed_validator.slashed,
+        Error::ValidatorAlreadySlashed {
+            index: reveal.revealed_index,
+        }
+    );
+    ensure!(
+        !state.exposed_derived_secrets[derived_secret_location].contains(&reveal.revealed_index),
+        Error::DerivedSecretAlreadyExposed {
+            index: reveal.revealed_index,
+            epoch: reveal.epoch,
+        }
+    );
+
+    // Verify signature correctness
+    let masker = validator(state, reveal.masker_index)?;
+    if verify_signatures {
+        let domain = get_domain(state, C::DOMAIN_RANDAO, Some(reveal.epoch));
+        let signing_roots = vec![
+            compute_signing_root(&reveal.epoch, domain),
+            compute_signing_root(&reveal.mask, domain),
+        ];
+        ensure!(
+            optional_aggregate_verify(
+                vec![revealed_validator.pubkey, masker.pubkey],
+                signing_roots,
+                reveal.reveal,
+            )?,
+            Error::InvalidEarlyDerivedSecretReveal {}
+        );
+    }
+
+    if reveal.epoch >= current_epoch + C::CUSTODY_PERIOD_TO_RANDAO_PADDING {
+        // Full slashing when the secret was revealed so early it may be a valid custody
+        // round key
+        return slash_validator(
+            state,
+            reveal.revealed_index,
+            Some(reveal.masker_index),
+            chain_spec,
+        );
+    }
+
+    // Only a small penalty proportional to proposer slot reward for RANDAO reveal
+    // that does not interfere with the custody period
+    let active_validator_count = get_active_validator_indices(state, current_epoch)?.count() as u64;
+    let max_proposer_slot_reward = get_base_reward(state, reveal.revealed_index)?
+        * C::SlotsPerEpoch::U64
+        / active_validator_count
+        / C::PROPOSER_REWARD_QUOTIENT;
+    let exposed_count = state.exposed_derived_secrets[derived_secret_location].len() as u64;
+    let penalty: Gwei = max_proposer_slot_reward
+        * C::EARLY_DERIVED_SECRET_REVEAL_SLOT_REWARD_MULTIPLE
+        * (exposed_count + 1);
+
+    // Apply penalty
+    let proposer_index = get_beacon_proposer_index(state)?;
+    let whistleblower_index = reveal.masker_index;
+    let whistleblowing_reward = penalty / C::WHISTLEBLOWER_REWARD_QUOTIENT;
+    let proposer_reward = whistleblowing_reward / C::PROPOSER_REWARD_QUOTIENT;
+    increase_balance(state, proposer_index, proposer_reward)?;
+    increase_balance(
+        state,
+        whistleblower_index,
+        whistleblowing_reward - proposer_reward,
+    )?;
+    decrease_balance(state, reveal.revealed_index, penalty)?;
+
+    // Mark this derived secret as exposed so validator cannot be punished repeatedly
+    state.exposed_derived_secrets.make_mut()[derived_secret_location]
+        .push(reveal.revealed_index)
+        .map_err(|_| Error::ExposedDerivedSecretsFull {
+            epoch: reveal.epoch,
+        })?;
+
+    Ok(())
+}
+
+pub fn process_custody_slashing<C: Config>(
+    state: &mut BeaconState<C>,
+    signed_custody_slashing: &SignedCustodySlashing<C>,
+    verify_signatures: bool,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let custody_slashing = &signed_custody_slashing.message;
+    let attestation = &custody_slashing.attestation;
+    let current_epoch = get_current_epoch(state);
+
+    // Any signed custody-slashing should result in at least one slashing.
+    // If the custody bits are valid, then the claim itself is slashed.
+    let malefactor = validator(state, custody_slashing.malefactor_index)?;
+    let whistleblower = validator(state, custody_slashing.whistleblower_index)?;
+    if verify_signatures {
+        let domain = get_domain(state, C::DOMAIN_CUSTODY_BIT_SLASHING, Some(current_epoch));
+        let signing_root = compute_signing_root(custody_slashing, domain);
+        ensure!(
+            bls_verify(
+                whistleblower.pubkey,
+                signing_root.as_bytes(),
+                signed_custody_slashing.signature,
+            )?,
+            Error::InvalidCustodySlashingSignature {}
+        );
+    }
+
+    // Verify that the whistleblower is slashable
+    ensure!(
+        is_slashable_validator(whistleblower, current_epoch),
+        Error::ValidatorNotSlashable {
+            index: custody_slashing.whistleblower_index,
+        }
+    );
+    // Verify that the claimed malefactor is slashable
+    ensure!(
+        is_slashable_validator(malefactor, current_epoch),
+        Error::ValidatorNotSlashable {
+            index: custody_slashing.malefactor_index,
+        }
+    );
+
+    // Verify the attestation
+    verify_attestation(state, attestation, verify_signatures)?;
+
+    // Verify the shard transition is indeed attested by the attestation
+    let shard_transition = &custody_slashing.shard_transition;
+    verify_shard_transition(attestation, shard_transition)?;
+
+    // Verify that the provided data matches the shard-transition
+    let data_index = usize::try_from(custody_slashing.data_index)?;
+    let unknown_data_index = || Error::UnknownDataIndex {
+        data_index: custody_slashing.data_index,
+    };
+    let expected_length = *shard_transition
+        .shard_block_lengths
+        .get(data_index)
+        .ok_or_else(unknown_data_index)?;
+    let length = custody_slashing.data.len() as u64;
+    ensure!(
+        length == expected_length,
+        Error::IncorrectCustodySlashingDataLength {
+            length,
+            expected_length,
+        }
+    );
+    let expected_root = *shard_transition
+        .shard_data_roots
+        .get(data_index)
+        .ok_or_else(unknown_data_index)?;
+    let root = custody_slashing.data.tree_hash_root();
+    ensure!(
+        root == expected_root,
+        Error::IncorrectCustodySlashingDataRoot {
+            root,
+            expected_root,
+        }
+    );
+
+    // Verify existence and participation of claimed malefactor
+    let attesters = verify_attester(state, attestation, custody_slashing.malefactor_index)?;
+
+    // Verify the malefactor custody key
+    if verify_signatures {
+        let epoch_to_sign = get_randao_epoch_for_custody_period::<C>(
+            get_custody_period_for_validator::<C>(
+                custody_slashing.malefactor_index,
+                attestation.data.target.epoch,
+            ),
+            custody_slashing.malefactor_index,
+        );
+        let domain = get_domain(state, C::DOMAIN_RANDAO, Some(epoch_to_sign));
+        let signing_root = compute_signing_root(&epoch_to_sign, domain);
+        ensure!(
+            bls_verify(
+                malefactor.pubkey,
+                signing_root.as_bytes(),
+                custody_slashing.malefactor_secret,
+            )?,
+            Error::InvalidMalefactorSecret {}
+        );
+    }
+
+    // Compute the custody bit
+    let computed_custody_bit =
+        compute_custody_bit::<C>(custody_slashing.malefactor_secret, &custody_slashing.data);
+
+    // Verify the claim
+    if computed_custody_bit {
+        // Slash the malefactor, reward the other committee members
+        slash_validator(state, custody_slashing.malefactor_index, None, chain_spec)?;
+        let committee = get_beacon_committee(state, attestation.data.slot, attestation.data.index)?;
+        // A committee made up of the malefactor alone has no one to reward.
+        let others_count = (committee.len() as u64 - 1).max(1);
+        let whistleblower_reward =
+            malefactor.effective_balance / C::WHISTLEBLOWER_REWARD_QUOTIENT / others_count;
+        for attester_index in attesters {
+            if attester_index != custody_slashing.malefactor_index {
+                increase_balance(state, attester_index, whistleblower_reward)?;
+            }
+        }
+        // No special whisteblower reward: it is expected to be an attester. Others are free to
+        // slash too however.
+        Ok(())
+    } else {
+        // The claim was false, the custody bit was correct. Slash the whistleblower that induced
+        // this work.
+        slash_validator(
+            state,
+            custody_slashing.whistleblower_index,
+            None,
+            chain_spec,
+        )
+    }
+}
+
+fn validator<C: Config>(state: &BeaconState<C>, index: ValidatorIndex) -> Result<Validator> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| state.validators.get(index))
+        .copied()
+        .ok_or_else(|| Error::UnknownValidator { index }.into())
+}
+
+fn verify_attestation<C: Config>(
+    state: &BeaconState<C>,
+    attestation: &Attestation<C>,
+    verify_signature: bool,
+) -> Result<()> {
+    validate_indexed_attestation(
+        state,
+        &get_indexed_attestation(state, attestation)?,
+        verify_signature,
+    )
+}
+
+// Returns the attesting indices so callers do not have to compute the committee again.
+fn verify_attester<C: Config>(
+    state: &BeaconState<C>,
+    attestation: &Attestation<C>,
+    index: ValidatorIndex,
+) -> Result<Vec<ValidatorIndex>> {
+    let attesters = get_attesting_indices(state, &attestation.data, &attestation.aggregation_bits)?
+        .collect::<Vec<_>>();
+    ensure!(attesters.contains(&index), Error::NotAnAttester { index });
+    Ok(attesters)
+}
+
+fn verify_shard_transition<C: Config>(
+    attestation: &Attestation<C>,
+    shard_transition: &ShardTransition<C>,
+) -> Result<()> {
+    let root = shard_transition.tree_hash_root();
+    let expected_root = attestation.data.shard_transition_root;
+    ensure!(
+        root == expected_root,
+        Error::IncorrectShardTransition {
+            root,
+            expected_root,
+        }
+    );
+    Ok(())
+}
+
+fn replace_empty_or_append<C: Config>(
+    state: &mut BeaconState<C>,
+    new_record: CustodyChunkChallengeRecord,
+) -> Result<()> {
+    let records = &mut state.custody_chunk_challenge_records;
+    let empty_record = CustodyChunkChallengeRecord::default();
+
+    match records.iter_mut().find(|record| **record == empty_record) {
+        Some(record) => *record = new_record,
+        None => records
+            .push(new_record)
+            .map_err(|_| Error::ChunkChallengeRecordsFull {})?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bls::Signature;
+    use helper_functions::misc::compute_epoch_at_slot;
+    use spec_test_utils::testing::{self, assert_error, secret_key};
+    use ssz_new::{
+        types::{BitList, ByteList, ByteVector, FixedVector, VariableList},
+        SszHash,
+    };
+    use types::{
+        config::MinimalConfig,
+        containers::{AttestationData, Checkpoint, CustodySlashing},
+        primitives::{DomainType, Slot, H256},
+    };
+
+    use super::*;
+
+    // Epoch 10 is past the first custody period of every validator.
+    const SLOT: Slot = 80;
+    const VALIDATOR_COUNT: usize = 64;
+
+    pub(crate) fn chain_spec() -> ChainSpec {
+        ChainSpec::from_config::<MinimalConfig>()
+    }
+
+    pub(crate) fn state() -> BeaconState<MinimalConfig> {
+        BeaconState {
+            slot: SLOT,
+            ..testing::state(VALIDATOR_COUNT)
+        }
+    }
+
+    fn sign(
+        state: &BeaconState<MinimalConfig>,
+        index: ValidatorIndex,
+        object: &impl SszHash,
+        domain_type: DomainType,
+        epoch: Epoch,
+    ) -> Signature {
+        let domain = get_domain(state, domain_type, Some(epoch));
+        secret_key(index).sign(compute_signing_root(object, domain))
+    }
+
+    fn data() -> ByteList<<MinimalConfig as Config>::MaxShardBlockSize> {
+        ByteList::from_bytes(vec![1; 100]).expect("data is shorter than the limit")
+    }
+
+    fn shard_transition() -> ShardTransition<MinimalConfig> {
+        ShardTransition {
+            shard_block_lengths: VariableList::from(vec![100]),
+            shard_data_roots: VariableList::from(vec![data().tree_hash_root()]),
+            ..ShardTransition::default()
+        }
+    }
+
+    fn committee(state: &BeaconState<MinimalConfig>) -> Vec<ValidatorIndex> {
+        get_beacon_committee(state, SLOT - 1, 0).expect("the test state has active validators")
+    }
+
+    fn attestation(state: &BeaconState<MinimalConfig>) -> Attestation<MinimalConfig> {
+        let committee = committee(state);
+        let data = AttestationData {
+            slot: SLOT - 1,
+            index: 0,
+            target: Checkpoint {
+                epoch: compute_epoch_at_slot::<MinimalConfig>(SLOT - 1),
+                ..Checkpoint::default()
+            },
+            shard_transition_root: shard_transition().tree_hash_root(),
+            ..AttestationData::default()
+        };
+
+        let mut aggregation_bits =
+            BitList::with_capacity(committee.len()).expect("committee fits in a bitlist");
+        let mut signatures = committee.iter().enumerate().map(|(position, index)| {
+            aggregation_bits
+                .set(position, true)
+                .expect("position is within the committee");
+            sign(
+                state,
+                *index,
+                &data,
+                MinimalConfig::DOMAIN_BEACON_ATTESTER,
+                data.target.epoch,
+            )
+        });
+        let mut signature = signatures.next().expect("committee is not empty");
+        signatures.for_each(|other| signature.aggregate_in_place(other));
+
+        Attestation {
+            aggregation_bits,
+            data,
+            signature: signature.into(),
+        }
+    }
+
+    fn chunk_challenge(state: &BeaconState<MinimalConfig>) -> CustodyChunkChallenge<MinimalConfig> {
+        CustodyChunkChallenge {
+            responder_index: committee(state)[0],
+            shard_transition: shard_transition(),
+            attestation: attestation(state),
+            data_index: 0,
+            chunk_index: 0,
+        }
+    }
+
+    fn chunk_response() -> (CustodyChunkResponse<MinimalConfig>, H256) {
+        let response = CustodyChunkResponse {
+            challenge_index: 0,
+            chunk_index: 0,
+            chunk: ByteVector::from_bytes(vec![2; 4096]).expect("chunk has the right length"),
+            branch: FixedVector::from(vec![H256::zero(); 9]),
+        };
+        let data_root = response
+            .branch
+            .iter()
+            .fold(response.chunk.tree_hash_root(), |hash, node| {
+                hashing::concatenate_and_hash(hash, node)
+            });
+        (response, data_root)
+    }
+
+    fn custody_key_reveal(
+        state: &BeaconState<MinimalConfig>,
+        revealer_index: ValidatorIndex,
+        period: u64,
+    ) -> CustodyKeyReveal {
+        let epoch = get_randao_epoch_for_custody_period::<MinimalConfig>(period, revealer_index);
+        CustodyKeyReveal {
+            revealer_index,
+            reveal: sign(
+                state,
+                revealer_index,
+                &epoch,
+                MinimalConfig::DOMAIN_RANDAO,
+                epoch,
+            )
+            .into(),
+        }
+    }
+
+    fn early_derived_secret_reveal(
+        state: &BeaconState<MinimalConfig>,
+        epoch: Epoch,
+    ) -> EarlyDerivedSecretReveal {
+        let mask = H256::repeat_byte(3);
+        let mut reveal = sign(state, 1, &epoch, MinimalConfig::DOMAIN_RANDAO, epoch);
+        reveal.aggregate_in_place(sign(state, 2, &mask, MinimalConfig::DOMAIN_RANDAO, epoch));
+
+        EarlyDerivedSecretReveal {
+            revealed_index: 1,
+            epoch,
+            reveal: reveal.into(),
+            masker_index: 2,
+            mask,
+        }
+    }
+
+    fn signed_custody_slashing(
+        state: &BeaconState<MinimalConfig>,
+    ) -> SignedCustodySlashing<MinimalConfig> {
+        let committee = committee(state);
+        let (malefactor_index, whistleblower_index) = (committee[0], committee[1]);
+        let attestation = attestation(state);
+        let epoch_to_sign = get_randao_epoch_for_custody_period::<MinimalConfig>(
+            get_custody_period_for_validator::<MinimalConfig>(
+                malefactor_index,
+                attestation.data.target.epoch,
+            ),
+            malefactor_index,
+        );
+        let custody_slashing = CustodySlashing {
+            data_index: 0,
+            malefactor_index,
+            malefactor_secret: sign(
+                state,
+                malefactor_index,
+                &epoch_to_sign,
+                MinimalConfig::DOMAIN_RANDAO,
+                epoch_to_sign,
+            )
+            .into(),
+            whistleblower_index,
+            shard_transition: shard_transition(),
+            attestation,
+            data: data(),
+        };
+
+        SignedCustodySlashing {
+            signature: sign(
+                state,
+                whistleblower_index,
+                &custody_slashing,
+                MinimalConfig::DOMAIN_CUSTODY_BIT_SLASHING,
+                get_current_epoch(state),
+            )
+            .into(),
+            message: custody_slashing,
+        }
+    }
+
+    #[test]
+    fn test_custody_periods() {
+        assert_eq!(get_custody_period_for_validator::<MinimalConfig>(3, 4), 0);
+        assert_eq!(get_custody_period_for_validator::<MinimalConfig>(3, 5), 1);
+        assert_eq!(get_custody_period_for_validator::<MinimalConfig>(8, 10), 1);
+        assert_eq!(
+            get_randao_epoch_for_custody_period::<MinimalConfig>(0, 3),
+            13
+        );
+        assert_eq!(
+            get_randao_epoch_for_custody_period::<MinimalConfig>(1, 8),
+            24
+        );
+    }
+
+    #[test]
+    fn test_process_chunk_challenge() {
+        let mut state = state();
+        let challenge = chunk_challenge(&state);
+
+        process_chunk_challenge(&mut state, &challenge, true).expect("challenge is valid");
+
+        assert_eq!(state.custody_chunk_challenge_index, 1);
+        assert_eq!(
+            state.custody_chunk_challenge_records[..],
+            [CustodyChunkChallengeRecord {
+                challenge_index: 0,
+                challenger_index: get_beacon_proposer_index(&state)
+                    .expect("the test state has active validators"),
+                responder_index: challenge.responder_index,
+                inclusion_epoch: 10,
+                data_root: data().tree_hash_root(),
+                chunk_index: 0,
+            }],
+        );
+
+        assert_error(
+            process_chunk_challenge(&mut state, &challenge, true),
+            |error: &Error| matches!(error, Error::DuplicateChunkChallenge { .. }),
+        );
+    }
+
+    #[test]
+    fn test_process_chunk_challenge_reuses_empty_records() {
+        let mut state = state();
+        state.custody_chunk_challenge_records = VariableList::from(vec![
+            CustodyChunkChallengeRecord {
+                challenge_index: 7,
+                ..CustodyChunkChallengeRecord::default()
+            },
+            CustodyChunkChallengeRecord::default(),
+        ]);
+
+        let challenge = chunk_challenge(&state);
+
+        process_chunk_challenge(&mut state, &challenge, true).expect("challenge is valid");
+
+        assert_eq!(state.custody_chunk_challenge_records.len(), 2);
+        assert_eq!(
+            state.custody_chunk_challenge_records[1].data_root,
+            data().tree_hash_root(),
+        );
+    }
+
+    #[test]
+    fn test_process_chunk_challenge_with_invalid_challenges() {
+        let state = state();
+        let rejects = |challenge: CustodyChunkChallenge<MinimalConfig>,
+                       is_expected: fn(&Error) -> bool| {
+            assert_error(
+                process_chunk_challenge(&mut state.clone(), &challenge, true),
+                is_expected,
+            )
+        };
+
+        let mut out_of_range = chunk_challenge(&state);
+        out_of_range.chunk_index = 1;
+        rejects(out_of_range, |error: &Error| {
+            matches!(error, Error::ChunkIndexOutOfRange { .. })
+        });
+
+        let mut non_attester = chunk_challenge(&state);
+        non_attester.responder_index = (0..VALIDATOR_COUNT as ValidatorIndex)
+            .find(|index| !committee(&state).contains(index))
+            .expect("not every validator is in the committee");
+        rejects(non_attester, |error: &Error| {
+            matches!(error, Error::NotAnAttester { .. })
+        });
+
+        let mut wrong_transition = chunk_challenge(&state);
+        wrong_transition.shard_transition.start_slot = 1;
+        rejects(wrong_transition, |error: &Error| {
+            matches!(error, Error::IncorrectShardTransition { .. })
+        });
+
+        let mut unknown_data = chunk_challenge(&state);
+        unknown_data.data_index = 1;
+        rejects(unknown_data, |error: &Error| {
+            matches!(error, Error::UnknownDataIndex { .. })
+        });
+    }
+
+    #[test]
+    fn test_process_chunk_challenge_response() {
+        let mut state = state();
+        let (response, data_root) = chunk_response();
+        state.custody_chunk_challenge_records =
+            VariableList::from(vec![CustodyChunkChallengeRecord {
+                challenge_index: 0,
+                data_root,
+                ..CustodyChunkChallengeRecord::default()
+            }]);
+
+        let mut wrong_chunk = response.clone();
+        wrong_chunk.chunk =
+            ByteVector::from_bytes(vec![3; 4096]).expect("chunk has the right length");
+        assert_error(
+            process_chunk_challenge_response(&mut state.clone(), &wrong_chunk),
+            |error: &Error| matches!(error, Error::InvalidChunkResponseBranch {}),
+        );
+
+        let mut unknown_challenge = response.clone();
+        unknown_challenge.challenge_index = 1;
+        assert_error(
+            process_chunk_challenge_response(&mut state.clone(), &unknown_challenge),
+            |error: &Error| matches!(error, Error::UnknownChunkChallenge { .. }),
+        );
+
+        process_chunk_challenge_response(&mut state, &response).expect("response is valid");
+
+        assert_eq!(
+            state.custody_chunk_challenge_records[..],
+            [CustodyChunkChallengeRecord::default()],
+        );
+    }
+
+    #[test]
+    fn test_process_custody_key_reveal() {
+        let mut state = state();
+        let reveal = custody_key_reveal(&state, 3, 0);
+        let premature_reveal = custody_key_reveal(&state, 3, 1);
+        let wrong_period_reveal = custody_key_reveal(&state, 4, 1);
+
+        process_custody_key_reveal(&mut state, &reveal, true).expect("reveal is valid");
+
+        assert_eq!(state.validators[3].next_custody_secret_to_reveal, 1);
+        assert_error(
+            process_custody_key_reveal(&mut state.clone(), &premature_reveal, true),
+            |error: &Error| matches!(error, Error::CustodyKeyRevealTooEarly { .. }),
+        );
+        assert_error(
+            process_custody_key_reveal(&mut state, &wrong_period_reveal, true),
+            |error: &Error| matches!(error, Error::InvalidCustodyKeyReveal {}),
+        );
+    }
+
+    #[test]
+    fn test_process_custody_key_reveal_without_verifying_signatures() {
+        let mut state = state();
+        let wrong_period_reveal = custody_key_reveal(&state, 3, 1);
+
+        process_custody_key_reveal(&mut state, &wrong_period_reveal, false)
+            .expect("the signature is not checked");
+
+        assert_eq!(state.validators[3].next_custody_secret_to_reveal, 1);
+    }
+
+    #[test]
+    fn test_process_custody_key_reveal_of_exit_period() {
+        let mut state = state();
+        state.validators.make_mut()[3].exit_epoch = 9;
+        let reveal = custody_key_reveal(&state, 3, 0);
+        let exit_period_reveal = custody_key_reveal(&state, 3, 1);
+
+        process_custody_key_reveal(&mut state, &reveal, true).expect("reveal is valid");
+        process_custody_key_reveal(&mut state, &exit_period_reveal, true)
+            .expect("the exit period can be revealed after exiting");
+
+        assert_eq!(state.validators[3].next_custody_secret_to_reveal, 2);
+        assert_eq!(state.validators[3].all_custody_secrets_revealed_epoch, 10);
+    }
+
+    #[test]
+    fn test_process_early_derived_secret_reveal() {
+        let mut state = state();
+        let reveal = early_derived_secret_reveal(&state, 12);
+
+        process_early_derived_secret_reveal(&mut state, &reveal, true, &chain_spec())
+            .expect("reveal is valid");
+
+        assert!(!state.validators[1].slashed);
+        assert_eq!(state.exposed_derived_secrets[12][..], [1]);
+        assert_error(
+            process_early_derived_secret_reveal(&mut state, &reveal, true, &chain_spec()),
+            |error: &Error| matches!(error, Error::DerivedSecretAlreadyExposed { .. }),
+        );
+    }
+
+    #[test]
+    fn test_process_early_derived_secret_reveal_slashes_custody_keys() {
+        let mut state = state();
+        let reveal = early_derived_secret_reveal(&state, 18);
+
+        process_early_derived_secret_reveal(&mut state, &reveal, true, &chain_spec())
+            .expect("reveal is valid");
+
+        assert!(state.validators[1].slashed);
+        assert!(state.exposed_derived_secrets[18].is_empty());
+    }
+
+    #[test]
+    fn test_process_early_derived_secret_reveal_out_of_range() {
+        let state = state();
+
+        assert_error(
+            process_early_derived_secret_reveal(
+                &mut state.clone(),
+                &early_derived_secret_reveal(&state, 11),
+                true,
+                &chain_spec(),
+            ),
+            |error: &Error| matches!(error, Error::DerivedSecretRevealTooLate { .. }),
+        );
+        assert_error(
+            process_early_derived_secret_reveal(
+                &mut state.clone(),
+                &early_derived_secret_reveal(&state, 74),
+                true,
+                &chain_spec(),
+            ),
+            |error: &Error| matches!(error, Error::DerivedSecretRevealTooEarly { .. }),
+        );
+    }
+
+    #[test]
+    fn test_process_custody_slashing() {
+        let mut state = state();
+        let signed_custody_slashing = signed_custody_slashing(&state);
+        let custody_slashing = &signed_custody_slashing.message;
+        let custody_bit = compute_custody_bit::<MinimalConfig>(
+            custody_slashing.malefactor_secret,
+            &custody_slashing.data,
+        );
+
+        process_custody_slashing(&mut state, &signed_custody_slashing, true, &chain_spec())
+            .expect("custody slashing is valid");
+
+        let malefactor = &state.validators[custody_slashing.malefactor_index as usize];
+        let whistleblower = &state.validators[custody_slashing.whistleblower_index as usize];
+        assert_eq!(malefactor.slashed, custody_bit);
+        assert_eq!(whistleblower.slashed, !custody_bit);
+    }
+
+    #[test]
+    fn test_process_custody_slashing_with_invalid_slashings() {
+        let state = state();
+        let rejects = |signed_custody_slashing: SignedCustodySlashing<MinimalConfig>,
+                       is_expected: fn(&Error) -> bool| {
+            assert_error(
+                process_custody_slashing(
+                    &mut state.clone(),
+                    &signed_custody_slashing,
+                    true,
+                    &chain_spec(),
+                ),
+                is_expected,
+            )
+        };
+
+        let mut wrong_signature = signed_custody_slashing(&state);
+        wrong_signature.signature = wrong_signature.message.malefactor_secret;
+        rejects(wrong_signature, |error: &Error| {
+            matches!(error, Error::InvalidCustodySlashingSignature {})
+        });
+
+        let mut wrong_secret = signed_custody_slashing(&state);
+        wrong_secret.message.malefactor_secret = wrong_secret.signature;
+        wrong_secret.signature = sign(
+            &state,
+            wrong_secret.message.whistleblower_index,
+            &wrong_secret.message,
+            MinimalConfig::DOMAIN_CUSTODY_BIT_SLASHING,
+            get_current_epoch(&state),
+        )
+        .into();
+        rejects(wrong_secret, |error: &Error| {
+            matches!(error, Error::InvalidMalefactorSecret {})
+        });
+
+        let mut wrong_data = signed_custody_slashing(&state);
+        wrong_data.message.data =
+            ByteList::from_bytes(vec![1; 99]).expect("data is shorter than the limit");
+        wrong_data.signature = sign(
+            &state,
+            wrong_data.message.whistleblower_index,
+            &wrong_data.message,
+            MinimalConfig::DOMAIN_CUSTODY_BIT_SLASHING,
+            get_current_epoch(&state),
+        )
+        .into();
+        rejects(wrong_data, |error: &Error| {
+            matches!(error, Error::IncorrectCustodySlashingDataLength { .. })
+        });
+    }
+}
+
+#[cfg(test)]
+mod spec_tests {
+    use spec_test_utils::{testing, BlsSetting, Case};
+    use ssz_new::SszDecode;
+    use test_generator::test_resources;
+    use types::config::{MainnetConfig, MinimalConfig};
+
+    use super::*;
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/operations/chunk_challenge/*/*")]
+    fn mainnet_chunk_challenge(case: Case) {
+        run_case::<MainnetConfig, _>(case, "custody_chunk_challenge", process_chunk_challenge);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/operations/chunk_challenge/*/*")]
+    fn minimal_chunk_challenge(case: Case) {
+        run_case::<MinimalConfig, _>(case, "custody_chunk_challenge", process_chunk_challenge);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/operations/custody_chunk_response/*/*"
+    )]
+    fn mainnet_custody_chunk_response(case: Case) {
+        run_case::<MainnetConfig, _>(case, "custody_response", |state, response, _| {
+            process_chunk_challenge_response(state, response)
+        });
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/operations/custody_chunk_response/*/*"
+    )]
+    fn minimal_custody_chunk_response(case: Case) {
+        run_case::<MinimalConfig, _>(case, "custody_response", |state, response, _| {
+            process_chunk_challenge_response(state, response)
+        });
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/operations/custody_key_reveal/*/*")]
+    fn mainnet_custody_key_reveal(case: Case) {
+        run_case::<MainnetConfig, _>(case, "custody_key_reveal", process_custody_key_reveal);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/operations/custody_key_reveal/*/*")]
+    fn minimal_custody_key_reveal(case: Case) {
+        run_case::<MinimalConfig, _>(case, "custody_key_reveal", process_custody_key_reveal);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/operations/early_derived_secret_reveal/*/*"
+    )]
+    fn mainnet_early_derived_secret_reveal(case: Case) {
+        run_case::<MainnetConfig, _>(
+            case,
+            "early_derived_secret_reveal",
+            |state, reveal, verify| {
+                process_early_derived_secret_reveal(
+                    state,
+                    reveal,
+                    verify,
+                    &ChainSpec::from_config::<MainnetConfig>(),
+                )
+            },
+        );
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/operations/early_derived_secret_reveal/*/*"
+    )]
+    fn minimal_early_derived_secret_reveal(case: Case) {
+        run_case::<MinimalConfig, _>(
+            case,
+            "early_derived_secret_reveal",
+            |state, reveal, verify| {
+                process_early_derived_secret_reveal(
+                    state,
+                    reveal,
+                    verify,
+                    &ChainSpec::from_config::<MinimalConfig>(),
+                )
+            },
+        );
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/operations/custody_slashing/*/*")]
+    fn mainnet_custody_slashing(case: Case) {
+        run_case::<MainnetConfig, _>(
+            case,
+            "custody_slashing",
+            |state, custody_slashing, verify| {
+                process_custody_slashing(
+                    state,
+                    custody_slashing,
+                    verify,
+                    &ChainSpec::from_config::<MainnetConfig>(),
+                )
+            },
+        );
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/operations/custody_slashing/*/*")]
+    fn minimal_custody_slashing(case: Case) {
+        run_case::<MinimalConfig, _>(
+            case,
+            "custody_slashing",
+            |state, custody_slashing, verify| {
+                process_custody_slashing(
+                    state,
+                    custody_slashing,
+                    verify,
+                    &ChainSpec::from_config::<MinimalConfig>(),
+                )
+            },
+        );
+    }
+
+    fn run_case<C: Config, O: SszDecode>(
+        case: Case,
+        operation_name: &str,
+        process: fn(&mut BeaconState<C>, &O, bool) -> Result<()>,
+    ) {
+        let verify_signatures = case.meta().bls_setting != BlsSetting::Ignored;
+        let operation = case.ssz(operation_name);
+
+        testing::run_case(case, |state| process(state, &operation, verify_signatures));
+    }
+}
@@ -1,9 +1,11 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use ethereum_types::H256;
 use hex_literal::hex;
 use sha2::{Digest as _, Sha256};
 
 #[rustfmt::skip]
-pub const ZERO_HASHES: [H256; 32] = [
+pub const ZERO_HASHES: [H256; 64] = [
     H256(hex!("0000000000000000000000000000000000000000000000000000000000000000")),
     H256(hex!("f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b")),
     H256(hex!("db56114e00fdd4c1f85c892bf35ac9a89289aaecb1ebd0a96cde606a748b5d71")),
@@ -36,6 +38,38 @@ pub const ZERO_HASHES: [H256; 32] = [
     H256(hex!("8869ff2c22b28cc10510d9853292803328be4fb0e80495e8bb8d271f5b889636")),
     H256(hex!("b5fe28e79f1b850f8658246ce9b6a1e7b49fc06db7143e8fe0b4f2b0c5523a5c")),
     H256(hex!("985e929f70af28d0bdd1a90a808f977f597c7c778c489e98d3bd8910d31ac0f7")),
+    H256(hex!("c6f67e02e6e4e1bdefb994c6098953f34636ba2b6ca20a4721d2b26a886722ff")),
+    H256(hex!("1c9a7e5ff1cf48b4ad1582d3f4e4a1004f3b20d8c5a2b71387a4254ad933ebc5")),
+    H256(hex!("2f075ae229646b6f6aed19a5e372cf295081401eb893ff599b3f9acc0c0d3e7d")),
+    H256(hex!("328921deb59612076801e8cd61592107b5c67c79b846595cc6320c395b46362c")),
+    H256(hex!("bfb909fdb236ad2411b4e4883810a074b840464689986c3f8a8091827e17c327")),
+    H256(hex!("55d8fb3687ba3ba49f342c77f5a1f89bec83d811446e1a467139213d640b6a74")),
+    H256(hex!("f7210d4f8e7e1039790e7bf4efa207555a10a6db1dd4b95da313aaa88b88fe76")),
+    H256(hex!("ad21b516cbc645ffe34ab5de1c8aef8cd4e7f8d2b51e8e1456adc7563cda206f")),
+    H256(hex!("6bfe8d2bcc4237b74a5047058ef455339ecd7360cb63bfbb8ee5448e6430ba04")),
+    H256(hex!("a7f23ce9181740dc220c814782654fee6aceb9f1ec9222c4e2467d0ab1680837")),
+    H256(hex!("aef9476c89590a2c8cc9b3b74f4967c757c49d9866a44bacf21fa2ed675ddfa2")),
+    H256(hex!("9a42bcad82f6a9e41284d808ead319f29f3b08209d680f0e2ce71510d071e205")),
+    H256(hex!("d1a66d354a67b9cf179571d8e5f97792716e8dd4ec44196839a3f7c6b74f8bac")),
+    H256(hex!("fafa3025f2f89509c2c71c74fba0cd92858ef49b0780fb5479746c8a9bfcb346")),
+    H256(hex!("3334a7c1e7f6705aa6011a6a949645016db4acde0ca9abd66dc79d8266423056")),
+    H256(hex!("0796fd75664faef744ee4e52d7271e2bbb769f91ed6f9b74d8b694f56606852c")),
+    H256(hex!("7ba3ae4a417fe8545b142bc89f4adcd7ae13941cbab7750b83e9f0a66d16be64")),
+    H256(hex!("788fafcc4aa520399adbaed195f8b12c4eb31ec10168e50aabc659a6aea516dc")),
+    H256(hex!("e833d7a67160e68bf4c9044a53077df2727ad00cf36f4949c7b681a912140cbb")),
+    H256(hex!("309eabf095dc6714f9f4d864bba5affae0b35ae2f5e3565bcc3a47b212767701")),
+    H256(hex!("226a8ebefa288665a644a50273335efbb610510f241b5b720c8a368d59a69a5d")),
+    H256(hex!("41abfd995425827625938131af0c4f33fe0bd4688c222c21fa9da8e89caa03f8")),
+    H256(hex!("442c642ef50fa1a667a6e6d105c77c5cc3fec8d7aa2570cf1a3077b503c38069")),
+    H256(hex!("a0a08dfc9b42d96c2de19b6d127b8ae136ddcf3e5ad0dce422c45a56f61f6a74")),
+    H256(hex!("7d348382af096dbe0bf086c7bb39b2a2c0bc36b621ab0c738e9885d731d81740")),
+    H256(hex!("3ab134751d191269026c86994eaa8b43a83b4ad1f6d0e77381c4e2974afbc8f6")),
+    H256(hex!("9a7452611db2d23eae26f9bdbb88958ef44c64d0fe987be9f726adf938f50f6c")),
+    H256(hex!("725c7f816037bfe452cd1e7ba35ac47edcb49a9a2b27aeca70dce483cb7ded1f")),
+    H256(hex!("2cea1af51fb28b62887c39998ac9fef4dfdeda1f07e071ba558a173afd06cbc3")),
+    H256(hex!("ff1d59f98b6c551d95089357057d5c8be26402279e9df0b1df1a10b72bf3927f")),
+    H256(hex!("2f8a181f7c99dd215a7529bfe296a9603a1446737186d21aeb8bc7ae59e1fd21")),
+    H256(hex!("ecc502c9b1145f3950cb7d3e3842446f81a4f0df1df537cee139ef64ea984bd9")),
 ];
 
 #[must_use]
@@ -59,6 +93,81 @@ pub fn hash_from_u64(number: u64) -> H256 {
     h256
 }
 
+// Generalized indices of the nodes needed to compute the root from the nodes at `indices`,
+// in the order used in multiproofs.
+#[must_use]
+pub fn get_helper_indices(indices: &[u64]) -> Vec<u64> {
+    let mut branch_indices = BTreeSet::new();
+    let mut path_indices = BTreeSet::new();
+
+    for index in indices {
+        let mut index = *index;
+
+        while index > 1 {
+            branch_indices.insert(index ^ 1);
+            path_indices.insert(index);
+            index /= 2;
+        }
+    }
+
+    let mut helper_indices = branch_indices
+        .difference(&path_indices)
+        .copied()
+        .collect::<Vec<_>>();
+
+    helper_indices.reverse();
+    helper_indices
+}
+
+// Returns `None` if `leaves` and `proof` are not enough to compute the root.
+#[must_use]
+pub fn calculate_multi_merkle_root(
+    leaves: &[H256],
+    proof: &[H256],
+    indices: &[u64],
+) -> Option<H256> {
+    let helper_indices = get_helper_indices(indices);
+
+    if leaves.len() != indices.len() || proof.len() != helper_indices.len() {
+        return None;
+    }
+
+    let mut nodes = indices
+        .iter()
+        .copied()
+        .zip(leaves.iter().copied())
+        .chain(helper_indices.into_iter().zip(proof.iter().copied()))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut keys = nodes.keys().rev().copied().collect::<Vec<_>>();
+    let mut position = 0;
+
+    while let Some(key) = keys.get(position).copied() {
+        let parent = key / 2;
+
+        if key > 1 && nodes.contains_key(&(key ^ 1)) && !nodes.contains_key(&parent) {
+            let left = nodes[&(key & !1)];
+            let right = nodes[&(key | 1)];
+            nodes.insert(parent, concatenate_and_hash(left, right));
+            keys.push(parent);
+        }
+
+        position += 1;
+    }
+
+    nodes.get(&1).copied()
+}
+
+#[must_use]
+pub fn verify_merkle_multiproof(
+    leaves: &[H256],
+    proof: &[H256],
+    indices: &[u64],
+    root: H256,
+) -> bool {
+    calculate_multi_merkle_root(leaves, proof, indices) == Some(root)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,4 +182,66 @@ mod tests {
             .map(|lower| concatenate_and_hash(lower, lower))
             .eq(higher_hashes.iter().copied()));
     }
+
+    // Leaves of a tree of depth 3 have generalized indices 8 through 15.
+    fn tree() -> (Vec<H256>, H256) {
+        let leaves = (0..8).map(hash_from_u64).collect::<Vec<_>>();
+        let layer_2 = leaves
+            .chunks(2)
+            .map(|pair| concatenate_and_hash(pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        let layer_1 = layer_2
+            .chunks(2)
+            .map(|pair| concatenate_and_hash(pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        let root = concatenate_and_hash(layer_1[0], layer_1[1]);
+        (leaves, root)
+    }
+
+    #[test]
+    fn helper_indices_exclude_nodes_that_can_be_computed() {
+        assert_eq!(get_helper_indices(&[8, 9, 14]), [15, 6, 5]);
+        assert_eq!(get_helper_indices(&[2]), [3]);
+        assert_eq!(get_helper_indices(&[2, 3]), []);
+    }
+
+    #[test]
+    fn multiproof_is_valid_only_with_the_right_nodes() {
+        let (leaves, root) = tree();
+        let indices = [8, 9, 14];
+        let proven_leaves = [leaves[0], leaves[1], leaves[6]];
+        let mut proof = [
+            leaves[7],
+            concatenate_and_hash(leaves[4], leaves[5]),
+            concatenate_and_hash(leaves[2], leaves[3]),
+        ];
+
+        assert!(verify_merkle_multiproof(
+            &proven_leaves,
+            &proof,
+            &indices,
+            root,
+        ));
+        assert!(!verify_merkle_multiproof(
+            &[leaves[0], leaves[1], leaves[7]],
+            &proof,
+            &indices,
+            root,
+        ));
+        assert!(!verify_merkle_multiproof(
+            &proven_leaves[..2],
+            &proof,
+            &indices,
+            root,
+        ));
+
+        proof[2] = H256::zero();
+
+        assert!(!verify_merkle_multiproof(
+            &proven_leaves,
+            &proof,
+            &indices,
+            root,
+        ));
+    }
 }
@@ -0,0 +1,34 @@
+use bls::SignatureBytes;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use ethereum_types::U256;
+use helper_functions::custody::{compute_custody_bit, legendre_bit, CUSTODY_PRIME};
+use ssz_new::types::ByteList;
+use typenum::Unsigned as _;
+use types::config::{Config, MainnetConfig};
+
+fn custody_bit(criterion: &mut Criterion) {
+    let size = <MainnetConfig as Config>::MaxShardBlockSize::USIZE;
+    let bytes = (0..size).map(|index| (index * 7) as u8).collect();
+    let data = ByteList::<<MainnetConfig as Config>::MaxShardBlockSize>::from_bytes(bytes)
+        .expect("data is exactly as long as the limit");
+    let key = SignatureBytes([0x5a; 96]);
+
+    let mut group = criterion.benchmark_group("custody_bit");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(size as u64));
+    group.bench_function("full shard block", |bencher| {
+        bencher.iter(|| compute_custody_bit::<MainnetConfig>(black_box(key), black_box(&data)))
+    });
+    group.finish();
+}
+
+fn legendre(criterion: &mut Criterion) {
+    let value = CUSTODY_PRIME / U256::from(3);
+
+    criterion.bench_function("legendre_bit", |bencher| {
+        bencher.iter(|| legendre_bit(black_box(value), black_box(CUSTODY_PRIME)))
+    });
+}
+
+criterion_group!(benches, custody_bit, legendre);
+criterion_main!(benches);
@@ -3,16 +3,18 @@ use core::{
     convert::{TryFrom as _, TryInto as _},
     fmt::Debug,
 };
+use std::collections::HashSet;
 
 use anyhow::{ensure, Result};
 use ethereum_types::H256;
 use itertools::Itertools as _;
 use ssz_new::types::BitList;
-use stubs::beacon_chain;
 use typenum::Unsigned as _;
 use types::{
     beacon_state::BeaconState,
+    chain_spec::ChainSpec,
     config::Config,
+    consts::BASE_REWARDS_PER_EPOCH,
     containers::{Attestation, AttestationData, IndexedAttestation},
     primitives::{CommitteeIndex, DomainType, Epoch, Gwei, Shard, Slot, ValidatorIndex},
 };
@@ -72,11 +74,33 @@ pub fn get_active_validator_indices<C: Config>(
     Ok(indices)
 }
 
-pub fn get_validator_churn_limit<C: Config>(state: &BeaconState<C>) -> Result<u64> {
+pub fn get_online_validator_indices<C: Config>(
+    state: &BeaconState<C>,
+) -> Result<HashSet<ValidatorIndex>> {
+    let mut online_indices = HashSet::new();
+
+    for index in get_active_validator_indices(state, get_current_epoch(state))? {
+        let countdown = state
+            .online_countdown
+            .get(usize::try_from(index)?)
+            .ok_or(Error::IndexOutOfBounds)?;
+
+        if *countdown != 0 {
+            online_indices.insert(index);
+        }
+    }
+
+    Ok(online_indices)
+}
+
+pub fn get_validator_churn_limit<C: Config>(
+    state: &BeaconState<C>,
+    chain_spec: &ChainSpec,
+) -> Result<u64> {
     let active_validator_count = cache::active_validator_count(state, get_current_epoch(state))?;
     Ok(max(
-        C::MIN_PER_EPOCH_CHURN_LIMIT,
-        active_validator_count / C::CHURN_LIMIT_QUOTIENT,
+        chain_spec.min_per_epoch_churn_limit,
+        active_validator_count / chain_spec.churn_limit_quotient,
     ))
 }
 
@@ -105,16 +129,8 @@ pub fn get_committee_count_per_slot<C: Config>(
     let active_count = cache::active_validator_count(state, epoch)?
         / C::SlotsPerEpoch::U64
         / C::TARGET_COMMITTEE_SIZE;
-    let active_shard_count = beacon_chain::get_active_shard_count::<C>(state);
-    let mut count = if active_shard_count < active_count {
-        beacon_chain::get_active_shard_count::<C>(state)
-    } else {
-        active_count
-    };
 
-    count = if 1 > count { 1 } else { count };
-
-    Ok(count)
+    Ok(get_active_shard_count(state).min(active_count).max(1))
 }
 
 pub fn get_beacon_proposer_index<C: Config>(state: &BeaconState<C>) -> Result<ValidatorIndex> {
@@ -152,7 +168,7 @@ pub fn get_beacon_committee<C: Config>(
 
     let indices: Vec<ValidatorIndex> = get_active_validator_indices(state, epoch)?.collect();
     let seed = get_seed(state, epoch, C::DOMAIN_BEACON_ATTESTER)?;
-    let index = (slot as u64 % C::SLOTS_PER_EPOCH) * committees_per_slot + index;
+    let index = (slot % C::SLOTS_PER_EPOCH) * committees_per_slot + index;
     let count = committees_per_slot * C::SLOTS_PER_EPOCH;
 
     let committee = misc::compute_committee::<C>(indices, seed, index, count)?;
@@ -172,6 +188,18 @@ pub fn get_total_balance<C: Config>(
     Ok(max(C::EFFECTIVE_BALANCE_INCREMENT, balance))
 }
 
+pub fn get_base_reward<C: Config>(state: &BeaconState<C>, index: ValidatorIndex) -> Result<Gwei> {
+    let total_balance = cache::total_active_balance(state)?;
+    let validator = usize::try_from(index)
+        .ok()
+        .and_then(|index| state.validators.get(index))
+        .ok_or(Error::IndexOutOfBounds)?;
+
+    Ok(validator.effective_balance * C::BASE_REWARD_FACTOR
+        / misc::integer_squareroot(total_balance)
+        / BASE_REWARDS_PER_EPOCH)
+}
+
 #[must_use]
 pub fn get_domain<C: Config>(
     state: &BeaconState<C>,
@@ -188,11 +216,7 @@ pub fn get_domain<C: Config>(
     } else {
         state.fork.current_version
     };
-    misc::compute_domain::<C>(
-        domain_type,
-        Some(&fork_version),
-        Some(state.genesis_validators_root),
-    )
+    misc::compute_domain_with_fork_version(domain_type, fork_version, state.genesis_validators_root)
 }
 
 pub fn get_indexed_attestation<C: Config>(
@@ -239,36 +263,66 @@ pub fn get_committee_count_delta<C: Config>(
     state: &BeaconState<C>,
     start_slot: Slot,
     stop_slot: Slot,
-) -> u64 {
+) -> Result<u64> {
     let mut sum: u64 = 0;
     for i in start_slot..stop_slot {
-        sum += beacon_chain::get_committee_count_per_slot::<C>(
-            state,
-            misc::compute_epoch_at_slot::<C>(i),
-        );
+        sum += get_committee_count_per_slot::<C>(state, misc::compute_epoch_at_slot::<C>(i))?;
     }
-    sum
+    Ok(sum)
 }
 
-pub fn get_start_shard<C: Config>(state: &BeaconState<C>, slot: Slot) -> Shard {
+pub fn get_start_shard<C: Config>(state: &BeaconState<C>, slot: Slot) -> Result<Shard> {
     let current_epoch_start_slot =
         misc::compute_start_slot_at_epoch::<C>(get_current_epoch::<C>(state));
-    let active_shard_count = beacon_chain::get_active_shard_count::<C>(state);
-    match slot {
+    let active_shard_count = get_active_shard_count::<C>(state);
+    let start_shard = match slot {
         slot if slot == current_epoch_start_slot => state.current_epoch_start_shard,
         slot if slot > current_epoch_start_slot => {
-            let shard_delta = get_committee_count_delta::<C>(state, current_epoch_start_slot, slot);
+            // Current epoch or the next epoch lookahead
+            let shard_delta =
+                get_committee_count_delta::<C>(state, current_epoch_start_slot, slot)?;
             (state.current_epoch_start_shard + shard_delta) % active_shard_count
         }
         _ => {
-            let shard_delta = get_committee_count_delta::<C>(state, current_epoch_start_slot, slot);
+            // Previous epoch
+            let shard_delta =
+                get_committee_count_delta::<C>(state, slot, current_epoch_start_slot)?;
             let max_committees_per_slot = active_shard_count;
             let max_committees_in_span =
                 max_committees_per_slot * (current_epoch_start_slot - slot);
+            // Adding `max_committees_in_span` keeps the result positive.
             (state.current_epoch_start_shard + max_committees_in_span - shard_delta)
                 % active_shard_count
         }
-    }
+    };
+    Ok(start_shard)
+}
+
+pub fn get_latest_slot_for_shard<C: Config>(state: &BeaconState<C>, shard: Shard) -> Result<Slot> {
+    state
+        .shard_states
+        .get(usize::try_from(shard)?)
+        .map(|shard_state| shard_state.slot)
+        .ok_or_else(|| Error::IndexOutOfBounds.into())
+}
+
+pub fn get_offset_slots<C: Config>(state: &BeaconState<C>, shard: Shard) -> Result<Vec<Slot>> {
+    let latest_slot = get_latest_slot_for_shard(state, shard)?;
+    Ok(misc::compute_offset_slots::<C>(latest_slot, state.slot))
+}
+
+#[must_use]
+pub fn get_active_shard_count<C: Config>(_state: &BeaconState<C>) -> u64 {
+    C::INITIAL_ACTIVE_SHARDS
+}
+
+pub fn compute_shard_from_committee_index<C: Config>(
+    state: &BeaconState<C>,
+    index: CommitteeIndex,
+    slot: Slot,
+) -> Result<Shard> {
+    let active_shards = get_active_shard_count(state);
+    Ok((index + get_start_shard(state, slot)?) % active_shards)
 }
 
 pub fn get_shard_committee<C: Config>(
@@ -276,45 +330,74 @@ pub fn get_shard_committee<C: Config>(
     epoch: Epoch,
     shard: Shard,
 ) -> Result<Vec<ValidatorIndex>> {
-    let source_epoch =
-        beacon_chain::compute_committee_source_epoch::<C>(epoch, C::SHARD_COMMITTEE_PERIOD);
-
-    let active_validator_indices = get_active_validator_indices::<C>(state, epoch);
-    let active_validator_indices = match active_validator_indices {
-        Ok(active_validator_indices) => {
-            let mut active_validator_indices_vec = Vec::new();
-            for index in active_validator_indices {
-                active_validator_indices_vec.push(index);
-            }
-            active_validator_indices_vec
-        }
-        Err(_error) => {
-            let active_validator_indices_vec = Vec::new();
-            active_validator_indices_vec
-        }
-    };
+    let source_epoch = misc::compute_committee_source_epoch(epoch, C::SHARD_COMMITTEE_PERIOD);
 
-    let seed = get_seed(state, source_epoch, 2164260864); // const DOMAIN_SHARD_COMMITTEE is missing so 2164260864 is added
-    let seed = match seed {
-        Ok(seed) => seed,
-        Err(_error) => panic!(), // unclear what to return, needs to be fixed
-    };
+    let active_validator_indices: Vec<_> =
+        get_active_validator_indices(state, source_epoch)?.collect();
 
-    return beacon_chain::compute_committee::<C>(
+    let seed = get_seed(state, source_epoch, C::DOMAIN_SHARD_COMMITTEE)?;
+
+    misc::compute_committee::<C>(
         active_validator_indices,
-        &seed,
+        seed,
         shard,
-        beacon_chain::get_active_shard_count::<C>(state),
-    );
+        get_active_shard_count(state),
+    )
+}
+
+pub fn get_light_client_committee<C: Config>(
+    state: &BeaconState<C>,
+    epoch: Epoch,
+) -> Result<Vec<ValidatorIndex>> {
+    let source_epoch =
+        misc::compute_committee_source_epoch(epoch, C::LIGHT_CLIENT_COMMITTEE_PERIOD);
+
+    let active_validator_indices: Vec<_> =
+        get_active_validator_indices(state, source_epoch)?.collect();
+
+    let seed = get_seed(state, source_epoch, C::DOMAIN_LIGHT_CLIENT)?;
+
+    let mut committee = misc::compute_committee::<C>(
+        active_validator_indices,
+        seed,
+        0,
+        get_active_shard_count(state),
+    )?;
+    committee.truncate(C::LightClientCommitteeSize::USIZE);
+
+    Ok(committee)
+}
+
+pub fn get_shard_proposer_index<C: Config>(
+    state: &BeaconState<C>,
+    slot: Slot,
+    shard: Shard,
+) -> Result<ValidatorIndex> {
+    let epoch = misc::compute_epoch_at_slot::<C>(slot);
+    let committee = get_shard_committee(state, epoch, shard)?;
+    let seed = get_seed(state, epoch, C::DOMAIN_SHARD_PROPOSAL)?;
+
+    let mut seed_with_slot = [0; 40];
+    seed_with_slot[..32].copy_from_slice(seed.as_bytes());
+    seed_with_slot[32..].copy_from_slice(&slot.to_le_bytes());
+    let seed = hashing::hash(&seed_with_slot[..]);
+
+    // Shard proposers pay gas fees out of their balance, so sampling is weighted by effective
+    // balance the same way beacon proposer selection is.
+    misc::compute_proposer_index(state, &committee, seed)
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
+    use spec_test_utils::testing::validators;
     use ssz_new::types::FixedVector;
     use typenum::U64;
-    use types::{config::MinimalConfig, containers::Validator};
+    use types::{
+        config::MinimalConfig,
+        containers::{ShardState, Validator},
+    };
 
     use super::*;
 
@@ -354,7 +437,7 @@ mod tests {
             base.push(H256::from([x; 32]));
         }
         let roots: FixedVector<_, U64> = FixedVector::from(base);
-        state.block_roots = roots;
+        state.block_roots = Arc::new(roots);
         let result = get_block_root::<MinimalConfig>(&state, 1);
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.expect("Expected success"), H256::from([8; 32]));
@@ -366,7 +449,7 @@ mod tests {
         state.slot = 2;
         let base: Vec<H256> = vec![H256::from([0; 32]), H256::from([1; 32])];
         let roots: FixedVector<_, U64> = FixedVector::from(base);
-        state.block_roots = roots;
+        state.block_roots = Arc::new(roots);
         let result = get_block_root_at_slot::<MinimalConfig>(&state, 1);
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.expect("Expected success"), H256::from([1; 32]));
@@ -377,22 +460,110 @@ mod tests {
         let mut state = BeaconState::<MinimalConfig>::default();
         let base: Vec<H256> = vec![H256::from([0; 32])];
         let mixes: FixedVector<_, U64> = FixedVector::from(base);
-        state.randao_mixes = mixes;
+        state.randao_mixes = Arc::new(mixes);
         let result = get_randao_mix::<MinimalConfig>(&state, 0);
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.expect("Expected success"), H256::from([0; 32]));
     }
 
+    #[test]
+    fn test_get_latest_slot_for_shard() {
+        let shard_state = |slot| ShardState {
+            slot,
+            ..ShardState::default()
+        };
+        let state = BeaconState::<MinimalConfig> {
+            shard_states: Arc::new(vec![shard_state(3), shard_state(7)].into()),
+            ..BeaconState::default()
+        };
+
+        assert_eq!(
+            get_latest_slot_for_shard(&state, 1).expect("shard 1 exists"),
+            7
+        );
+        assert!(get_latest_slot_for_shard(&state, 2).is_err());
+    }
+
+    #[test]
+    fn test_get_offset_slots() {
+        let state = BeaconState::<MinimalConfig> {
+            slot: 14,
+            shard_states: Arc::new(
+                vec![ShardState {
+                    slot: 10,
+                    ..ShardState::default()
+                }]
+                .into(),
+            ),
+            ..BeaconState::default()
+        };
+
+        assert_eq!(
+            get_offset_slots(&state, 0).expect("shard 0 exists"),
+            vec![11, 12, 13],
+        );
+        assert!(get_offset_slots(&state, 1).is_err());
+    }
+
+    #[test]
+    fn test_get_online_validator_indices() {
+        let mut validators = validators::<MinimalConfig>(4);
+        validators[3].exit_epoch = 0;
+        let state = BeaconState::<MinimalConfig> {
+            validators: Arc::new(validators.into()),
+            online_countdown: Arc::new(vec![1, 0, 8, 8].into()),
+            ..BeaconState::default()
+        };
+
+        let online_indices =
+            get_online_validator_indices(&state).expect("every validator has a countdown");
+
+        assert_eq!(online_indices, [0, 2].iter().copied().collect());
+    }
+
+    #[test]
+    fn test_get_online_validator_indices_without_countdowns() {
+        let state = BeaconState::<MinimalConfig> {
+            validators: Arc::new(validators::<MinimalConfig>(4).into()),
+            online_countdown: Arc::new(vec![8, 8].into()),
+            ..BeaconState::default()
+        };
+
+        assert!(get_online_validator_indices(&state).is_err());
+    }
+
     #[test]
     fn test_get_minimal_validator_churn_limit() {
         let state = BeaconState::<MinimalConfig>::default();
-        let result = get_validator_churn_limit::<MinimalConfig>(&state);
+        let result = get_validator_churn_limit::<MinimalConfig>(
+            &state,
+            &ChainSpec::from_config::<MinimalConfig>(),
+        );
         assert_eq!(
             result.expect("Expected min_per_epoch_churn_limit"),
             MinimalConfig::MIN_PER_EPOCH_CHURN_LIMIT
         );
     }
 
+    #[test]
+    fn test_get_validator_churn_limit_from_chain_spec() {
+        let state = BeaconState::<MinimalConfig> {
+            slot: 5,
+            validators: Arc::new(validators::<MinimalConfig>(64).into()),
+            ..BeaconState::default()
+        };
+        let chain_spec = ChainSpec {
+            min_per_epoch_churn_limit: 2,
+            churn_limit_quotient: 16,
+            ..ChainSpec::from_config::<MinimalConfig>()
+        };
+
+        assert_eq!(
+            get_validator_churn_limit(&state, &chain_spec).expect("state has active validators"),
+            4,
+        );
+    }
+
     #[test]
     fn test_get_total_minimal_balance() {
         let mut state = BeaconState::<MinimalConfig>::default();
@@ -417,6 +588,19 @@ mod tests {
         assert_eq!(result.expect("Expected success"), 27_000_000_000);
     }
 
+    #[test]
+    fn test_get_base_reward() {
+        let mut state = BeaconState::<MinimalConfig>::default();
+        state.validators = Arc::new(validators::<MinimalConfig>(16).into());
+
+        // 32 ETH * 64 / isqrt(16 * 32 ETH) / 4
+        assert_eq!(
+            get_base_reward(&state, 0).expect("validator 0 exists"),
+            715_542,
+        );
+        assert!(get_base_reward(&state, 16).is_err());
+    }
+
     #[test]
     fn test_get_active_validators() {
         let mut validator1 = Validator::default();
@@ -435,4 +619,149 @@ mod tests {
             .expect("number of validators should fit in usize");
         assert!(result.eq([0, 2].iter().copied()));
     }
+
+    // 32 validators in the minimal preset make 32 / 8 / 4 = 1 committee per slot.
+    fn state_with_one_committee_per_slot() -> BeaconState<MinimalConfig> {
+        BeaconState {
+            slot: 20,
+            current_epoch_start_shard: 1,
+            validators: Arc::new(validators::<MinimalConfig>(32).into()),
+            ..BeaconState::default()
+        }
+    }
+
+    #[test]
+    fn test_get_active_shard_count() {
+        let state = BeaconState::<MinimalConfig>::default();
+        assert_eq!(get_active_shard_count(&state), 2);
+    }
+
+    #[test]
+    fn test_get_committee_count_per_slot() {
+        let committee_count = |validator_count| {
+            // Caches are keyed by slot rather than by validators.
+            let state = BeaconState::<MinimalConfig> {
+                slot: validator_count as Slot,
+                validators: Arc::new(validators::<MinimalConfig>(validator_count).into()),
+                ..BeaconState::default()
+            };
+            get_committee_count_per_slot(&state, 0).expect("validator count fits in u64")
+        };
+
+        // Never less than 1.
+        assert_eq!(committee_count(16), 1);
+        assert_eq!(committee_count(64), 2);
+        // Never more than the 2 active shards.
+        assert_eq!(committee_count(256), 2);
+    }
+
+    #[test]
+    fn test_get_start_shard() {
+        let state = state_with_one_committee_per_slot();
+        let start_shard = |slot| get_start_shard(&state, slot).expect("slot is valid");
+
+        // Current epoch start slot.
+        assert_eq!(start_shard(16), 1);
+        // (1 + 1 * 1) % 2
+        assert_eq!(start_shard(17), 0);
+        // (1 + 2 * 1) % 2
+        assert_eq!(start_shard(18), 1);
+        // Next epoch: (1 + 9 * 1) % 2
+        assert_eq!(start_shard(25), 0);
+        // Previous epoch: (1 + 1 * 2 - 1 * 1) % 2
+        assert_eq!(start_shard(15), 0);
+        // Previous epoch: (1 + 2 * 2 - 2 * 1) % 2
+        assert_eq!(start_shard(14), 1);
+    }
+
+    #[test]
+    fn test_compute_shard_from_committee_index() {
+        let state = state_with_one_committee_per_slot();
+        let shard = |index, slot| {
+            compute_shard_from_committee_index(&state, index, slot).expect("slot is valid")
+        };
+
+        assert_eq!(shard(0, 16), 1);
+        assert_eq!(shard(1, 16), 0);
+        assert_eq!(shard(0, 17), 0);
+        assert_eq!(shard(1, 17), 1);
+        assert_eq!(shard(0, 15), 0);
+    }
+
+    #[test]
+    fn test_shard_committees_partition_active_validators() {
+        let state = state_with_one_committee_per_slot();
+
+        let mut members = (0..get_active_shard_count(&state))
+            .flat_map(|shard| {
+                let committee = get_shard_committee(&state, get_current_epoch(&state), shard)
+                    .expect("shard committee should be computable");
+                assert_eq!(committee.len(), 16);
+                committee
+            })
+            .collect::<Vec<_>>();
+        members.sort_unstable();
+
+        assert!(members.into_iter().eq(0..32));
+    }
+
+    #[test]
+    fn test_shard_committee_is_fixed_within_lookahead_period() {
+        let state = state_with_one_committee_per_slot();
+        let committee = |epoch| {
+            get_shard_committee(&state, epoch, 1).expect("shard committee should be computable")
+        };
+
+        // Epochs 0 through 127 all use source epoch 0.
+        assert_eq!(committee(2), committee(127));
+        assert_ne!(committee(127), committee(128));
+    }
+
+    #[test]
+    fn test_get_light_client_committee() {
+        let state = state_with_one_committee_per_slot();
+
+        let mut committee = get_light_client_committee(&state, get_current_epoch(&state))
+            .expect("light client committee should be computable");
+        committee.sort_unstable();
+        committee.dedup();
+
+        // 32 validators split between 2 active shards.
+        assert_eq!(committee.len(), 16);
+    }
+
+    #[test]
+    fn test_shard_proposer_is_member_of_shard_committee() {
+        let state = BeaconState::<MinimalConfig> {
+            slot: 17,
+            validators: Arc::new(validators::<MinimalConfig>(64).into()),
+            ..BeaconState::default()
+        };
+
+        for shard in 0..get_active_shard_count(&state) {
+            let proposer = get_shard_proposer_index(&state, state.slot, shard)
+                .expect("shard committee should not be empty");
+            let committee = get_shard_committee(&state, get_current_epoch(&state), shard)
+                .expect("shard committee should be computable");
+            assert!(committee.contains(&proposer));
+        }
+    }
+
+    #[test]
+    fn test_cached_shard_proposer_matches_uncached() {
+        let state = BeaconState::<MinimalConfig> {
+            slot: 9,
+            validators: Arc::new(validators::<MinimalConfig>(64).into()),
+            ..BeaconState::default()
+        };
+
+        for slot in 8..16 {
+            assert_eq!(
+                cache::shard_proposer_index(&state, slot, 1)
+                    .expect("shard committee should not be empty"),
+                get_shard_proposer_index(&state, slot, 1)
+                    .expect("shard committee should not be empty"),
+            );
+        }
+    }
 }
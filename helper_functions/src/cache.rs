@@ -4,14 +4,14 @@ use std::sync::{Arc, Mutex};
 use anyhow::{ensure, Result};
 use cached::{once_cell::sync::Lazy, SizedCache};
 use owning_ref::ArcRef;
-use tree_hash::TreeHash;
-use tree_hash_derive::TreeHash;
+use ssz_new::SszHash;
+use ssz_new_derive::SszHash;
 use typenum::Unsigned as _;
 use types::{
     beacon_state::BeaconState,
     config::Config,
     containers::{BeaconBlockHeader, Fork},
-    primitives::{CommitteeIndex, Epoch, Gwei, Slot, ValidatorIndex, Version, H256},
+    primitives::{CommitteeIndex, Epoch, Gwei, Shard, Slot, ValidatorIndex, Version, H256},
 };
 
 use crate::{
@@ -20,10 +20,6 @@ use crate::{
 
 // Hashing a key of this size may be slow. We could instead store `std::sync::Weak`s pointing to
 // `BeaconState`s. However, that would require a rewrite of `beacon_fork_choice::Store`.
-//
-// Note that this does not contain the `genesis_time` and `genesis_validator_root` fields.
-// Their absence could cause collisions between disjoint chains with the same preset,
-// but there is currently no way for that to happen.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 struct BeaconStateKey {
     // This is needed to prevent collisions when a `BeaconState` is processed through empty slots.
@@ -32,11 +28,17 @@ struct BeaconStateKey {
     // This is needed to prevent collisions between chains with different presets as well as hard
     // forks of the same chain.
     //
-    // We rely on the fact that all presets have different values of `genesis_fork_version`.
-    // If that were not the case then there would have to be another field that stores a `TypeId`
-    // corresponding to the `Config` parameter.
+    // We rely on the fact that states of different presets differ in either this or
+    // `genesis_validators_root`. If that were not the case then there would have to be another
+    // field that stores a `TypeId` corresponding to the `Config` parameter.
     current_version: Version,
 
+    // This is needed to prevent collisions between disjoint chains with the same preset.
+    // `ChainSpec` lets them share `genesis_fork_version`, so `current_version` is not enough.
+    // `genesis_time` is left out. Chains with the same genesis validators and fork version are
+    // not expected to coexist.
+    genesis_validators_root: H256,
+
     // `BeaconBlockHeader` fields except for `state_root`.
     latest_block_slot: Slot,
     proposer_index: ValidatorIndex,
@@ -47,6 +49,7 @@ struct BeaconStateKey {
 impl<C: Config> From<&BeaconState<C>> for BeaconStateKey {
     fn from(state: &BeaconState<C>) -> Self {
         let BeaconState {
+            genesis_validators_root,
             slot: state_slot,
             fork: Fork {
                 current_version, ..
@@ -65,6 +68,7 @@ impl<C: Config> From<&BeaconState<C>> for BeaconStateKey {
         Self {
             state_slot,
             current_version,
+            genesis_validators_root,
             latest_block_slot,
             proposer_index,
             parent_root,
@@ -79,7 +83,7 @@ impl<C: Config> From<&BeaconState<C>> for BeaconStateKey {
 /// of `beacon_state_hash_tree_root`.
 ///
 /// [summarized]:    https://github.com/ethereum/eth2.0-specs/blob/f2440451919d4d7516903ec2646e7a00e12be1b7/ssz/simple-serialize.md#summaries-and-expansions
-#[derive(TreeHash)]
+#[derive(SszHash)]
 struct BeaconStateSummary {
     genesis_time: H256,
     genesis_validators_root: H256,
@@ -102,6 +106,14 @@ struct BeaconStateSummary {
     previous_justified_checkpoint: H256,
     current_justified_checkpoint: H256,
     finalized_checkpoint: H256,
+    current_epoch_start_shard: H256,
+    shard_states: H256,
+    online_countdown: H256,
+    current_light_committee: H256,
+    next_light_committee: H256,
+    exposed_derived_secrets: H256,
+    custody_chunk_challenge_records: H256,
+    custody_chunk_challenge_index: H256,
 }
 
 impl<C: Config> From<&BeaconState<C>> for BeaconStateSummary {
@@ -128,7 +140,14 @@ impl<C: Config> From<&BeaconState<C>> for BeaconStateSummary {
             previous_justified_checkpoint,
             current_justified_checkpoint,
             finalized_checkpoint,
-            ..
+            current_epoch_start_shard,
+            shard_states,
+            online_countdown,
+            current_light_committee,
+            next_light_committee,
+            exposed_derived_secrets,
+            custody_chunk_challenge_records,
+            custody_chunk_challenge_index,
         } = state;
 
         Self {
@@ -155,6 +174,14 @@ impl<C: Config> From<&BeaconState<C>> for BeaconStateSummary {
             previous_justified_checkpoint: previous_justified_checkpoint.tree_hash_root(),
             current_justified_checkpoint: current_justified_checkpoint.tree_hash_root(),
             finalized_checkpoint: finalized_checkpoint.tree_hash_root(),
+            current_epoch_start_shard: current_epoch_start_shard.tree_hash_root(),
+            shard_states: shard_states.tree_hash_root(),
+            online_countdown: online_countdown.tree_hash_root(),
+            current_light_committee: current_light_committee.tree_hash_root(),
+            next_light_committee: next_light_committee.tree_hash_root(),
+            exposed_derived_secrets: exposed_derived_secrets.tree_hash_root(),
+            custody_chunk_challenge_records: custody_chunk_challenge_records.tree_hash_root(),
+            custody_chunk_challenge_index: custody_chunk_challenge_index.tree_hash_root(),
         }
     }
 }
@@ -165,7 +192,7 @@ pub fn beacon_state_hash_tree_root(state: &BeaconState<impl Config>) -> H256 {
 }
 
 #[must_use]
-pub fn arc_hash_tree_root(arc: &Arc<impl TreeHash + Send + Sync + 'static>) -> H256 {
+pub fn arc_hash_tree_root(arc: &Arc<impl SszHash + Send + Sync + 'static>) -> H256 {
     static CACHE: Lazy<Mutex<SizedCache<WeakKey, H256>>> =
         Lazy::new(|| Mutex::new(SizedCache::with_size(512)));
 
@@ -250,6 +277,23 @@ pub fn beacon_committee<C: Config>(
     })
 }
 
+pub fn shard_proposer_index<C: Config>(
+    state: &BeaconState<C>,
+    slot: Slot,
+    shard: Shard,
+) -> Result<ValidatorIndex> {
+    thread_local! {
+        static CACHE: Mutex<SizedCache<(BeaconStateKey, Slot, Shard), ValidatorIndex>> =
+            Mutex::new(SizedCache::with_size(1024));
+    }
+
+    let key = (state.into(), slot, shard);
+
+    CACHE.try_look_up(key, || {
+        accessors::get_shard_proposer_index(state, slot, shard)
+    })
+}
+
 fn active_validator_indices_shuffled<C: Config>(
     state: &BeaconState<C>,
     epoch: Epoch,
@@ -269,3 +313,103 @@ fn active_validator_indices_shuffled<C: Config>(
         Ok(indices.into())
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use ssz_new::types::VariableList;
+    use types::{
+        arc_ext::ArcExt as _,
+        config::MinimalConfig,
+        consts::FAR_FUTURE_EPOCH,
+        containers::{CustodyChunkChallengeRecord, ShardState, Validator},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_beacon_state_hash_tree_root_covers_phase1_fields() {
+        let mut state = BeaconState::<MinimalConfig> {
+            slot: 3,
+            validators: Arc::new(vec![Validator::default(); 2].into()),
+            balances: Arc::new(vec![1, 2].into()),
+            current_epoch_start_shard: 2,
+            shard_states: Arc::new(VariableList::from(vec![ShardState {
+                slot: 1,
+                gasprice: 8,
+                latest_block_root: H256::repeat_byte(1),
+            }])),
+            online_countdown: Arc::new(VariableList::from(vec![8, 0])),
+            custody_chunk_challenge_records: VariableList::from(vec![
+                CustodyChunkChallengeRecord {
+                    challenge_index: 4,
+                    data_root: H256::repeat_byte(2),
+                    ..CustodyChunkChallengeRecord::default()
+                },
+            ]),
+            custody_chunk_challenge_index: 5,
+            ..BeaconState::default()
+        };
+        state.exposed_derived_secrets.make_mut()[1] = VariableList::from(vec![1]);
+
+        let root = beacon_state_hash_tree_root(&state);
+        assert_eq!(root, state.tree_hash_root());
+
+        state.custody_chunk_challenge_index += 1;
+        assert_ne!(beacon_state_hash_tree_root(&state), root);
+    }
+
+    #[test]
+    fn test_states_of_different_chains_do_not_share_cache_entries() {
+        let validator = Validator {
+            exit_epoch: FAR_FUTURE_EPOCH,
+            ..Validator::default()
+        };
+        let state = |genesis_validators_root, validator_count| BeaconState::<MinimalConfig> {
+            genesis_validators_root,
+            validators: Arc::new(vec![validator; validator_count].into()),
+            ..BeaconState::default()
+        };
+        let first_chain_state = state(H256::repeat_byte(1), 2);
+        let second_chain_state = state(H256::repeat_byte(2), 3);
+
+        let first_chain_indices = active_validator_indices_ordered(&first_chain_state, 0)
+            .expect("indices can be computed");
+        let second_chain_indices = active_validator_indices_ordered(&second_chain_state, 0)
+            .expect("indices can be computed");
+
+        assert_eq!(first_chain_indices[..], [0, 1]);
+        assert_eq!(second_chain_indices[..], [0, 1, 2]);
+    }
+}
+
+#[cfg(test)]
+mod spec_tests {
+    use serde::Deserialize;
+    use spec_test_utils::Case;
+    use test_generator::test_resources;
+    use types::config::{MainnetConfig, MinimalConfig};
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Roots {
+        root: H256,
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/ssz_static/BeaconState/*/*")]
+    fn mainnet_beacon_state_hash_tree_root(case: Case) {
+        run_case::<MainnetConfig>(case);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/ssz_static/BeaconState/*/*")]
+    fn minimal_beacon_state_hash_tree_root(case: Case) {
+        run_case::<MinimalConfig>(case);
+    }
+
+    fn run_case<C: Config>(case: Case) {
+        let state: BeaconState<C> = case.ssz("serialized");
+        let Roots { root } = case.yaml("roots");
+
+        assert_eq!(beacon_state_hash_tree_root(&state), root);
+    }
+}
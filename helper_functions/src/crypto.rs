@@ -2,6 +2,14 @@ use core::convert::TryInto as _;
 
 use anyhow::Result;
 use bls::{PublicKey, PublicKeyBytes, Signature, SignatureBytes};
+use types::primitives::H256;
+
+// See <https://tools.ietf.org/html/draft-irtf-cfrg-pairing-friendly-curves-07#appendix-C.1>.
+const G2_POINT_AT_INFINITY: SignatureBytes = {
+    let mut bytes = [0; 96];
+    bytes[0] = 0b1100_0000;
+    SignatureBytes(bytes)
+};
 
 pub fn bls_verify(
     pubkey: PublicKeyBytes,
@@ -14,6 +22,44 @@ pub fn bls_verify(
     Ok(sg.verify(pk, message))
 }
 
+pub fn optional_aggregate_verify(
+    pubkeys: impl IntoIterator<Item = PublicKeyBytes>,
+    messages: impl IntoIterator<Item = H256>,
+    signature: SignatureBytes,
+) -> Result<bool> {
+    let pubkeys = pubkeys
+        .into_iter()
+        .map(PublicKeyBytes::try_into)
+        .collect::<Result<Vec<PublicKey>, _>>()?;
+
+    if pubkeys.is_empty() {
+        return Ok(signature == G2_POINT_AT_INFINITY);
+    }
+
+    let sg: Signature = signature.try_into()?;
+
+    Ok(sg.aggregate_verify(pubkeys, messages))
+}
+
+pub fn optional_fast_aggregate_verify(
+    pubkeys: impl IntoIterator<Item = PublicKeyBytes>,
+    message: H256,
+    signature: SignatureBytes,
+) -> Result<bool> {
+    let pubkeys = pubkeys
+        .into_iter()
+        .map(PublicKeyBytes::try_into)
+        .collect::<Result<Vec<PublicKey>, _>>()?;
+
+    if pubkeys.is_empty() {
+        return Ok(signature == G2_POINT_AT_INFINITY);
+    }
+
+    let sg: Signature = signature.try_into()?;
+
+    Ok(sg.fast_aggregate_verify(pubkeys, message))
+}
+
 #[cfg(test)]
 mod tests {
     use core::convert::TryFrom as _;
@@ -67,4 +113,124 @@ mod tests {
 
         assert!(bls_verify(pk_bytes, b"aaabbb", sg_bytes).is_err());
     }
+
+    fn secret_keys() -> Vec<SecretKey> {
+        (1..=3)
+            .map(|byte| {
+                SecretKey::try_from(SecretKeyBytes::from([byte; 32]))
+                    .expect("bytes represent a valid secret key")
+            })
+            .collect()
+    }
+
+    fn sign_distinct_messages(secret_keys: &[SecretKey]) -> (Vec<H256>, SignatureBytes) {
+        let messages = (0..secret_keys.len())
+            .map(|index| H256::repeat_byte(index as u8))
+            .collect::<Vec<_>>();
+
+        let mut signature = Signature::default();
+        for (sk, message) in secret_keys.iter().zip(&messages) {
+            signature.aggregate_in_place(sk.sign(message));
+        }
+
+        (messages, signature.into())
+    }
+
+    #[test]
+    fn test_optional_aggregate_verify_valid() {
+        let secret_keys = secret_keys();
+        let pubkeys = secret_keys.iter().map(|sk| sk.to_public_key().into());
+        let (messages, signature) = sign_distinct_messages(&secret_keys);
+
+        assert!(matches!(
+            optional_aggregate_verify(pubkeys, messages, signature),
+            Ok(true)
+        ));
+    }
+
+    #[test]
+    fn test_optional_aggregate_verify_wrong_message() {
+        let secret_keys = secret_keys();
+        let pubkeys = secret_keys.iter().map(|sk| sk.to_public_key().into());
+        let (mut messages, signature) = sign_distinct_messages(&secret_keys);
+        messages[1] = H256::repeat_byte(0xff);
+
+        assert!(matches!(
+            optional_aggregate_verify(pubkeys, messages, signature),
+            Ok(false)
+        ));
+    }
+
+    #[test]
+    fn test_optional_aggregate_verify_count_mismatch() {
+        let secret_keys = secret_keys();
+        let pubkeys = secret_keys.iter().map(|sk| sk.to_public_key().into());
+        let (mut messages, signature) = sign_distinct_messages(&secret_keys);
+        messages.pop();
+
+        assert!(matches!(
+            optional_aggregate_verify(pubkeys, messages, signature),
+            Ok(false)
+        ));
+    }
+
+    #[test]
+    fn test_optional_aggregate_verify_empty() {
+        assert!(matches!(
+            optional_aggregate_verify(vec![], vec![], G2_POINT_AT_INFINITY),
+            Ok(true)
+        ));
+
+        let sk = SecretKey::try_from(SecretKeyBytes::from(*b"????????????????????????????????"))
+            .expect("bytes represent a valid secret key");
+        let signature = sk.sign(b"cats").into();
+
+        assert!(matches!(
+            optional_aggregate_verify(vec![], vec![], signature),
+            Ok(false)
+        ));
+    }
+
+    #[test]
+    fn test_optional_fast_aggregate_verify() {
+        let secret_keys = secret_keys();
+        let pubkeys = secret_keys
+            .iter()
+            .map(|sk| sk.to_public_key().into())
+            .collect::<Vec<_>>();
+        let message = H256::repeat_byte(1);
+
+        let mut signature = Signature::default();
+        for sk in &secret_keys {
+            signature.aggregate_in_place(sk.sign(message));
+        }
+        let signature = signature.into();
+
+        assert!(matches!(
+            optional_fast_aggregate_verify(pubkeys.iter().copied(), message, signature),
+            Ok(true)
+        ));
+        assert!(matches!(
+            optional_fast_aggregate_verify(pubkeys.iter().copied().skip(1), message, signature),
+            Ok(false)
+        ));
+        assert!(matches!(
+            optional_fast_aggregate_verify(pubkeys, H256::repeat_byte(2), signature),
+            Ok(false)
+        ));
+    }
+
+    #[test]
+    fn test_optional_fast_aggregate_verify_empty() {
+        let message = H256::repeat_byte(1);
+
+        assert!(matches!(
+            optional_fast_aggregate_verify(vec![], message, G2_POINT_AT_INFINITY),
+            Ok(true)
+        ));
+        assert!(matches!(
+            optional_fast_aggregate_verify(vec![], message, SignatureBytes([1; 96])),
+            Ok(false)
+        ));
+    }
 }
@@ -0,0 +1,323 @@
+use core::mem;
+
+use bls::SignatureBytes;
+use ethereum_types::{U256, U512};
+use ssz_new::types::ByteList;
+use types::{
+    config::Config,
+    consts::{BYTES_PER_CUSTODY_ATOM, CUSTODY_PROBABILITY_EXPONENT, CUSTODY_SECRETS},
+};
+
+// 2**256 - 189
+pub const CUSTODY_PRIME: U256 = U256([u64::MAX - 188, u64::MAX, u64::MAX, u64::MAX]);
+
+// 2**256 - CUSTODY_PRIME. Arithmetic modulo `CUSTODY_PRIME` relies on it being small.
+const CUSTODY_PRIME_COMPLEMENT: u64 = 189;
+
+// The Jacobi symbol computed with the binary algorithm, which only needs shifts and subtractions.
+// Division is only used once to reduce `a`. `q` must be odd.
+#[must_use]
+pub fn legendre_bit(a: U256, q: U256) -> bool {
+    debug_assert!(q.bit(0), "q must be odd");
+
+    let mut a = a % q;
+    let mut n = q;
+    let mut positive = true;
+
+    if a.is_zero() {
+        return false;
+    }
+
+    loop {
+        let twos = a.trailing_zeros();
+        a >>= twos as usize;
+
+        if twos % 2 == 1 && matches!(n.low_u64() % 8, 3 | 5) {
+            positive = !positive;
+        }
+
+        if a == n {
+            break;
+        }
+
+        if a < n {
+            mem::swap(&mut a, &mut n);
+
+            if a.low_u64() % 4 == 3 && n.low_u64() % 4 == 3 {
+                positive = !positive;
+            }
+        }
+
+        // Both are odd, so the difference is even and nonzero.
+        a -= n;
+    }
+
+    // `n` is now the greatest common divisor of the original arguments.
+    n == U256::one() && positive
+}
+
+pub fn get_custody_atoms(bytes: &[u8]) -> impl Iterator<Item = [u8; BYTES_PER_CUSTODY_ATOM]> + '_ {
+    bytes.chunks(BYTES_PER_CUSTODY_ATOM).map(|chunk| {
+        // Right-pad the last atom with zeros.
+        let mut atom = [0; BYTES_PER_CUSTODY_ATOM];
+        atom[..chunk.len()].copy_from_slice(chunk);
+        atom
+    })
+}
+
+// The specification reads the secrets from the x coordinate of the decompressed signature,
+// serialized as `c0 || c1` in little-endian order. The compressed encoding already contains it as
+// `c1 || c0` in big-endian order, with flags in the top 3 bits.
+#[must_use]
+pub fn get_custody_secrets(key: SignatureBytes) -> [U256; CUSTODY_SECRETS] {
+    let (c1, c0) = key.as_bytes().split_at(48);
+
+    let mut signature_bytes = [0; 96];
+    for (byte, key_byte) in signature_bytes
+        .iter_mut()
+        .zip(c0.iter().rev().chain(c1.iter().rev()))
+    {
+        *byte = *key_byte;
+    }
+    signature_bytes[95] &= 0b0001_1111;
+
+    let secret = |index: usize| {
+        U256::from_little_endian(
+            &signature_bytes[index * BYTES_PER_CUSTODY_ATOM..(index + 1) * BYTES_PER_CUSTODY_ATOM],
+        )
+    };
+
+    [secret(0), secret(1), secret(2)]
+}
+
+#[must_use]
+pub fn universal_hash_function(
+    atoms: impl IntoIterator<Item = [u8; BYTES_PER_CUSTODY_ATOM]>,
+    secrets: &[U256; CUSTODY_SECRETS],
+) -> U256 {
+    let secrets = [
+        reduce_once(secrets[0]),
+        reduce_once(secrets[1]),
+        reduce_once(secrets[2]),
+    ];
+
+    // Atom `i` is multiplied by `secrets[i % 3] ** i`. Keeping the next power for each secret
+    // around replaces exponentiation with 2 multiplications per atom.
+    let mut powers = [U256::one(), secrets[1], mul_mod(secrets[2], secrets[2])];
+    let cube = |secret| mul_mod(mul_mod(secret, secret), secret);
+    let cubes = [cube(secrets[0]), cube(secrets[1]), cube(secrets[2])];
+    let mut sum = U256::zero();
+    let mut atom_count = 0;
+
+    for (index, atom) in atoms.into_iter().enumerate() {
+        let secret_index = index % CUSTODY_SECRETS;
+        let atom = U256::from_little_endian(&atom);
+        sum = add_mod(sum, mul_mod(powers[secret_index], atom));
+        powers[secret_index] = mul_mod(powers[secret_index], cubes[secret_index]);
+        atom_count = index + 1;
+    }
+
+    // `powers[atom_count % 3]` is now `secrets[atom_count % 3] ** atom_count`.
+    add_mod(sum, powers[atom_count % CUSTODY_SECRETS])
+}
+
+#[must_use]
+pub fn compute_custody_bit<C: Config>(
+    key: SignatureBytes,
+    data: &ByteList<C::MaxShardBlockSize>,
+) -> bool {
+    let data = data.clone().into_bytes();
+    let secrets = get_custody_secrets(key);
+    let uhf = universal_hash_function(get_custody_atoms(&data), &secrets);
+    let base = add_mod(uhf, reduce_once(secrets[0]));
+
+    (0..CUSTODY_PROBABILITY_EXPONENT)
+        .all(|offset| legendre_bit(add_mod(base, U256::from(offset)), CUSTODY_PRIME))
+}
+
+fn reduce_once(value: U256) -> U256 {
+    if value >= CUSTODY_PRIME {
+        value - CUSTODY_PRIME
+    } else {
+        value
+    }
+}
+
+// Both arguments must be reduced.
+fn add_mod(left: U256, right: U256) -> U256 {
+    let (sum, overflow) = left.overflowing_add(right);
+
+    // On overflow the wrapped sum is 2**256 lower than the real one, so subtracting the prime
+    // with wrapping yields the reduced result.
+    if overflow || sum >= CUSTODY_PRIME {
+        sum.overflowing_sub(CUSTODY_PRIME).0
+    } else {
+        sum
+    }
+}
+
+// Products are reduced using 2**256 ≡ CUSTODY_PRIME_COMPLEMENT instead of division.
+fn mul_mod(left: U256, right: U256) -> U256 {
+    let U512(product) = left.full_mul(right);
+
+    // `low + high * 2**256` becomes `low + high * CUSTODY_PRIME_COMPLEMENT` (at most 265 bits).
+    let low = U512([product[0], product[1], product[2], product[3], 0, 0, 0, 0]);
+    let high = U256([product[4], product[5], product[6], product[7]]);
+    let U512(folded) = low + high.full_mul(U256::from(CUSTODY_PRIME_COMPLEMENT));
+
+    // Fold the remaining 9 bits the same way.
+    let low = U256([folded[0], folded[1], folded[2], folded[3]]);
+    let (sum, overflow) = low.overflowing_add(U256::from(folded[4] * CUSTODY_PRIME_COMPLEMENT));
+
+    if overflow {
+        // The wrapped sum is small, so adding the complement cannot overflow again.
+        sum + U256::from(CUSTODY_PRIME_COMPLEMENT)
+    } else {
+        reduce_once(sum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use types::config::MinimalConfig;
+
+    use super::*;
+
+    fn u256() -> impl Strategy<Value = U256> {
+        any::<[u64; 4]>().prop_map(U256)
+    }
+
+    fn naive_mul_mod(left: U256, right: U256) -> U256 {
+        let U512(remainder) = left.full_mul(right) % U512::from(CUSTODY_PRIME);
+        U256([remainder[0], remainder[1], remainder[2], remainder[3]])
+    }
+
+    fn naive_pow_mod(base: U256, exponent: usize) -> U256 {
+        (0..exponent).fold(U256::one(), |power, _| naive_mul_mod(power, base))
+    }
+
+    #[test]
+    fn test_legendre_bit_for_small_primes() {
+        for q in [3_u64, 5, 7, 11, 13, 101].iter().copied() {
+            for a in 0..2 * q {
+                let is_residue = a % q != 0 && (1..q).any(|x| x * x % q == a % q);
+                assert_eq!(
+                    legendre_bit(U256::from(a), U256::from(q)),
+                    is_residue,
+                    "a = {}, q = {}",
+                    a,
+                    q,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_legendre_bit_for_custody_prime() {
+        // CUSTODY_PRIME ≡ 3 (mod 8), so neither 2 nor -1 are quadratic residues.
+        assert!(!legendre_bit(U256::from(2), CUSTODY_PRIME));
+        assert!(!legendre_bit(CUSTODY_PRIME - 1, CUSTODY_PRIME));
+        assert!(legendre_bit(U256::from(4), CUSTODY_PRIME));
+        assert!(!legendre_bit(CUSTODY_PRIME, CUSTODY_PRIME));
+    }
+
+    #[test]
+    fn test_get_custody_atoms() {
+        let bytes = (0..40).collect::<Vec<u8>>();
+        let atoms = get_custody_atoms(&bytes).collect::<Vec<_>>();
+
+        let mut last_atom = [0; BYTES_PER_CUSTODY_ATOM];
+        last_atom[..8].copy_from_slice(&bytes[32..]);
+
+        assert_eq!(atoms.len(), 2);
+        assert_eq!(atoms[0][..], bytes[..32]);
+        assert_eq!(atoms[1], last_atom);
+        assert_eq!(get_custody_atoms(&[]).count(), 0);
+    }
+
+    #[test]
+    fn test_get_custody_secrets() {
+        let mut bytes = [0; 96];
+        bytes[0] = 0b1010_0000 | 2;
+        bytes[47] = 3;
+        bytes[48] = 4;
+        bytes[95] = 5;
+
+        assert_eq!(
+            get_custody_secrets(SignatureBytes(bytes)),
+            [
+                U256::from(5),
+                (U256::from(3) << 128) + (U256::from(4) << 120),
+                U256::from(2) << 248,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_universal_hash_function() {
+        let secrets = [U256::from(3), CUSTODY_PRIME - 5, U256::MAX];
+        let data = (0..=255).cycle().take(200).collect::<Vec<u8>>();
+
+        let expected = get_custody_atoms(&data)
+            .enumerate()
+            .map(|(index, atom)| {
+                let power = naive_pow_mod(secrets[index % 3], index);
+                naive_mul_mod(power, U256::from_little_endian(&atom))
+            })
+            .chain(core::iter::once(naive_pow_mod(secrets[7 % 3], 7)))
+            .fold(U256::zero(), add_mod);
+
+        assert_eq!(
+            universal_hash_function(get_custody_atoms(&data), &secrets),
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_compute_custody_bit_of_empty_data() {
+        let key = SignatureBytes([7; 96]);
+        let secrets = get_custody_secrets(key);
+        // The hash of no atoms is `secrets[0] ** 0`.
+        let expected = (0..CUSTODY_PROBABILITY_EXPONENT).all(|offset| {
+            legendre_bit(
+                U256::one() + reduce_once(secrets[0]) + offset,
+                CUSTODY_PRIME,
+            )
+        });
+
+        assert_eq!(
+            compute_custody_bit::<MinimalConfig>(key, &ByteList::default()),
+            expected,
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn mul_mod_matches_division(left in u256(), right in u256()) {
+            prop_assert_eq!(mul_mod(left, right), naive_mul_mod(left, right));
+        }
+
+        #[test]
+        fn add_mod_matches_division(left in u256(), right in u256()) {
+            let (left, right) = (reduce_once(left), reduce_once(right));
+            let U512(sum) = (U512::from(left) + U512::from(right)) % U512::from(CUSTODY_PRIME);
+            prop_assert_eq!(add_mod(left, right), U256([sum[0], sum[1], sum[2], sum[3]]));
+        }
+
+        #[test]
+        fn squares_are_quadratic_residues(value in u256()) {
+            let square = mul_mod(value, value);
+            prop_assert_eq!(legendre_bit(square, CUSTODY_PRIME), !square.is_zero());
+        }
+
+        #[test]
+        fn legendre_bit_is_multiplicative(left in u256(), right in u256()) {
+            prop_assume!(!reduce_once(left).is_zero() && !reduce_once(right).is_zero());
+            prop_assert_eq!(
+                legendre_bit(mul_mod(left, right), CUSTODY_PRIME),
+                legendre_bit(left, CUSTODY_PRIME) == legendre_bit(right, CUSTODY_PRIME),
+            );
+        }
+    }
+}
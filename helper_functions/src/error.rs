@@ -8,6 +8,8 @@ pub enum Error {
     AttestingIndicesEmpty,
     #[error("attesting indices are not sorted and unique")]
     AttestingIndicesNotSortedAndUnique,
+    #[error("committee is too large")]
+    CommitteeTooLarge,
     #[error("index is out of bounds")]
     IndexOutOfBounds,
     #[error("slot is out of range")]
@@ -1,11 +1,15 @@
 pub mod accessors;
 pub mod cache;
 pub mod crypto;
+pub mod custody;
+pub mod merkle_proofs;
 pub mod misc;
 pub mod mutators;
 pub mod predicates;
+pub mod tree_hash_cache;
 
 mod cached_ext;
 mod error;
+mod merkle_tree;
 mod shuffle;
 mod weak_key;
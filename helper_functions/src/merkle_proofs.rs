@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+
+use anyhow::{ensure, Result};
+use ssz_new::merkleization::{chunk_generalized_index, floor_log2, PathError};
+pub use ssz_new::merkleization::{
+    concat_generalized_indices, GeneralizedIndex, Merkleized, PathElement,
+};
+use types::primitives::H256;
+
+use crate::merkle_tree::MerkleTree;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Proof {
+    pub leaf: H256,
+    // Sibling nodes starting from the bottom, as expected by `is_valid_merkle_branch`.
+    pub branch: Vec<H256>,
+    pub generalized_index: GeneralizedIndex,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Multiproof {
+    pub leaves: Vec<H256>,
+    // Helper nodes in the order returned by `hashing::get_helper_indices`.
+    pub proof: Vec<H256>,
+    pub generalized_indices: Vec<GeneralizedIndex>,
+}
+
+impl Multiproof {
+    #[must_use]
+    pub fn verify(&self, root: H256) -> bool {
+        hashing::verify_merkle_multiproof(
+            self.leaves.as_slice(),
+            self.proof.as_slice(),
+            self.generalized_indices.as_slice(),
+            root,
+        )
+    }
+}
+
+impl Proof {
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        floor_log2(self.generalized_index)
+    }
+
+    #[must_use]
+    pub fn index(&self) -> u64 {
+        self.generalized_index ^ (1 << self.depth())
+    }
+}
+
+/// Proofs for SSZ values along paths as defined in the
+/// [specification](https://github.com/ethereum/eth2.0-specs/blob/dev/ssz/merkle-proofs.md).
+///
+/// Implemented for every [`Merkleized`] value. Containers get their [`Merkleized`] impls from
+/// `#[derive(SszHash)]`.
+pub trait MerkleProofs: Merkleized + Sized {
+    fn proof(&self, path: &[PathElement]) -> Result<Proof> {
+        single_proof(self, path)
+    }
+
+    fn multiproof(&self, paths: &[&[PathElement]]) -> Result<Multiproof> {
+        multiproof(self, paths)
+    }
+}
+
+impl<T: Merkleized> MerkleProofs for T {}
+
+pub fn single_proof(node: &dyn Merkleized, path: &[PathElement]) -> Result<Proof> {
+    let (element, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            return Ok(Proof {
+                leaf: node.root(),
+                branch: vec![],
+                generalized_index: 1,
+            })
+        }
+    };
+
+    let tree = MerkleTree::from_leaves(node.depth(), node.chunks());
+
+    if *element == PathElement::Length {
+        let length = node.length().ok_or(PathError::PathInvalid)?;
+        ensure!(rest.is_empty(), PathError::PathInvalid);
+
+        return Ok(Proof {
+            leaf: length_chunk(length),
+            branch: vec![tree.root()],
+            generalized_index: 3,
+        });
+    }
+
+    let (position, child) = node.child(*element)?;
+    let mut branch = tree.branch(position);
+
+    if let Some(length) = node.length() {
+        branch.push(length_chunk(length));
+    }
+
+    let inner = match child {
+        Some(child) => single_proof(child, rest)?,
+        None => {
+            ensure!(rest.is_empty(), PathError::PathInvalid);
+            Proof {
+                leaf: tree.node(0, position),
+                branch: vec![],
+                generalized_index: 1,
+            }
+        }
+    };
+
+    let generalized_index = concat_generalized_indices(
+        chunk_generalized_index(node.depth(), node.length().is_some(), position),
+        inner.generalized_index,
+    )?;
+
+    Ok(Proof {
+        leaf: inner.leaf,
+        branch: inner.branch.into_iter().chain(branch).collect(),
+        generalized_index,
+    })
+}
+
+pub fn multiproof(node: &dyn Merkleized, paths: &[&[PathElement]]) -> Result<Multiproof> {
+    let proofs = paths
+        .iter()
+        .map(|path| single_proof(node, path))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Every helper node is in the branch of at least one of the single proofs.
+    let mut nodes = HashMap::new();
+
+    for proof in &proofs {
+        for (height, node) in proof.branch.iter().enumerate() {
+            nodes.insert((proof.generalized_index >> height) ^ 1, *node);
+        }
+    }
+
+    let generalized_indices = proofs
+        .iter()
+        .map(|proof| proof.generalized_index)
+        .collect::<Vec<_>>();
+
+    let proof = hashing::get_helper_indices(generalized_indices.as_slice())
+        .into_iter()
+        .map(|index| nodes[&index])
+        .collect();
+
+    Ok(Multiproof {
+        leaves: proofs.into_iter().map(|proof| proof.leaf).collect(),
+        proof,
+        generalized_indices,
+    })
+}
+
+fn length_chunk(length: usize) -> H256 {
+    hashing::hash_from_u64(length as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bls::SignatureBytes;
+    use ssz_new::{
+        types::{BitList, VariableList},
+        SszHash,
+    };
+    use types::{
+        arc_ext::ArcExt as _,
+        beacon_state::BeaconState,
+        config::MinimalConfig,
+        containers::{
+            Attestation, AttestationData, BeaconBlock, BeaconBlockBody, Checkpoint, ShardState,
+            Validator,
+        },
+    };
+
+    use crate::predicates::is_valid_merkle_branch;
+
+    use super::*;
+
+    use PathElement::{Field, Index, Length};
+
+    fn state() -> BeaconState<MinimalConfig> {
+        let validators = (0..5)
+            .map(|index| Validator {
+                effective_balance: index * 1_000_000_000,
+                ..Validator::default()
+            })
+            .collect::<Vec<_>>();
+
+        let mut exposed_derived_secrets =
+            BeaconState::<MinimalConfig>::default().exposed_derived_secrets;
+        exposed_derived_secrets.make_mut()[1] = VariableList::from(vec![4, 2]);
+
+        BeaconState {
+            slot: 17,
+            validators: Arc::new(validators.into()),
+            balances: Arc::new((10..15).collect::<Vec<_>>().into()),
+            finalized_checkpoint: Checkpoint {
+                epoch: 2,
+                root: H256::repeat_byte(2),
+            },
+            shard_states: Arc::new(
+                (0..4)
+                    .map(|slot| ShardState {
+                        slot,
+                        gasprice: 8,
+                        latest_block_root: H256::repeat_byte(slot as u8),
+                    })
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            online_countdown: Arc::new(vec![8; 5].into()),
+            exposed_derived_secrets,
+            ..BeaconState::default()
+        }
+    }
+
+    fn assert_valid_proof<T: Merkleized + SszHash>(value: &T, path: &[PathElement]) -> Proof {
+        let proof = value.proof(path).expect("path is valid");
+
+        assert_eq!(
+            proof.generalized_index,
+            T::generalized_index(path).expect("path is valid"),
+        );
+        assert_eq!(proof.branch.len(), proof.depth());
+        assert!(is_valid_merkle_branch(
+            proof.leaf,
+            proof.branch.as_slice(),
+            proof.depth(),
+            proof.index(),
+            value.tree_hash_root(),
+        )
+        .expect("branch has the right length"));
+
+        proof
+    }
+
+    #[test]
+    fn test_chunks_match_roots() {
+        let state = state();
+        let tree = MerkleTree::from_leaves(state.depth(), state.chunks());
+        assert_eq!(tree.root(), state.tree_hash_root());
+
+        let body = BeaconBlockBody::<MinimalConfig>::default();
+        let tree = MerkleTree::from_leaves(body.depth(), body.chunks());
+        assert_eq!(tree.root(), body.tree_hash_root());
+    }
+
+    #[test]
+    fn test_generalized_indices_match_specification() {
+        // `FINALIZED_ROOT_INDEX` from the phase 0 light client sync specification is based on the
+        // phase 0 `BeaconState`, which has 21 fields and thus the same depth.
+        assert_eq!(
+            BeaconState::<MinimalConfig>::generalized_index(&[
+                Field("finalized_checkpoint"),
+                Field("root"),
+            ])
+            .expect("path is valid"),
+            105,
+        );
+        assert_eq!(
+            VariableList::<u64, typenum::U8>::generalized_index(&[Length]).expect("path is valid"),
+            3,
+        );
+        // Four `u64`s fit in a chunk. 8 `u64`s take up 2 chunks.
+        assert_eq!(
+            VariableList::<u64, typenum::U8>::generalized_index(&[Index(5)])
+                .expect("path is valid"),
+            0b101,
+        );
+    }
+
+    #[test]
+    fn test_beacon_state_proofs() {
+        let state = state();
+
+        let proof = assert_valid_proof(&state, &[Field("finalized_checkpoint")]);
+        assert_eq!(proof.leaf, state.finalized_checkpoint.tree_hash_root());
+
+        let proof = assert_valid_proof(&state, &[Field("shard_states"), Index(3)]);
+        assert_eq!(proof.leaf, state.shard_states[3].tree_hash_root());
+
+        let proof = assert_valid_proof(&state, &[Field("shard_states"), Length]);
+        assert_eq!(proof.leaf, length_chunk(4));
+
+        let proof = assert_valid_proof(&state, &[Field("validators"), Index(4)]);
+        assert_eq!(proof.leaf, state.validators[4].tree_hash_root());
+
+        let proof = assert_valid_proof(
+            &state,
+            &[Field("validators"), Index(2), Field("effective_balance")],
+        );
+        assert_eq!(proof.leaf, 2_000_000_000_u64.tree_hash_root());
+
+        // Four balances fit in a chunk. The second chunk only contains the last one.
+        let proof = assert_valid_proof(&state, &[Field("balances"), Index(4)]);
+        assert_eq!(proof.leaf[..8], 14_u64.to_le_bytes());
+
+        let proof = assert_valid_proof(
+            &state,
+            &[Field("exposed_derived_secrets"), Index(1), Index(1)],
+        );
+        assert_eq!(
+            proof.leaf[..16],
+            [4, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        assert_valid_proof(&state, &[Field("block_roots"), Index(63)]);
+        assert_valid_proof(&state, &[Field("online_countdown"), Length]);
+        assert_valid_proof(&state, &[]);
+    }
+
+    #[test]
+    fn test_nested_container_proofs() {
+        let attestation = Attestation::<MinimalConfig> {
+            aggregation_bits: BitList::with_capacity(3).expect("length is within limit"),
+            data: AttestationData {
+                target: Checkpoint {
+                    epoch: 3,
+                    root: H256::repeat_byte(3),
+                },
+                ..AttestationData::default()
+            },
+            signature: SignatureBytes::default(),
+        };
+        let block = BeaconBlock::<MinimalConfig> {
+            body: BeaconBlockBody {
+                attestations: VariableList::from(vec![attestation]),
+                ..BeaconBlockBody::default()
+            },
+            ..BeaconBlock::default()
+        };
+
+        let proof = assert_valid_proof(
+            &block,
+            &[
+                Field("body"),
+                Field("attestations"),
+                Index(0),
+                Field("data"),
+                Field("target"),
+                Field("epoch"),
+            ],
+        );
+        assert_eq!(proof.leaf, 3_u64.tree_hash_root());
+
+        assert_valid_proof(&block, &[Field("body"), Field("attestations"), Length]);
+        assert_valid_proof(&block, &[Field("body"), Field("randao_reveal")]);
+    }
+
+    #[test]
+    fn test_multiproof_matches_single_proofs() {
+        let state = state();
+        let paths: [&[PathElement]; 3] = [
+            &[Field("validators"), Index(3)],
+            &[Field("balances"), Index(3)],
+            &[Field("finalized_checkpoint")],
+        ];
+
+        let multiproof = state.multiproof(&paths).expect("paths are valid");
+
+        assert!(multiproof.verify(state.tree_hash_root()));
+        assert!(!multiproof.verify(BeaconState::<MinimalConfig>::default().tree_hash_root()));
+
+        let mut branch_length = 0;
+
+        for (index, path) in paths.iter().enumerate() {
+            let proof = assert_valid_proof(&state, path);
+            assert_eq!(multiproof.leaves[index], proof.leaf);
+            assert_eq!(
+                multiproof.generalized_indices[index],
+                proof.generalized_index
+            );
+            branch_length += proof.branch.len();
+        }
+
+        assert!(multiproof.proof.len() < branch_length);
+
+        let mut tampered = multiproof;
+        tampered.leaves[1] = H256::zero();
+
+        assert!(!tampered.verify(state.tree_hash_root()));
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        let state = state();
+
+        assert!(state.proof(&[Field("no_such_field")]).is_err());
+        assert!(state.proof(&[Field("validators"), Index(5)]).is_err());
+        assert!(state.proof(&[Field("slot"), Index(0)]).is_err());
+        assert!(state.proof(&[Field("block_roots"), Length]).is_err());
+        assert!(state
+            .proof(&[Field("balances"), Index(0), Field("slot")])
+            .is_err());
+        assert!(BeaconState::<MinimalConfig>::generalized_index(&[
+            Field("validators"),
+            Index(usize::MAX),
+        ])
+        .is_err());
+        assert!(concat_generalized_indices(1 << 40, 1 << 30).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use hashing::{concatenate_and_hash, ZERO_HASHES};
+use types::primitives::H256;
+
+/// Merkle tree that only rehashes the paths from changed leaves to the root.
+///
+/// Only nodes with at least one leaf below them are stored. Missing nodes are zero hashes.
+#[derive(Clone)]
+pub struct MerkleTree {
+    depth: usize,
+    // `layers[0]` contains the leaves. `layers[depth]` contains the root unless the tree is empty.
+    layers: Vec<Vec<H256>>,
+}
+
+impl MerkleTree {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            layers: vec![vec![]; depth + 1],
+        }
+    }
+
+    pub fn from_leaves(depth: usize, leaves: Vec<H256>) -> Self {
+        let mut tree = Self::new(depth);
+        let leaf_count = leaves.len();
+        tree.update(leaf_count, leaves.into_iter().enumerate().collect());
+        tree
+    }
+
+    pub fn update(&mut self, leaf_count: usize, changed_leaves: Vec<(usize, H256)>) -> H256 {
+        let old_leaf_count = self.layers[0].len();
+        let mut dirty_indices = Vec::with_capacity(changed_leaves.len() + 1);
+
+        self.layers[0].resize(leaf_count, H256::zero());
+
+        for (index, leaf) in changed_leaves {
+            self.layers[0][index] = leaf;
+            dirty_indices.push(index);
+        }
+
+        // The last node on every layer may have lost or gained a sibling.
+        if leaf_count != old_leaf_count && leaf_count > 0 {
+            dirty_indices.push(leaf_count - 1);
+        }
+
+        dirty_indices.sort_unstable();
+        dirty_indices.dedup();
+
+        for height in 0..self.depth {
+            let (lower, upper) = self.layers.split_at_mut(height + 1);
+            let children = &lower[height];
+            let parents = &mut upper[0];
+
+            parents.resize(children.len().saturating_add(1) / 2, H256::zero());
+
+            for index in &mut dirty_indices {
+                *index /= 2;
+            }
+            dirty_indices.dedup();
+
+            for &index in &dirty_indices {
+                let left = children[2 * index];
+                let right = children
+                    .get(2 * index + 1)
+                    .copied()
+                    .unwrap_or(ZERO_HASHES[height]);
+                parents[index] = concatenate_and_hash(left, right);
+            }
+        }
+
+        self.root()
+    }
+
+    pub fn root(&self) -> H256 {
+        self.node(self.depth, 0)
+    }
+
+    /// Returns the node at `index` counting from the left on the layer `height` levels above the
+    /// leaves.
+    pub fn node(&self, height: usize, index: usize) -> H256 {
+        self.layers[height]
+            .get(index)
+            .copied()
+            .unwrap_or(ZERO_HASHES[height])
+    }
+
+    /// Returns the siblings of the nodes on the path from leaf `index` to the root, bottom first.
+    pub fn branch(&self, index: usize) -> Vec<H256> {
+        (0..self.depth)
+            .map(|height| self.node(height, (index >> height) ^ 1))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::predicates::is_valid_merkle_branch;
+
+    use super::*;
+
+    #[test]
+    fn test_branch_is_valid_for_every_leaf() {
+        let leaves = (0..5).map(H256::from_low_u64_le).collect::<Vec<_>>();
+        let tree = MerkleTree::from_leaves(4, leaves.clone());
+
+        for (index, leaf) in leaves.into_iter().enumerate() {
+            assert!(is_valid_merkle_branch(
+                leaf,
+                tree.branch(index).as_slice(),
+                4,
+                index as u64,
+                tree.root(),
+            )
+            .expect("branch has the right length"));
+        }
+    }
+}
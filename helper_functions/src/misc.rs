@@ -1,17 +1,21 @@
 use core::{
+    cmp::{max, min},
     convert::{TryFrom as _, TryInto as _},
     ops::Range,
 };
 
 use anyhow::{ensure, Result};
 use bit_field::BitArray as _;
-use tree_hash::TreeHash;
+use ssz_new::{types::VariableList, SszHash};
 use typenum::Unsigned as _;
 use types::{
     beacon_state::BeaconState,
+    chain_spec::ChainSpec,
     config::Config,
-    containers::{ForkData, SigningData},
-    primitives::{Domain, DomainType, Epoch, ForkDigest, Slot, ValidatorIndex, Version, H256},
+    containers::{CompactCommittee, ForkData, SigningData},
+    primitives::{
+        Domain, DomainType, Epoch, ForkDigest, Gwei, Slot, ValidatorIndex, Version, H256,
+    },
 };
 
 use crate::error::Error;
@@ -59,23 +63,40 @@ pub fn compute_fork_digest(current_version: Version, genesis_validators_root: H2
     ForkDigest::from_slice(&root[..ForkDigest::len_bytes()])
 }
 
+// The default fork version comes from the runtime `ChainSpec` rather than `Config`, so that
+// networks sharing a preset but not a genesis fork version get different domains.
 #[must_use]
-pub fn compute_domain<C: Config>(
+pub fn compute_domain(
     domain_type: DomainType,
     fork_version: Option<&Version>,
     genesis_validators_root: Option<H256>,
+    chain_spec: &ChainSpec,
+) -> Domain {
+    compute_domain_with_fork_version(
+        domain_type,
+        fork_version
+            .copied()
+            .unwrap_or(chain_spec.genesis_fork_version),
+        genesis_validators_root.unwrap_or_else(|| H256::from([0; 32])),
+    )
+}
+
+// `accessors::get_domain` always knows the fork version and has no `ChainSpec` to pass.
+#[must_use]
+pub(crate) fn compute_domain_with_fork_version(
+    domain_type: DomainType,
+    fork_version: Version,
+    genesis_validators_root: H256,
 ) -> Domain {
     let mut domain_bytes = [0; 32];
-    let f = fork_version.copied().unwrap_or(C::GENESIS_FORK_VERSION);
-    let validators_root = genesis_validators_root.unwrap_or_else(|| H256::from([0; 32]));
-    let fork_data_root = compute_fork_data_root(f, validators_root);
+    let fork_data_root = compute_fork_data_root(fork_version, genesis_validators_root);
     domain_bytes[..4].copy_from_slice(&domain_type.to_le_bytes());
     domain_bytes[4..].copy_from_slice(&fork_data_root[..28]);
     Domain::from(domain_bytes)
 }
 
 #[must_use]
-pub fn compute_signing_root(object: &impl TreeHash, domain: Domain) -> H256 {
+pub fn compute_signing_root(object: &impl SszHash, domain: Domain) -> H256 {
     SigningData {
         object_root: object.tree_hash_root(),
         domain,
@@ -157,6 +178,18 @@ pub fn compute_proposer_index<C: Config>(
     }
 }
 
+#[must_use]
+pub fn integer_squareroot(n: u64) -> u64 {
+    // `n / 2 + n % 2` is `(n + 1) / 2` without overflowing for `u64::MAX`.
+    let mut x = n;
+    let mut y = n / 2 + n % 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
 #[must_use]
 pub fn compute_previous_slot(slot: Slot) -> Slot {
     if slot > 0 {
@@ -166,6 +199,30 @@ pub fn compute_previous_slot(slot: Slot) -> Slot {
     }
 }
 
+#[must_use]
+pub fn compute_offset_slots<C: Config>(start_slot: Slot, end_slot: Slot) -> Vec<Slot> {
+    C::SHARD_BLOCK_OFFSETS
+        .iter()
+        .map(|offset| start_slot + offset)
+        .filter(|slot| *slot < end_slot)
+        .collect()
+}
+
+#[must_use]
+pub fn compute_updated_gasprice<C: Config>(prev_gasprice: Gwei, shard_block_length: u64) -> Gwei {
+    if shard_block_length > C::TARGET_SHARD_BLOCK_SIZE {
+        let delta = prev_gasprice * (shard_block_length - C::TARGET_SHARD_BLOCK_SIZE)
+            / C::TARGET_SHARD_BLOCK_SIZE
+            / C::GASPRICE_ADJUSTMENT_COEFFICIENT;
+        min(prev_gasprice + delta, C::MAX_GASPRICE)
+    } else {
+        let delta = prev_gasprice * (C::TARGET_SHARD_BLOCK_SIZE - shard_block_length)
+            / C::TARGET_SHARD_BLOCK_SIZE
+            / C::GASPRICE_ADJUSTMENT_COEFFICIENT;
+        max(prev_gasprice, C::MIN_GASPRICE + delta) - delta
+    }
+}
+
 #[must_use]
 pub fn pack_compact_validator(
     index: ValidatorIndex,
@@ -175,6 +232,45 @@ pub fn pack_compact_validator(
     return (index << 16) + ((slashed as u64) << 15) + balance_in_increments;
 }
 
+#[must_use]
+pub fn compute_committee_source_epoch(epoch: Epoch, period: u64) -> Epoch {
+    let source_epoch = epoch - epoch % period;
+    if source_epoch >= period {
+        // `period` epochs lookahead
+        source_epoch - period
+    } else {
+        source_epoch
+    }
+}
+
+pub fn committee_to_compact_committee<C: Config>(
+    state: &BeaconState<C>,
+    committee: &[ValidatorIndex],
+) -> Result<CompactCommittee<C>> {
+    let mut pubkeys = Vec::with_capacity(committee.len());
+    let mut compact_validators = Vec::with_capacity(committee.len());
+
+    for validator_index in committee.iter().copied() {
+        let validator = state
+            .validators
+            .get(usize::try_from(validator_index)?)
+            .ok_or(Error::IndexOutOfBounds)?;
+
+        pubkeys.push(validator.pubkey);
+        compact_validators.push(pack_compact_validator(
+            validator_index,
+            validator.slashed,
+            validator.effective_balance / C::EFFECTIVE_BALANCE_INCREMENT,
+        ));
+    }
+
+    Ok(CompactCommittee {
+        pubkeys: VariableList::new(pubkeys).map_err(|_| Error::CommitteeTooLarge)?,
+        compact_validators: VariableList::new(compact_validators)
+            .map_err(|_| Error::CommitteeTooLarge)?,
+    })
+}
+
 #[must_use]
 pub fn compute_committee<C: Config>(
     indices: Vec<ValidatorIndex>,
@@ -202,6 +298,7 @@ mod tests {
 
     use bls::PublicKeyBytes;
     use hex_literal::hex;
+    use proptest::prelude::*;
     use types::{config::MinimalConfig, consts::FAR_FUTURE_EPOCH, containers::Validator};
 
     use super::*;
@@ -227,12 +324,30 @@ mod tests {
 
     #[test]
     fn test_compute_domain() {
-        let domain = compute_domain::<MinimalConfig>(1, Some(&hex!("00000001").into()), None);
+        let chain_spec = ChainSpec::from_config::<MinimalConfig>();
+        let domain = compute_domain(1, Some(&hex!("00000001").into()), None, &chain_spec);
         assert_eq!(
             domain,
             hex!("0100000018ae4ccbda9538839d79bb18ca09e23e24ae8c1550f56cbb3d84b053").into()
         );
     }
+
+    #[test]
+    fn test_compute_domain_uses_chain_spec_genesis_fork_version() {
+        let chain_spec = ChainSpec {
+            genesis_fork_version: hex!("00000002").into(),
+            ..ChainSpec::from_config::<MinimalConfig>()
+        };
+
+        assert_eq!(
+            compute_domain(1, None, None, &chain_spec),
+            compute_domain(1, Some(&hex!("00000002").into()), None, &chain_spec),
+        );
+        assert_ne!(
+            compute_domain(1, None, None, &chain_spec),
+            compute_domain(1, None, None, &ChainSpec::from_config::<MinimalConfig>()),
+        );
+    }
     #[test]
     fn test_compute_shuffled_index() {
         let test_indices_length = 25;
@@ -297,6 +412,19 @@ mod tests {
         assert_eq!(compute_previous_slot(a), 0);
     }
 
+    #[test]
+    fn test_compute_offset_slots() {
+        assert!(compute_offset_slots::<MinimalConfig>(10, 11).is_empty());
+        assert_eq!(
+            compute_offset_slots::<MinimalConfig>(10, 14),
+            vec![11, 12, 13]
+        );
+        assert_eq!(
+            compute_offset_slots::<MinimalConfig>(10, 40),
+            vec![11, 12, 13, 15, 18, 23, 31],
+        );
+    }
+
     #[test]
     fn test_pack_compact_validator() {
         let index: ValidatorIndex = 54321;
@@ -308,4 +436,123 @@ mod tests {
             3560026169
         );
     }
+
+    #[test]
+    fn test_compute_committee_source_epoch() {
+        assert_eq!(compute_committee_source_epoch(0, 64), 0);
+        assert_eq!(compute_committee_source_epoch(63, 64), 0);
+        assert_eq!(compute_committee_source_epoch(64, 64), 0);
+        assert_eq!(compute_committee_source_epoch(127, 64), 0);
+        assert_eq!(compute_committee_source_epoch(128, 64), 64);
+        assert_eq!(compute_committee_source_epoch(200, 64), 128);
+    }
+
+    #[test]
+    fn test_committee_to_compact_committee() {
+        let validator = |byte, slashed, effective_balance| Validator {
+            pubkey: PublicKeyBytes::repeat_byte(byte),
+            slashed,
+            effective_balance,
+            ..Validator::default()
+        };
+        let state = BeaconState::<MinimalConfig> {
+            validators: Arc::new(
+                vec![
+                    validator(1, false, 32_000_000_000),
+                    validator(2, true, 16_500_000_000),
+                    validator(3, false, 31_000_000_000),
+                ]
+                .into(),
+            ),
+            ..BeaconState::default()
+        };
+
+        let compact_committee =
+            committee_to_compact_committee(&state, &[2, 1]).expect("committee is valid");
+
+        assert_eq!(
+            compact_committee.pubkeys[..],
+            [
+                PublicKeyBytes::repeat_byte(3),
+                PublicKeyBytes::repeat_byte(2)
+            ],
+        );
+        assert_eq!(
+            compact_committee.compact_validators[..],
+            [
+                pack_compact_validator(2, false, 31),
+                pack_compact_validator(1, true, 16),
+            ],
+        );
+        assert!(committee_to_compact_committee(&state, &[3]).is_err());
+    }
+
+    #[test]
+    fn test_gasprice_unchanged_at_target_size() {
+        let target = MinimalConfig::TARGET_SHARD_BLOCK_SIZE;
+        assert_eq!(compute_updated_gasprice::<MinimalConfig>(100, target), 100);
+    }
+
+    #[test]
+    fn test_gasprice_of_full_and_empty_blocks() {
+        let max_size = <MinimalConfig as Config>::MaxShardBlockSize::U64;
+        // (2^20 - 2^18) / 2^18 / 8 = 3 / 8 of the previous price.
+        assert_eq!(
+            compute_updated_gasprice::<MinimalConfig>(800, max_size),
+            1100
+        );
+        // 2^18 / 2^18 / 8 = 1 / 8 of the previous price.
+        assert_eq!(compute_updated_gasprice::<MinimalConfig>(800, 0), 700);
+        assert_eq!(
+            compute_updated_gasprice::<MinimalConfig>(MinimalConfig::MIN_GASPRICE, 0),
+            MinimalConfig::MIN_GASPRICE,
+        );
+        assert_eq!(
+            compute_updated_gasprice::<MinimalConfig>(MinimalConfig::MAX_GASPRICE, max_size),
+            MinimalConfig::MAX_GASPRICE,
+        );
+    }
+
+    #[test]
+    fn test_integer_squareroot() {
+        assert_eq!(integer_squareroot(0), 0);
+        assert_eq!(integer_squareroot(15), 3);
+        assert_eq!(integer_squareroot(16), 4);
+        assert_eq!(integer_squareroot(u64::MAX), u64::from(u32::MAX));
+    }
+
+    proptest! {
+        #[test]
+        fn gasprice_stays_within_bounds(
+            prev_gasprice in MinimalConfig::MIN_GASPRICE..=MinimalConfig::MAX_GASPRICE,
+            length in 0..=<MinimalConfig as Config>::MaxShardBlockSize::U64,
+        ) {
+            let gasprice = compute_updated_gasprice::<MinimalConfig>(prev_gasprice, length);
+            prop_assert!(MinimalConfig::MIN_GASPRICE <= gasprice);
+            prop_assert!(gasprice <= MinimalConfig::MAX_GASPRICE);
+        }
+
+        #[test]
+        fn gasprice_rises_above_target_size(
+            prev_gasprice in MinimalConfig::MIN_GASPRICE..=MinimalConfig::MAX_GASPRICE,
+            length in MinimalConfig::TARGET_SHARD_BLOCK_SIZE..=<MinimalConfig as Config>::MaxShardBlockSize::U64,
+        ) {
+            prop_assert!(compute_updated_gasprice::<MinimalConfig>(prev_gasprice, length) >= prev_gasprice);
+        }
+
+        #[test]
+        fn gasprice_falls_below_target_size(
+            prev_gasprice in MinimalConfig::MIN_GASPRICE..=MinimalConfig::MAX_GASPRICE,
+            length in 0..=MinimalConfig::TARGET_SHARD_BLOCK_SIZE,
+        ) {
+            prop_assert!(compute_updated_gasprice::<MinimalConfig>(prev_gasprice, length) <= prev_gasprice);
+        }
+
+        #[test]
+        fn integer_squareroot_is_floor_of_square_root(n in any::<u64>()) {
+            let root = u128::from(integer_squareroot(n));
+            prop_assert!(root * root <= u128::from(n));
+            prop_assert!((root + 1) * (root + 1) > u128::from(n));
+        }
+    }
 }
@@ -5,6 +5,7 @@ use typenum::Unsigned as _;
 use types::{
     arc_ext::ArcExt as _,
     beacon_state::BeaconState,
+    chain_spec::ChainSpec,
     config::Config,
     consts::FAR_FUTURE_EPOCH,
     primitives::{Epoch, Gwei, ValidatorIndex},
@@ -46,9 +47,10 @@ pub fn slash_validator<C: Config>(
     state: &mut BeaconState<C>,
     slashed_index: ValidatorIndex,
     whistleblower_index: Option<ValidatorIndex>,
+    chain_spec: &ChainSpec,
 ) -> Result<()> {
     let epoch: Epoch = get_current_epoch(state);
-    initiate_validator_exit(state, slashed_index)?;
+    initiate_validator_exit(state, slashed_index, chain_spec)?;
     let sl_index = usize::try_from(slashed_index)?;
     let validator = &mut state.validators.make_mut()[sl_index];
     validator.slashed = true;
@@ -57,7 +59,7 @@ pub fn slash_validator<C: Config>(
         cmp::max(validator.withdrawable_epoch, epoch + epochs_per_slashings);
     let effective_balance = validator.effective_balance;
     let slashings_index = usize::try_from(epoch % epochs_per_slashings)?;
-    state.slashings[slashings_index] += effective_balance;
+    state.slashings.make_mut()[slashings_index] += effective_balance;
     let decr = validator.effective_balance / C::MIN_SLASHING_PENALTY_QUOTIENT;
     decrease_balance(state, slashed_index, decr)?;
 
@@ -81,6 +83,7 @@ pub fn slash_validator<C: Config>(
 pub fn initiate_validator_exit<C: Config>(
     state: &mut BeaconState<C>,
     index: ValidatorIndex,
+    chain_spec: &ChainSpec,
 ) -> Result<()> {
     let validator_index = usize::try_from(index)?;
     let exit_epoch = state.validators[validator_index].exit_epoch;
@@ -116,14 +119,15 @@ pub fn initiate_validator_exit<C: Config>(
             exit_queue_churn += 1;
         }
     }
-    if exit_queue_churn >= get_validator_churn_limit(state)? {
+    if exit_queue_churn >= get_validator_churn_limit(state, chain_spec)? {
         exit_queue_epoch += 1;
     }
 
     // change validator's exit epoch in the beacon chain
     let validator = &mut state.validators.make_mut()[validator_index];
     validator.exit_epoch = exit_queue_epoch;
-    validator.withdrawable_epoch = exit_queue_epoch + C::MIN_VALIDATOR_WITHDRAWABILITY_DELAY;
+    validator.withdrawable_epoch =
+        exit_queue_epoch + chain_spec.min_validator_withdrawability_delay;
     Ok(())
 }
 
@@ -166,11 +170,14 @@ mod tests {
             state.validators = Arc::new(vec![default_validator()].into());
             state.balances = Arc::new(vec![100].into());
 
+            let chain_spec = ChainSpec::from_config::<MainnetConfig>();
+
             let mut state_copy = state.clone();
-            initiate_validator_exit(&mut state_copy, 0)
+            initiate_validator_exit(&mut state_copy, 0, &chain_spec)
                 .expect("Expected successful initiate_validator_exit");
 
-            slash_validator(&mut state, 0, None).expect("slash_validator should succeed");
+            slash_validator(&mut state, 0, None, &chain_spec)
+                .expect("slash_validator should succeed");
 
             assert_eq!(
                 state_copy.validators[0].exit_epoch,
@@ -208,13 +215,14 @@ mod tests {
         };
 
         state.validators = Arc::new(vec![val1, val2].into());
+        let chain_spec = ChainSpec::from_config::<MinimalConfig>();
         // 1 - exit epoch is already set and should remain the same
         let expected_exit_epoch: Epoch = 4;
-        initiate_validator_exit(&mut state, 0).expect("");
+        initiate_validator_exit(&mut state, 0, &chain_spec).expect("");
         assert_eq!(expected_exit_epoch, state.validators[0].exit_epoch);
         assert_ne!(5, state.validators[0].exit_epoch);
         // 2 - exit epoch is FAR_FUTURE epoch and should be set to the lowest possible value
-        initiate_validator_exit(&mut state, 1).expect("");
+        initiate_validator_exit(&mut state, 1, &chain_spec).expect("");
         assert_ne!(FAR_FUTURE_EPOCH, state.validators[1].exit_epoch);
         assert_eq!(5, state.validators[1].exit_epoch);
     }
@@ -130,7 +130,7 @@ pub fn is_on_time_attestation<C: Config>(
 }
 pub fn is_winning_attestation<C: Config>(
     state: &BeaconState<C>,
-    attestation: PendingAttestation<C>,
+    attestation: &PendingAttestation<C>,
     committee_index: CommitteeIndex,
     winning_root: Root,
 ) -> bool {
@@ -0,0 +1,491 @@
+use core::{marker::PhantomData, ops::DerefMut};
+use std::sync::{Arc, Weak};
+
+use ssz_new::{
+    merkle_root,
+    merkleization::{self, chunk_count, packing_factor},
+    mix_in_length,
+    types::{FixedVector, VariableList},
+    SszHash, BYTES_PER_CHUNK,
+};
+use typenum::Unsigned;
+use types::{
+    arc_ext::ArcExt as _,
+    beacon_state::{BeaconState, ExposedDerivedSecrets},
+    config::Config,
+    containers::{PendingAttestation, ShardState, Validator},
+    primitives::{Gwei, OnlineEpochs, ValidatorIndex, H256},
+};
+
+use crate::merkle_tree::MerkleTree;
+
+/// Cache for the root of a homogeneous sequence (an SSZ vector or the contents of an SSZ list)
+/// behind an [`Arc`].
+///
+/// The sequence cannot change without going through [`Arc::make_mut`], which moves it to a new
+/// allocation when a [`Weak`] pointing to it exists. The cache holds one to the last sequence it
+/// has seen. Writes made through [`SequenceCache::set`] are recorded as dirty chunks, so only the
+/// paths from those chunks to the root are rehashed. Any other change to the sequence makes the
+/// next update rebuild the tree from scratch. Sequences shared with the last one seen are free.
+#[derive(Clone)]
+struct SequenceCache<S, T> {
+    shared: Weak<S>,
+    root: H256,
+    dirty_chunks: Vec<usize>,
+    tree: MerkleTree,
+    phantom: PhantomData<T>,
+}
+
+impl<S: Clone + DerefMut<Target = [T]>, T: SszHash> SequenceCache<S, T> {
+    fn new(limit: usize) -> Self {
+        Self {
+            shared: Weak::new(),
+            root: H256::zero(),
+            dirty_chunks: vec![],
+            tree: MerkleTree::new(merkleization::depth::<T>(limit)),
+            phantom: PhantomData,
+        }
+    }
+
+    fn set(&mut self, shared: &mut Arc<S>, index: usize, value: T) {
+        let tracked = self.is_current(shared);
+
+        shared.make_mut()[index] = value;
+
+        if tracked {
+            self.dirty_chunks.push(index / packing_factor::<T>());
+            self.shared = Arc::downgrade(shared);
+        }
+    }
+
+    fn vector_root(&mut self, shared: &Arc<S>) -> H256 {
+        let chunk_count = chunk_count(shared.len(), packing_factor::<T>());
+
+        if self.is_current(shared) {
+            if !self.dirty_chunks.is_empty() {
+                let changed_leaves = self
+                    .dirty_chunks
+                    .drain(..)
+                    .map(|chunk_index| (chunk_index, merkleization::chunk(shared, chunk_index)))
+                    .collect();
+                self.root = self.tree.update(chunk_count, changed_leaves);
+            }
+        } else {
+            let leaves = merkleization::chunks(shared)
+                .into_iter()
+                .enumerate()
+                .collect();
+            self.root = self.tree.update(chunk_count, leaves);
+            self.dirty_chunks.clear();
+            self.shared = Arc::downgrade(shared);
+        }
+
+        self.root
+    }
+
+    fn list_root(&mut self, shared: &Arc<S>) -> H256 {
+        mix_in_length(self.vector_root(shared), shared.len())
+    }
+
+    fn is_current(&self, shared: &Arc<S>) -> bool {
+        self.shared
+            .upgrade()
+            .map(|cached| Arc::ptr_eq(&cached, shared))
+            .unwrap_or_default()
+    }
+}
+
+/// Persistent cache for [`BeaconState`] roots.
+///
+/// Meant to be kept alongside a state that is modified and hashed repeatedly, such as during slot
+/// processing. Writes that happen on every slot should go through the setters so that hashing the
+/// state afterwards only rehashes the written elements. Small fields stored inline are rehashed in
+/// full every time. A clone of the cache can be used with a clone of the state. Using a single cache
+/// for unrelated states is correct but slow.
+#[derive(Clone)]
+pub struct BeaconStateTreeHashCache<C: Config> {
+    block_roots: SequenceCache<FixedVector<H256, C::SlotsPerHistoricalRoot>, H256>,
+    state_roots: SequenceCache<FixedVector<H256, C::SlotsPerHistoricalRoot>, H256>,
+    validators: SequenceCache<VariableList<Validator, C::ValidatorRegistryLimit>, Validator>,
+    balances: SequenceCache<VariableList<Gwei, C::ValidatorRegistryLimit>, Gwei>,
+    randao_mixes: SequenceCache<FixedVector<H256, C::EpochsPerHistoricalVector>, H256>,
+    slashings: SequenceCache<FixedVector<Gwei, C::EpochsPerSlashingsVector>, Gwei>,
+    previous_epoch_attestations: SequenceCache<
+        VariableList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
+        PendingAttestation<C>,
+    >,
+    current_epoch_attestations: SequenceCache<
+        VariableList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
+        PendingAttestation<C>,
+    >,
+    shard_states: SequenceCache<VariableList<ShardState, C::MaxShards>, ShardState>,
+    online_countdown:
+        SequenceCache<VariableList<OnlineEpochs, C::ValidatorRegistryLimit>, OnlineEpochs>,
+    exposed_derived_secrets: SequenceCache<
+        ExposedDerivedSecrets<C>,
+        VariableList<ValidatorIndex, C::MaxEarlyDerivedSecretRevealsBySlots>,
+    >,
+}
+
+impl<C: Config> Default for BeaconStateTreeHashCache<C> {
+    fn default() -> Self {
+        Self {
+            block_roots: SequenceCache::new(C::SlotsPerHistoricalRoot::USIZE),
+            state_roots: SequenceCache::new(C::SlotsPerHistoricalRoot::USIZE),
+            validators: SequenceCache::new(C::ValidatorRegistryLimit::USIZE),
+            balances: SequenceCache::new(C::ValidatorRegistryLimit::USIZE),
+            randao_mixes: SequenceCache::new(C::EpochsPerHistoricalVector::USIZE),
+            slashings: SequenceCache::new(C::EpochsPerSlashingsVector::USIZE),
+            previous_epoch_attestations: SequenceCache::new(C::MaxAttestationsPerEpoch::USIZE),
+            current_epoch_attestations: SequenceCache::new(C::MaxAttestationsPerEpoch::USIZE),
+            shard_states: SequenceCache::new(C::MaxShards::USIZE),
+            online_countdown: SequenceCache::new(C::ValidatorRegistryLimit::USIZE),
+            exposed_derived_secrets: SequenceCache::new(
+                C::EarlyDerivedSecretPenaltyMaxFutureEpochs::USIZE,
+            ),
+        }
+    }
+}
+
+impl<C: Config> BeaconStateTreeHashCache<C> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_block_root(&mut self, state: &mut BeaconState<C>, index: usize, root: H256) {
+        self.block_roots.set(&mut state.block_roots, index, root);
+    }
+
+    pub fn set_state_root(&mut self, state: &mut BeaconState<C>, index: usize, root: H256) {
+        self.state_roots.set(&mut state.state_roots, index, root);
+    }
+
+    pub fn tree_hash_root(&mut self, state: &BeaconState<C>) -> H256 {
+        let BeaconState {
+            genesis_time,
+            genesis_validators_root,
+            slot,
+            fork,
+            latest_block_header,
+            block_roots,
+            state_roots,
+            historical_roots,
+            eth1_data,
+            eth1_data_votes,
+            eth1_deposit_index,
+            validators,
+            balances,
+            randao_mixes,
+            slashings,
+            previous_epoch_attestations,
+            current_epoch_attestations,
+            justification_bits,
+            previous_justified_checkpoint,
+            current_justified_checkpoint,
+            finalized_checkpoint,
+            current_epoch_start_shard,
+            shard_states,
+            online_countdown,
+            current_light_committee,
+            next_light_committee,
+            exposed_derived_secrets,
+            custody_chunk_challenge_records,
+            custody_chunk_challenge_index,
+        } = state;
+
+        let field_roots = [
+            genesis_time.tree_hash_root(),
+            genesis_validators_root.tree_hash_root(),
+            slot.tree_hash_root(),
+            fork.tree_hash_root(),
+            latest_block_header.tree_hash_root(),
+            self.block_roots.vector_root(block_roots),
+            self.state_roots.vector_root(state_roots),
+            historical_roots.tree_hash_root(),
+            eth1_data.tree_hash_root(),
+            eth1_data_votes.tree_hash_root(),
+            eth1_deposit_index.tree_hash_root(),
+            self.validators.list_root(validators),
+            self.balances.list_root(balances),
+            self.randao_mixes.vector_root(randao_mixes),
+            self.slashings.vector_root(slashings),
+            self.previous_epoch_attestations
+                .list_root(previous_epoch_attestations),
+            self.current_epoch_attestations
+                .list_root(current_epoch_attestations),
+            justification_bits.tree_hash_root(),
+            previous_justified_checkpoint.tree_hash_root(),
+            current_justified_checkpoint.tree_hash_root(),
+            finalized_checkpoint.tree_hash_root(),
+            current_epoch_start_shard.tree_hash_root(),
+            self.shard_states.list_root(shard_states),
+            self.online_countdown.list_root(online_countdown),
+            current_light_committee.tree_hash_root(),
+            next_light_committee.tree_hash_root(),
+            self.exposed_derived_secrets
+                .vector_root(exposed_derived_secrets),
+            custody_chunk_challenge_records.tree_hash_root(),
+            custody_chunk_challenge_index.tree_hash_root(),
+        ];
+
+        let bytes = field_roots
+            .iter()
+            .flat_map(|root| root.as_bytes())
+            .copied()
+            .collect::<Vec<_>>();
+
+        merkle_root(bytes.as_slice(), bytes.len() / BYTES_PER_CHUNK)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+    use typenum::{U1024, U16, U5};
+    use types::{
+        config::MinimalConfig,
+        containers::{AttestationData, Validator},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_empty_sequences() {
+        let packed = Arc::new(VariableList::<u64, U1024>::from(vec![]));
+        let composite = Arc::new(VariableList::<H256, U5>::from(vec![]));
+
+        assert_eq!(
+            SequenceCache::new(1024).list_root(&packed),
+            packed.tree_hash_root(),
+        );
+        assert_eq!(
+            SequenceCache::new(5).list_root(&composite),
+            composite.tree_hash_root(),
+        );
+    }
+
+    #[test]
+    fn test_set_only_rehashes_written_chunks() {
+        let mut cache = SequenceCache::new(16);
+        let mut vector = Arc::new(FixedVector::<H256, U16>::from_elem(H256::zero()));
+
+        assert_eq!(cache.vector_root(&vector), vector.tree_hash_root());
+
+        cache.set(&mut vector, 3, H256::repeat_byte(3));
+        cache.set(&mut vector, 15, H256::repeat_byte(15));
+
+        assert_eq!(cache.dirty_chunks, [3, 15]);
+        assert_eq!(cache.vector_root(&vector), vector.tree_hash_root());
+        assert!(cache.dirty_chunks.is_empty());
+    }
+
+    #[test]
+    fn test_untracked_write_rebuilds_tree() {
+        let mut cache = SequenceCache::new(16);
+        let mut vector = Arc::new(FixedVector::<H256, U16>::from_elem(H256::zero()));
+
+        assert_eq!(cache.vector_root(&vector), vector.tree_hash_root());
+
+        vector.make_mut()[7] = H256::repeat_byte(7);
+        cache.set(&mut vector, 3, H256::repeat_byte(3));
+
+        assert!(cache.dirty_chunks.is_empty());
+        assert_eq!(cache.vector_root(&vector), vector.tree_hash_root());
+    }
+
+    #[test]
+    fn test_beacon_state_root_after_shrinking_lists() {
+        let mut cache = BeaconStateTreeHashCache::new();
+        let mut state = BeaconState::<MinimalConfig> {
+            balances: Arc::new((0..13).collect::<Vec<_>>().into()),
+            online_countdown: Arc::new(VariableList::from(vec![8; 70])),
+            ..BeaconState::default()
+        };
+
+        assert_eq!(cache.tree_hash_root(&state), state.tree_hash_root());
+
+        state.balances = Arc::new((0..12).collect::<Vec<_>>().into());
+        state.online_countdown = Arc::new(VariableList::from(vec![8; 33]));
+
+        assert_eq!(cache.tree_hash_root(&state), state.tree_hash_root());
+
+        state.balances = Arc::new(vec![].into());
+        state.online_countdown = Arc::new(VariableList::from(vec![]));
+
+        assert_eq!(cache.tree_hash_root(&state), state.tree_hash_root());
+    }
+
+    #[derive(Debug, Clone)]
+    enum Change {
+        PushValidator(u64),
+        SetBalance(usize, Gwei),
+        PopBalance,
+        SetBlockRoot(usize, u8),
+        CacheBlockRoot(usize, u8),
+        CacheStateRoot(usize, u8),
+        PushAttestation(u64),
+        RotateAttestations,
+        SetOnlineCountdown(usize, OnlineEpochs),
+        PushOnlineCountdown,
+        SetSlashing(usize, Gwei),
+    }
+
+    fn change() -> impl Strategy<Value = Change> {
+        prop_oneof![
+            any::<u64>().prop_map(Change::PushValidator),
+            (any::<usize>(), any::<Gwei>())
+                .prop_map(|(index, balance)| { Change::SetBalance(index, balance) }),
+            Just(Change::PopBalance),
+            (any::<usize>(), any::<u8>())
+                .prop_map(|(index, byte)| { Change::SetBlockRoot(index, byte) }),
+            (any::<usize>(), any::<u8>())
+                .prop_map(|(index, byte)| { Change::CacheBlockRoot(index, byte) }),
+            (any::<usize>(), any::<u8>())
+                .prop_map(|(index, byte)| { Change::CacheStateRoot(index, byte) }),
+            any::<u64>().prop_map(Change::PushAttestation),
+            Just(Change::RotateAttestations),
+            (any::<usize>(), any::<OnlineEpochs>())
+                .prop_map(|(index, countdown)| { Change::SetOnlineCountdown(index, countdown) }),
+            Just(Change::PushOnlineCountdown),
+            (any::<usize>(), any::<Gwei>())
+                .prop_map(|(index, amount)| { Change::SetSlashing(index, amount) }),
+        ]
+    }
+
+    fn apply(
+        cache: &mut BeaconStateTreeHashCache<MinimalConfig>,
+        state: &mut BeaconState<MinimalConfig>,
+        change: Change,
+    ) {
+        match change {
+            Change::PushValidator(effective_balance) => {
+                state
+                    .validators
+                    .make_mut()
+                    .push(Validator {
+                        effective_balance,
+                        ..Validator::default()
+                    })
+                    .expect("the registry limit is not reached in tests");
+                state
+                    .balances
+                    .make_mut()
+                    .push(effective_balance)
+                    .expect("the registry limit is not reached in tests");
+            }
+            Change::SetBalance(index, balance) => {
+                let balances = state.balances.make_mut();
+                if !balances.is_empty() {
+                    let length = balances.len();
+                    balances[index % length] = balance;
+                }
+            }
+            Change::PopBalance => {
+                let mut balances = state.balances.to_vec();
+                balances.pop();
+                state.balances = Arc::new(balances.into());
+            }
+            Change::SetBlockRoot(index, byte) => {
+                let length = state.block_roots.len();
+                state.block_roots.make_mut()[index % length] = H256::repeat_byte(byte);
+            }
+            Change::CacheBlockRoot(index, byte) => {
+                let length = state.block_roots.len();
+                cache.set_block_root(state, index % length, H256::repeat_byte(byte));
+            }
+            Change::CacheStateRoot(index, byte) => {
+                let length = state.state_roots.len();
+                cache.set_state_root(state, index % length, H256::repeat_byte(byte));
+            }
+            Change::PushAttestation(slot) => {
+                let attestation = PendingAttestation {
+                    aggregation_bits: Default::default(),
+                    data: AttestationData {
+                        slot,
+                        ..AttestationData::default()
+                    },
+                    inclusion_delay: 1,
+                    proposer_index: 0,
+                    crosslink_success: false,
+                };
+                // Ignore the error when the list is full.
+                let _ = state
+                    .current_epoch_attestations
+                    .make_mut()
+                    .push(attestation);
+            }
+            Change::RotateAttestations => {
+                state.previous_epoch_attestations = core::mem::replace(
+                    &mut state.current_epoch_attestations,
+                    Arc::new(VariableList::from(vec![])),
+                );
+            }
+            Change::SetOnlineCountdown(index, countdown) => {
+                let online_countdown = state.online_countdown.make_mut();
+                if !online_countdown.is_empty() {
+                    let length = online_countdown.len();
+                    online_countdown[index % length] = countdown;
+                }
+            }
+            Change::PushOnlineCountdown => {
+                state
+                    .online_countdown
+                    .make_mut()
+                    .push(MinimalConfig::ONLINE_PERIOD)
+                    .expect("the registry limit is not reached in tests");
+            }
+            Change::SetSlashing(index, amount) => {
+                let length = state.slashings.len();
+                state.slashings.make_mut()[index % length] = amount;
+            }
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn test_cached_root_matches_full_rehash(changes in vec(vec(change(), 0..8), 1..8)) {
+            let mut cache = BeaconStateTreeHashCache::new();
+            let mut state = BeaconState::<MinimalConfig>::default();
+
+            for batch in changes {
+                // Keep the previous state alive to make `Arc::make_mut` copy shared fields.
+                let previous_state = state.clone();
+                let previous_root = cache.tree_hash_root(&previous_state);
+
+                for change in batch {
+                    apply(&mut cache, &mut state, change);
+                }
+
+                prop_assert_eq!(cache.tree_hash_root(&state), state.tree_hash_root());
+                prop_assert_eq!(previous_state.tree_hash_root(), previous_root);
+            }
+        }
+
+        #[test]
+        fn test_sequence_cache_matches_list_root(
+            length in 0_usize..100,
+            batches in vec(vec((any::<usize>(), any::<u16>()), 0..40), 1..8),
+        ) {
+            let mut packed_cache = SequenceCache::new(1024);
+            let mut composite_cache = SequenceCache::new(1024);
+            let mut packed = Arc::new(VariableList::<u16, U1024>::from(vec![0; length]));
+            let mut composite = Arc::new(VariableList::<H256, U1024>::from(vec![H256::zero(); length]));
+
+            for batch in batches {
+                if length > 0 {
+                    for (index, value) in batch {
+                        let root = H256::from_low_u64_le(u64::from(value));
+                        packed_cache.set(&mut packed, index % length, value);
+                        composite_cache.set(&mut composite, index % length, root);
+                    }
+                }
+
+                prop_assert_eq!(packed_cache.list_root(&packed), packed.tree_hash_root());
+                prop_assert_eq!(composite_cache.list_root(&composite), composite.tree_hash_root());
+            }
+        }
+    }
+}
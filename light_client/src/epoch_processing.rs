@@ -0,0 +1,67 @@
+use anyhow::Result;
+use helper_functions::{
+    accessors::get_light_client_committee,
+    misc::{committee_to_compact_committee, compute_epoch_at_slot},
+};
+use typenum::Unsigned as _;
+use types::{beacon_state::BeaconState, config::Config};
+
+pub fn process_light_client_committee_updates<C: Config>(state: &mut BeaconState<C>) -> Result<()> {
+    let next_epoch = compute_epoch_at_slot::<C>(state.slot + C::SlotsPerEpoch::U64);
+
+    if next_epoch % C::LIGHT_CLIENT_COMMITTEE_PERIOD == 0 {
+        state.current_light_committee = state.next_light_committee.clone();
+        let new_committee =
+            get_light_client_committee(state, next_epoch + C::LIGHT_CLIENT_COMMITTEE_PERIOD)?;
+        state.next_light_committee = committee_to_compact_committee(state, &new_committee)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use types::config::MinimalConfig;
+
+    use crate::tests::state;
+
+    use super::*;
+
+    #[test]
+    fn test_process_light_client_committee_updates_at_period_boundary() {
+        let mut state = state();
+        // The next epoch is the start of the third light client committee period.
+        state.slot =
+            2 * MinimalConfig::LIGHT_CLIENT_COMMITTEE_PERIOD * MinimalConfig::SLOTS_PER_EPOCH - 1;
+        let previous_next_committee =
+            committee_to_compact_committee(&state, &[4, 5]).expect("validators exist");
+        state.next_light_committee = previous_next_committee.clone();
+
+        process_light_client_committee_updates(&mut state).expect("state has active validators");
+
+        let expected_committee =
+            get_light_client_committee(&state, 3 * MinimalConfig::LIGHT_CLIENT_COMMITTEE_PERIOD)
+                .expect("state has active validators");
+        assert_eq!(state.current_light_committee, previous_next_committee);
+        assert_eq!(
+            state.next_light_committee,
+            committee_to_compact_committee(&state, &expected_committee)
+                .expect("committee members exist"),
+        );
+        assert!(!state.next_light_committee.pubkeys.is_empty());
+    }
+
+    #[test]
+    fn test_process_light_client_committee_updates_within_period() {
+        let mut state = state();
+        state.slot =
+            2 * MinimalConfig::LIGHT_CLIENT_COMMITTEE_PERIOD * MinimalConfig::SLOTS_PER_EPOCH;
+        state.next_light_committee =
+            committee_to_compact_committee(&state, &[4, 5]).expect("validators exist");
+        let expected_state = state.clone();
+
+        process_light_client_committee_updates(&mut state).expect("state has active validators");
+
+        assert_eq!(state, expected_state);
+    }
+}
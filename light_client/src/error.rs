@@ -0,0 +1,10 @@
+use thiserror::Error;
+use types::primitives::ValidatorIndex;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unknown validator ({index})")]
+    UnknownValidator { index: ValidatorIndex },
+    #[error("light client signature is invalid")]
+    InvalidLightClientSignature {},
+}
@@ -0,0 +1,290 @@
+pub mod epoch_processing;
+mod error;
+
+use core::convert::TryFrom as _;
+
+use anyhow::{ensure, Result};
+use error::Error;
+use helper_functions::{
+    accessors::{
+        get_base_reward, get_beacon_proposer_index, get_block_root_at_slot, get_current_epoch,
+        get_domain, get_light_client_committee,
+    },
+    crypto::optional_fast_aggregate_verify,
+    misc::{compute_epoch_at_slot, compute_previous_slot, compute_signing_root},
+    mutators::increase_balance,
+};
+use types::{beacon_state::BeaconState, config::Config, containers::BeaconBlockBody};
+
+pub fn process_light_client_aggregate<C: Config>(
+    state: &mut BeaconState<C>,
+    block_body: &BeaconBlockBody<C>,
+    verify_signatures: bool,
+) -> Result<()> {
+    let committee = get_light_client_committee(state, get_current_epoch(state))?;
+    let previous_slot = compute_previous_slot(state.slot);
+    let previous_block_root = get_block_root_at_slot(state, previous_slot)?;
+
+    let mut total_reward = 0;
+    let mut signer_pubkeys = vec![];
+
+    for (participant_index, participated) in committee
+        .into_iter()
+        .zip(block_body.light_client_bits.iter())
+    {
+        if !participated {
+            continue;
+        }
+
+        let participant = usize::try_from(participant_index)
+            .ok()
+            .and_then(|index| state.validators.get(index))
+            .copied()
+            .ok_or(Error::UnknownValidator {
+                index: participant_index,
+            })?;
+
+        signer_pubkeys.push(participant.pubkey);
+
+        if !participant.slashed {
+            let base_reward = get_base_reward(state, participant_index)?;
+            increase_balance(state, participant_index, base_reward)?;
+            total_reward += base_reward;
+        }
+    }
+
+    let proposer_index = get_beacon_proposer_index(state)?;
+    increase_balance(
+        state,
+        proposer_index,
+        total_reward / C::PROPOSER_REWARD_QUOTIENT,
+    )?;
+
+    if verify_signatures {
+        let domain = get_domain(
+            state,
+            C::DOMAIN_LIGHT_CLIENT,
+            Some(compute_epoch_at_slot::<C>(previous_slot)),
+        );
+        let signing_root = compute_signing_root(&previous_block_root, domain);
+
+        ensure!(
+            optional_fast_aggregate_verify(
+                signer_pubkeys,
+                signing_root,
+                block_body.light_client_signature,
+            )?,
+            Error::InvalidLightClientSignature {},
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use bls::{Signature, SignatureBytes};
+    use spec_test_utils::testing::{self, assert_error, secret_key};
+    use ssz_new::types::{BitVector, FixedVector};
+    use typenum::Unsigned as _;
+    use types::{
+        arc_ext::ArcExt as _,
+        config::MinimalConfig,
+        primitives::{Gwei, Slot, ValidatorIndex, H256},
+    };
+
+    use super::*;
+
+    const SLOT: Slot = 20;
+    const VALIDATOR_COUNT: usize = 64;
+
+    pub(crate) fn state() -> BeaconState<MinimalConfig> {
+        let block_roots = (0..)
+            .map(H256::repeat_byte)
+            .take(<MinimalConfig as Config>::SlotsPerHistoricalRoot::USIZE)
+            .collect::<Vec<_>>();
+
+        BeaconState {
+            slot: SLOT,
+            block_roots: Arc::new(FixedVector::from(block_roots)),
+            ..testing::state(VALIDATOR_COUNT)
+        }
+    }
+
+    fn committee(state: &BeaconState<MinimalConfig>) -> Vec<ValidatorIndex> {
+        get_light_client_committee(state, get_current_epoch(state))
+            .expect("the test state has active validators")
+    }
+
+    // Returns a block body in which the members of the light client committee at `positions`
+    // sign the previous block root.
+    fn block_body(
+        state: &BeaconState<MinimalConfig>,
+        positions: &[usize],
+    ) -> BeaconBlockBody<MinimalConfig> {
+        let committee = committee(state);
+        let previous_slot = compute_previous_slot(state.slot);
+        let previous_block_root =
+            get_block_root_at_slot(state, previous_slot).expect("the test state has block roots");
+        let domain = get_domain(
+            state,
+            MinimalConfig::DOMAIN_LIGHT_CLIENT,
+            Some(compute_epoch_at_slot::<MinimalConfig>(previous_slot)),
+        );
+        let signing_root = compute_signing_root(&previous_block_root, domain);
+
+        let mut light_client_bits = BitVector::new();
+        let mut signature = Signature::default();
+
+        for position in positions.iter().copied() {
+            light_client_bits
+                .set(position, true)
+                .expect("position is within the committee");
+            signature.aggregate_in_place(secret_key(committee[position]).sign(signing_root));
+        }
+
+        BeaconBlockBody {
+            light_client_bits,
+            light_client_signature: signature.into(),
+            ..BeaconBlockBody::default()
+        }
+    }
+
+    fn balances(state: &BeaconState<MinimalConfig>) -> Vec<Gwei> {
+        state.balances.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_process_light_client_aggregate() {
+        let mut state = state();
+        let committee = committee(&state);
+        let positions = [0, committee.len() - 1];
+        let block_body = block_body(&state, &positions);
+
+        let pre_balances = balances(&state);
+        let mut expected_balances = pre_balances.clone();
+        let mut total_reward = 0;
+        for position in positions.iter().copied() {
+            let index = committee[position];
+            let base_reward = get_base_reward(&state, index).expect("index is a committee member");
+            expected_balances[index as usize] += base_reward;
+            total_reward += base_reward;
+        }
+        let proposer_index =
+            get_beacon_proposer_index(&state).expect("the test state has active validators");
+        expected_balances[proposer_index as usize] +=
+            total_reward / MinimalConfig::PROPOSER_REWARD_QUOTIENT;
+
+        process_light_client_aggregate(&mut state, &block_body, true)
+            .expect("light client aggregate is valid");
+
+        let post_balances = balances(&state);
+        assert_eq!(post_balances, expected_balances);
+
+        // 64 validators with the maximum effective balance have a base reward of 357_771 Gwei.
+        for position in positions.iter().copied() {
+            let index = committee[position] as usize;
+            assert!(post_balances[index] >= pre_balances[index] + 357_771);
+        }
+        assert!(post_balances[proposer_index as usize] > pre_balances[proposer_index as usize]);
+    }
+
+    #[test]
+    fn test_light_client_committee_is_shuffled_from_active_validators() {
+        let state = state();
+        let committee = committee(&state);
+        let distinct_members = committee.iter().collect::<HashSet<_>>();
+
+        assert!(!committee.is_empty());
+        assert_eq!(distinct_members.len(), committee.len());
+        assert!(committee
+            .iter()
+            .all(|index| (*index as usize) < VALIDATOR_COUNT));
+        assert_ne!(
+            committee,
+            (0..committee.len() as ValidatorIndex).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_process_light_client_aggregate_without_participants() {
+        let mut state = state();
+        let block_body = BeaconBlockBody {
+            light_client_signature: Signature::default().into(),
+            ..block_body(&state, &[])
+        };
+
+        process_light_client_aggregate(&mut state, &block_body, true)
+            .expect("an empty aggregate signed with the point at infinity is valid");
+    }
+
+    #[test]
+    fn test_slashed_participant_is_not_rewarded() {
+        let mut state = state();
+        let participant_index = committee(&state)[0];
+        state.validators.make_mut()[participant_index as usize].slashed = true;
+        let block_body = block_body(&state, &[0]);
+        let balance = state.balances[participant_index as usize];
+
+        process_light_client_aggregate(&mut state, &block_body, true)
+            .expect("slashed participants can still sign");
+
+        // The proposer reward is zero because the only participant is not rewarded.
+        assert_eq!(state.balances[participant_index as usize], balance);
+    }
+
+    #[test]
+    fn test_process_light_client_aggregate_with_missing_signer() {
+        let mut state = state();
+        let mut block_body = block_body(&state, &[0, 1]);
+        block_body
+            .light_client_bits
+            .set(2, true)
+            .expect("position is within the committee");
+
+        assert_error(
+            process_light_client_aggregate(&mut state, &block_body, true),
+            |error: &Error| matches!(error, Error::InvalidLightClientSignature {}),
+        );
+    }
+
+    #[test]
+    fn test_process_light_client_aggregate_with_wrong_root() {
+        let mut state = state();
+        let block_body = block_body(&state, &[0]);
+        state.block_roots.make_mut()[(SLOT - 1) as usize] = H256::repeat_byte(0xff);
+
+        assert_error(
+            process_light_client_aggregate(&mut state, &block_body, true),
+            |error: &Error| matches!(error, Error::InvalidLightClientSignature {}),
+        );
+    }
+
+    #[test]
+    fn test_process_light_client_aggregate_with_unsigned_empty_aggregate() {
+        let mut state = state();
+        let block_body = BeaconBlockBody {
+            light_client_signature: SignatureBytes::default(),
+            ..block_body(&state, &[])
+        };
+
+        assert_error(
+            process_light_client_aggregate(&mut state, &block_body, true),
+            |error: &Error| matches!(error, Error::InvalidLightClientSignature {}),
+        );
+    }
+
+    #[test]
+    fn test_process_light_client_aggregate_without_verifying_signatures() {
+        let mut state = state();
+        let block_body = BeaconBlockBody {
+            light_client_signature: SignatureBytes::default(),
+            ..block_body(&state, &[0])
+        };
+
+        process_light_client_aggregate(&mut state, &block_body, false)
+            .expect("the signature is not checked");
+    }
+}
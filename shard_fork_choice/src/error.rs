@@ -0,0 +1,27 @@
+use thiserror::Error;
+use types::primitives::{Root, Shard, Slot};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("shard {shard} is not active ({active_shard_count} active shards)")]
+    InactiveShard {
+        shard: Shard,
+        active_shard_count: u64,
+    },
+    #[error("anchor state has no shard state for shard {shard}")]
+    UnknownAnchorShardState { shard: Shard },
+    #[error("shard block is for shard {shard} but the store tracks shard {expected_shard}")]
+    WrongShard { shard: Shard, expected_shard: Shard },
+    #[error("unknown shard block ({root})")]
+    UnknownBlock { root: Root },
+    #[error("shard parent block is not in the store ({root})")]
+    UnknownShardParent { root: Root },
+    #[error(
+        "shard block slot is not later than the finalized shard slot ({slot} <= {finalized_slot})"
+    )]
+    SlotNotLaterThanFinalized { slot: Slot, finalized_slot: Slot },
+    #[error("finalized state has no shard state for shard {shard}")]
+    UnknownFinalizedShardState { shard: Shard },
+    #[error("beacon parent ({root}) does not descend from the finalized block ({finalized_root})")]
+    NotFinalizedDescendant { root: Root, finalized_root: Root },
+}
@@ -1,33 +1,42 @@
+//! Phase 1 shard fork choice.
+//!
+//! The beacon chain `Store` from the specification is not part of this repository. Functions that
+//! look up beacon states in it take the relevant states as parameters instead.
+
+use core::convert::TryFrom as _;
 pub use std::collections::HashMap;
+
+use anyhow::{ensure, Result};
+pub use helper_functions::misc::compute_previous_slot;
+use helper_functions::{
+    accessors::{get_active_shard_count, get_active_validator_indices, get_current_epoch},
+    misc::compute_start_slot_at_epoch,
+};
+use shard_transition_processing::shard_block::shard_state_transition;
+use ssz_new::SszHash as _;
 pub use types::{
     beacon_state::BeaconState,
     config::Config,
-    containers::{ShardBlock, SignedShardBlock, ShardState},
-    primitives::{Shard, Slot, H256, Root},
+    containers::{AttestationData, Checkpoint, ShardBlock, ShardState, SignedShardBlock},
+    primitives::{Epoch, Gwei, Root, Shard, Slot, ValidatorIndex, H256},
 };
 
-#[derive(Debug, Default)]
+pub use crate::error::Error;
+
+mod error;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct ShardLatestMessage {
+    pub epoch: Epoch,
+    pub root: Root,
+}
+
+#[derive(Clone, PartialEq, Default, Debug)]
 pub struct ShardStore<C: Config> {
     pub shard: Shard,
     pub signed_blocks: HashMap<Root, SignedShardBlock<C>>,
     pub block_states: HashMap<Root, ShardState>,
-    pub latest_messages: i32,
-}
-
-impl<C: Config> PartialEq for ShardStore<C> {
-    fn eq(&self, other: &Self) -> bool {
-        self.shard == other.shard
-            && self.signed_blocks == other.signed_blocks
-            && self.block_states == other.block_states
-    }
-}
-
-pub fn compute_previous_slot(slot: Slot) -> Slot {
-    if slot > 0 {
-        slot - 1
-    } else {
-        slot
-    }
+    pub latest_messages: HashMap<ValidatorIndex, ShardLatestMessage>,
 }
 
 /*
@@ -42,38 +51,245 @@ def get_forkchoice_shard_store(anchor_state: BeaconState, shard: Shard) -> Shard
         block_states={anchor_state.shard_states[shard].latest_block_root: anchor_state.copy().shard_states[shard]},
     )
 */
-pub fn get_forkchoice_shard_store<C: Config>(anchor_state: &BeaconState<C>, shard: Shard) -> ShardStore<C> {
-    let shard_block = ShardBlock{
-        beacon_parent_root: /* TODO */,
-        body: /* TODO */,
-        proposer_index: /* TODO */,
-        shard_parent_root: /* TODO */,
-        slot: compute_previous_slot(anchor_state.slot),
-        shard};
-    let signed_shard_block = SignedShardBlock{
-        message: shard_block,
-        signature: /* TODO */,
-    };
-    let signed_blocks = HashMap::new();
-    signed_blocks.insert(
-        anchor_state.shard_states[shard as usize].latest_block_root,
-        signed_shard_block,
-    );
+pub fn get_forkchoice_shard_store<C: Config>(
+    anchor_state: &BeaconState<C>,
+    shard: Shard,
+) -> Result<ShardStore<C>> {
+    let active_shard_count = get_active_shard_count(anchor_state);
 
-    let block_states: HashMap<H256, ShardState> = HashMap::new();
-    block_states.insert(
-        anchor_state.shard_states[shard as usize].latest_block_root,
-        anchor_state.clone().shard_states[shard as usize],
+    ensure!(
+        shard < active_shard_count,
+        Error::InactiveShard {
+            shard,
+            active_shard_count,
+        },
     );
 
-    ShardStore {
+    let shard_state = usize::try_from(shard)
+        .ok()
+        .and_then(|index| anchor_state.shard_states.get(index))
+        .ok_or(Error::UnknownAnchorShardState { shard })?
+        .clone();
+
+    let signed_shard_block = SignedShardBlock {
+        message: ShardBlock {
+            slot: compute_previous_slot(anchor_state.slot),
+            shard,
+            ..ShardBlock::default()
+        },
+        signature: Default::default(),
+    };
+
+    let mut signed_blocks = HashMap::new();
+    signed_blocks.insert(shard_state.latest_block_root, signed_shard_block);
+
+    let mut block_states = HashMap::new();
+    block_states.insert(shard_state.latest_block_root, shard_state);
+
+    Ok(ShardStore {
         shard,
         signed_blocks,
         block_states,
-        ..Default::default()
+        latest_messages: HashMap::new(),
+    })
+}
+
+// The shard part of `update_latest_messages` from the phase 1 beacon fork choice.
+pub fn update_shard_latest_messages<C: Config>(
+    shard_store: &mut ShardStore<C>,
+    attesting_indices: impl IntoIterator<Item = ValidatorIndex>,
+    attestation_data: &AttestationData,
+) {
+    let latest_message = ShardLatestMessage {
+        epoch: attestation_data.target.epoch,
+        root: attestation_data.shard_head_root,
+    };
+
+    for index in attesting_indices {
+        let previous_epoch = shard_store
+            .latest_messages
+            .get(&index)
+            .map(|message| message.epoch);
+
+        if previous_epoch < Some(latest_message.epoch) {
+            shard_store.latest_messages.insert(index, latest_message);
+        }
+    }
+}
+
+pub fn get_shard_ancestor<C: Config>(
+    shard_store: &ShardStore<C>,
+    root: Root,
+    slot: Slot,
+) -> Result<Root> {
+    let mut root = root;
+
+    loop {
+        let block = &shard_store
+            .signed_blocks
+            .get(&root)
+            .ok_or(Error::UnknownBlock { root })?
+            .message;
+
+        // If the block is older than the queried slot, the slot was skipped.
+        // The most recent root prior to it is the ancestor.
+        if block.slot <= slot {
+            return Ok(root);
+        }
+
+        root = block.shard_parent_root;
     }
 }
 
+// `justified_state` corresponds to `store.checkpoint_states[store.justified_checkpoint]`.
+pub fn get_shard_latest_attesting_balance<C: Config>(
+    shard_store: &ShardStore<C>,
+    justified_state: &BeaconState<C>,
+    root: Root,
+) -> Result<Gwei> {
+    let slot = shard_store
+        .signed_blocks
+        .get(&root)
+        .ok_or(Error::UnknownBlock { root })?
+        .message
+        .slot;
+
+    let mut balance = 0;
+
+    for index in get_active_validator_indices(justified_state, get_current_epoch(justified_state))?
+    {
+        if let Some(message) = shard_store.latest_messages.get(&index) {
+            // Validators' previous attestation roots are assumed to be on the same fork as the
+            // current attestation root.
+            if get_shard_ancestor(shard_store, message.root, slot)? == root {
+                balance += justified_state.validators[index as usize].effective_balance;
+            }
+        }
+    }
+
+    Ok(balance)
+}
+
+// `beacon_head_state` corresponds to `store.block_states[get_head(store)]`.
+pub fn get_shard_head<C: Config>(
+    shard_store: &ShardStore<C>,
+    justified_state: &BeaconState<C>,
+    beacon_head_state: &BeaconState<C>,
+) -> Result<Root> {
+    let shard_head_state = &beacon_head_state.shard_states[shard_store.shard as usize];
+    let mut shard_head_root = shard_head_state.latest_block_root;
+
+    let shard_blocks = shard_store
+        .signed_blocks
+        .iter()
+        .map(|(root, signed_shard_block)| (*root, &signed_shard_block.message))
+        .filter(|(_, shard_block)| shard_block.slot > shard_head_state.slot)
+        .collect::<Vec<_>>();
+
+    // Execute the LMD-GHOST fork choice.
+    loop {
+        let mut best_child = None;
+
+        for (root, shard_block) in &shard_blocks {
+            if shard_block.shard_parent_root != shard_head_root {
+                continue;
+            }
+
+            // Ties are broken lexicographically.
+            let key = (
+                get_shard_latest_attesting_balance(shard_store, justified_state, *root)?,
+                *root,
+            );
+
+            if Some(key) > best_child {
+                best_child = Some(key);
+            }
+        }
+
+        match best_child {
+            Some((_, root)) => shard_head_root = root,
+            None => return Ok(shard_head_root),
+        }
+    }
+}
+
+// `beacon_parent_state` corresponds to `store.block_states[shard_block.beacon_parent_root]`,
+// `finalized_state` to `store.block_states[store.finalized_checkpoint.root]` and
+// `get_ancestor` to `get_ancestor(store, root, slot)`.
+pub fn on_shard_block<C: Config>(
+    shard_store: &mut ShardStore<C>,
+    signed_shard_block: SignedShardBlock<C>,
+    beacon_parent_state: &BeaconState<C>,
+    finalized_checkpoint: Checkpoint,
+    finalized_state: &BeaconState<C>,
+    get_ancestor: impl FnOnce(Root, Slot) -> Result<Root>,
+) -> Result<()> {
+    let shard_block = &signed_shard_block.message;
+    let shard = shard_block.shard;
+
+    ensure!(
+        shard == shard_store.shard,
+        Error::WrongShard {
+            shard,
+            expected_shard: shard_store.shard,
+        },
+    );
+
+    // Check shard parent exists
+    let shard_parent_state = shard_store
+        .block_states
+        .get(&shard_block.shard_parent_root)
+        .ok_or(Error::UnknownShardParent {
+            root: shard_block.shard_parent_root,
+        })?;
+
+    // Check that block is later than the finalized shard state slot
+    let finalized_slot = usize::try_from(shard)
+        .ok()
+        .and_then(|index| finalized_state.shard_states.get(index))
+        .ok_or(Error::UnknownFinalizedShardState { shard })?
+        .slot;
+
+    ensure!(
+        shard_block.slot > finalized_slot,
+        Error::SlotNotLaterThanFinalized {
+            slot: shard_block.slot,
+            finalized_slot,
+        },
+    );
+
+    // Check block is a descendant of the finalized block at the checkpoint finalized slot
+    let ancestor = get_ancestor(
+        shard_block.beacon_parent_root,
+        compute_start_slot_at_epoch::<C>(finalized_checkpoint.epoch),
+    )?;
+
+    ensure!(
+        ancestor == finalized_checkpoint.root,
+        Error::NotFinalizedDescendant {
+            root: shard_block.beacon_parent_root,
+            finalized_root: finalized_checkpoint.root,
+        },
+    );
+
+    // `shard_state_transition` also checks that `beacon_parent_state` is the beacon parent.
+    // Check the block is valid and compute the post-state
+    let mut shard_state = shard_parent_state.clone();
+    shard_state_transition(
+        &mut shard_state,
+        &signed_shard_block,
+        beacon_parent_state,
+        true,
+    )?;
+
+    // Signed blocks are stored for computing `ShardTransition.proposer_signature_aggregate`.
+    let root = shard_block.tree_hash_root();
+    shard_store.signed_blocks.insert(root, signed_shard_block);
+    shard_store.block_states.insert(root, shard_state);
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::compute_previous_slot;
@@ -1,47 +1,353 @@
-// TODO: Re-write test
+use std::sync::Arc;
 
-
-/*use std::collections::HashMap;
-use types::{
-    beacon_state::BeaconState,
-    containers::{ShardState, ShardBlock, SignedShardBlock},
-    primitives::{H256},
+use helper_functions::{
+    accessors::{get_active_shard_count, get_domain},
+    cache::shard_proposer_index,
+    misc::{compute_epoch_at_slot, compute_signing_root},
+};
+use shard_fork_choice::*;
+use shard_transition_processing::shard_block::beacon_parent_root;
+use spec_test_utils::testing::{self, secret_key};
+use ssz_new::{
+    types::{ByteList, VariableList},
+    SszHash as _,
 };
+use types::{config::MinimalConfig, containers::Checkpoint};
+
+const TEST_SHARD: Shard = 0;
+
+fn finalized_checkpoint() -> Checkpoint {
+    Checkpoint {
+        epoch: 0,
+        root: H256::repeat_byte(7),
+    }
+}
+
+// Stands in for a beacon chain in which every block descends from the finalized checkpoint.
+fn finalized_ancestor(_root: Root, _slot: Slot) -> anyhow::Result<Root> {
+    Ok(finalized_checkpoint().root)
+}
+
+fn beacon_state(slot: Slot) -> BeaconState<MinimalConfig> {
+    let shard_state = ShardState {
+        latest_block_root: H256::repeat_byte(1),
+        ..ShardState::default()
+    };
+
+    BeaconState {
+        slot,
+        shard_states: Arc::new(VariableList::from(vec![shard_state])),
+        ..testing::state(8)
+    }
+}
+
+fn child_block(
+    shard_store: &mut ShardStore<MinimalConfig>,
+    parent_root: Root,
+    slot: Slot,
+    proposer_index: ValidatorIndex,
+) -> Root {
+    let shard_block = ShardBlock {
+        shard_parent_root: parent_root,
+        slot,
+        shard: TEST_SHARD,
+        proposer_index,
+        ..ShardBlock::default()
+    };
+    let root = shard_block.tree_hash_root();
+    shard_store.signed_blocks.insert(
+        root,
+        SignedShardBlock {
+            message: shard_block,
+            signature: Default::default(),
+        },
+    );
+    root
+}
+
+fn attest(
+    shard_store: &mut ShardStore<MinimalConfig>,
+    attesting_indices: Vec<ValidatorIndex>,
+    epoch: Epoch,
+    root: Root,
+) {
+    let attestation_data = AttestationData {
+        target: Checkpoint {
+            epoch,
+            ..Checkpoint::default()
+        },
+        shard_head_root: root,
+        ..AttestationData::default()
+    };
+    update_shard_latest_messages(shard_store, attesting_indices, &attestation_data);
+}
+
+fn signed_block(
+    beacon_parent_state: &BeaconState<MinimalConfig>,
+    slot: Slot,
+) -> SignedShardBlock<MinimalConfig> {
+    let proposer_index = shard_proposer_index(beacon_parent_state, slot, TEST_SHARD)
+        .expect("the test state has active validators");
+    let shard_block = ShardBlock {
+        shard_parent_root: H256::repeat_byte(1),
+        beacon_parent_root: beacon_parent_root(beacon_parent_state),
+        slot,
+        shard: TEST_SHARD,
+        proposer_index,
+        body: ByteList::from_bytes(vec![1, 2, 3]).expect("body is shorter than the limit"),
+    };
+    let domain = get_domain(
+        beacon_parent_state,
+        MinimalConfig::DOMAIN_SHARD_PROPOSAL,
+        Some(compute_epoch_at_slot::<MinimalConfig>(slot)),
+    );
+    let signature = secret_key(proposer_index)
+        .sign(compute_signing_root(&shard_block, domain))
+        .into();
+
+    SignedShardBlock {
+        message: shard_block,
+        signature,
+    }
+}
 
 #[test]
 fn get_forkchoice_shard_store_test() {
-    let test_shard = 0;
-    let shard_state = ShardState {
-        slot: ,
-        gasprice: ,
-        latest_block_root: H256([
-            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
-            1, 1, 1,
-        ]),
+    let beacon_state = beacon_state(5);
+    let shard_store =
+        get_forkchoice_shard_store(&beacon_state, TEST_SHARD).expect("the test shard is active");
+
+    let anchor_root = H256::repeat_byte(1);
+    let anchor_block = &shard_store.signed_blocks[&anchor_root].message;
+
+    assert_eq!(shard_store.shard, TEST_SHARD);
+    assert_eq!(anchor_block.slot, 4);
+    assert_eq!(anchor_block.shard, TEST_SHARD);
+    assert_eq!(
+        shard_store.block_states[&anchor_root],
+        beacon_state.shard_states[TEST_SHARD as usize],
+    );
+    assert!(shard_store.latest_messages.is_empty());
+}
+
+#[test]
+fn get_forkchoice_shard_store_rejects_inactive_shards() {
+    let beacon_state = beacon_state(5);
+    let active_shard_count = get_active_shard_count(&beacon_state);
+
+    testing::assert_error(
+        get_forkchoice_shard_store(&beacon_state, active_shard_count),
+        |error: &Error| matches!(error, Error::InactiveShard { .. }),
+    );
+}
+
+#[test]
+fn update_shard_latest_messages_keeps_newest_target() {
+    let mut shard_store =
+        get_forkchoice_shard_store(&beacon_state(1), TEST_SHARD).expect("the test shard is active");
+
+    attest(&mut shard_store, vec![1, 2], 2, H256::repeat_byte(2));
+    attest(&mut shard_store, vec![2, 3], 1, H256::repeat_byte(3));
+
+    assert_eq!(shard_store.latest_messages[&1].root, H256::repeat_byte(2));
+    assert_eq!(shard_store.latest_messages[&2].root, H256::repeat_byte(2));
+    assert_eq!(shard_store.latest_messages[&3].root, H256::repeat_byte(3));
+}
+
+#[test]
+fn get_shard_ancestor_skips_empty_slots() {
+    let mut shard_store =
+        get_forkchoice_shard_store(&beacon_state(1), TEST_SHARD).expect("the test shard is active");
+    let anchor_root = H256::repeat_byte(1);
+    let block_2 = child_block(&mut shard_store, anchor_root, 2, 0);
+    let block_5 = child_block(&mut shard_store, block_2, 5, 0);
+
+    assert_eq!(
+        get_shard_ancestor(&shard_store, block_5, 5).ok(),
+        Some(block_5)
+    );
+    assert_eq!(
+        get_shard_ancestor(&shard_store, block_5, 4).ok(),
+        Some(block_2)
+    );
+    assert_eq!(
+        get_shard_ancestor(&shard_store, block_5, 2).ok(),
+        Some(block_2)
+    );
+    assert_eq!(
+        get_shard_ancestor(&shard_store, block_5, 1).ok(),
+        Some(anchor_root)
+    );
+    assert!(get_shard_ancestor(&shard_store, H256::repeat_byte(9), 1).is_err());
+}
+
+#[test]
+fn get_shard_head_follows_heaviest_fork() {
+    let state = beacon_state(1);
+    let mut shard_store =
+        get_forkchoice_shard_store(&state, TEST_SHARD).expect("the test shard is active");
+    let anchor_root = H256::repeat_byte(1);
+    let light = child_block(&mut shard_store, anchor_root, 1, 0);
+    let heavy = child_block(&mut shard_store, anchor_root, 1, 1);
+    let heavy_child = child_block(&mut shard_store, heavy, 2, 0);
+
+    attest(&mut shard_store, vec![0], 0, light);
+    attest(&mut shard_store, vec![1, 2], 0, heavy_child);
+
+    let balance = MinimalConfig::MAX_EFFECTIVE_BALANCE;
+    assert_eq!(
+        get_shard_latest_attesting_balance(&shard_store, &state, light).ok(),
+        Some(balance),
+    );
+    assert_eq!(
+        get_shard_latest_attesting_balance(&shard_store, &state, heavy).ok(),
+        Some(2 * balance),
+    );
+    assert_eq!(
+        get_shard_head(&shard_store, &state, &state).ok(),
+        Some(heavy_child),
+    );
+}
+
+#[test]
+fn get_shard_head_breaks_ties_lexicographically() {
+    let state = beacon_state(1);
+    let mut shard_store =
+        get_forkchoice_shard_store(&state, TEST_SHARD).expect("the test shard is active");
+    let anchor_root = H256::repeat_byte(1);
+    let first = child_block(&mut shard_store, anchor_root, 1, 0);
+    let second = child_block(&mut shard_store, anchor_root, 1, 1);
+
+    assert_eq!(
+        get_shard_head(&shard_store, &state, &state).ok(),
+        Some(first.max(second)),
+    );
+}
+
+#[test]
+fn on_shard_block_accepts_valid_block() {
+    let state = beacon_state(1);
+    let mut shard_store =
+        get_forkchoice_shard_store(&state, TEST_SHARD).expect("the test shard is active");
+    let signed_shard_block = signed_block(&state, 1);
+    let root = signed_shard_block.message.tree_hash_root();
+
+    on_shard_block(
+        &mut shard_store,
+        signed_shard_block.clone(),
+        &state,
+        finalized_checkpoint(),
+        &state,
+        finalized_ancestor,
+    )
+    .expect("block is valid");
+
+    assert_eq!(shard_store.signed_blocks[&root], signed_shard_block);
+    assert!(shard_store.block_states.contains_key(&root));
+}
+
+#[test]
+fn on_shard_block_rejects_invalid_blocks() {
+    let state = beacon_state(1);
+    let shard_store =
+        get_forkchoice_shard_store(&state, TEST_SHARD).expect("the test shard is active");
+    let valid_block = signed_block(&state, 1);
+
+    let rejects = |signed_shard_block: SignedShardBlock<MinimalConfig>| {
+        on_shard_block(
+            &mut shard_store.clone(),
+            signed_shard_block,
+            &state,
+            finalized_checkpoint(),
+            &state,
+            finalized_ancestor,
+        )
+        .is_err()
     };
-    let beacon_state = BeaconState {};
-    let shard_block = ShardBlock::new(1, test_shard);
-    let signed_shard_block = SignedShardBlock::new(shard_block);
-    let mut signed_blocks = HashMap::new();
-    signed_blocks.insert(
-        beacon_state.shard_states[test_shard as usize].latest_block_root,
+
+    let mut wrong_shard = valid_block.clone();
+    wrong_shard.message.shard = 1;
+    assert!(rejects(wrong_shard));
+
+    let mut unknown_parent = valid_block.clone();
+    unknown_parent.message.shard_parent_root = H256::repeat_byte(9);
+    assert!(rejects(unknown_parent));
+
+    let mut wrong_beacon_parent = valid_block.clone();
+    wrong_beacon_parent.message.beacon_parent_root = H256::repeat_byte(9);
+    assert!(rejects(wrong_beacon_parent));
+
+    let mut finalized_slot = valid_block.clone();
+    finalized_slot.message.slot = 0;
+    assert!(rejects(finalized_slot));
+
+    let mut wrong_proposer = valid_block.clone();
+    wrong_proposer.message.proposer_index = (valid_block.message.proposer_index + 1) % 8;
+    assert!(rejects(wrong_proposer));
+
+    let mut empty_body = valid_block.clone();
+    empty_body.message.body = ByteList::from_bytes(vec![]).expect("empty body fits");
+    assert!(rejects(empty_body));
+
+    let mut bad_signature = valid_block;
+    bad_signature.signature = signed_block(&state, 1).signature;
+    bad_signature.message.body = ByteList::from_bytes(vec![4]).expect("body fits");
+    assert!(rejects(bad_signature));
+}
+
+#[test]
+fn on_shard_block_rejects_block_not_descending_from_finalized_checkpoint() {
+    let state = beacon_state(1);
+    let mut shard_store =
+        get_forkchoice_shard_store(&state, TEST_SHARD).expect("the test shard is active");
+    let signed_shard_block = signed_block(&state, 1);
+    let beacon_parent_root = signed_shard_block.message.beacon_parent_root;
+
+    let result = on_shard_block(
+        &mut shard_store,
         signed_shard_block,
+        &state,
+        finalized_checkpoint(),
+        &state,
+        |root, slot| {
+            assert_eq!(root, beacon_parent_root);
+            assert_eq!(slot, 0);
+            Ok(H256::repeat_byte(8))
+        },
     );
-    let mut block_states: HashMap<H256, ShardState> = HashMap::new();
-    block_states.insert(
-        beacon_state.shard_states[test_shard as usize].latest_block_root,
-        beacon_state.shard_states[test_shard as usize],
-    );
-    let shard_store = ShardStore {
-        shard: test_shard,
-        signed_blocks,
-        block_states,
-        ..Default::default()
+
+    assert!(matches!(
+        result
+            .expect_err("beacon parent is on a conflicting fork")
+            .downcast_ref::<Error>(),
+        Some(Error::NotFinalizedDescendant { .. }),
+    ));
+    assert_eq!(shard_store.signed_blocks.len(), 1);
+}
+
+#[test]
+fn on_shard_block_rejects_finalized_state_without_shard() {
+    let state = beacon_state(1);
+    let mut shard_store =
+        get_forkchoice_shard_store(&state, TEST_SHARD).expect("the test shard is active");
+    let finalized_state = BeaconState {
+        shard_states: Arc::default(),
+        ..state.clone()
     };
 
-    assert_eq!(
-        get_forkchoice_shard_store<MainnetConfig>(&beacon_state, test_shard),
-        shard_store
+    let result = on_shard_block(
+        &mut shard_store,
+        signed_block(&state, 1),
+        &state,
+        finalized_checkpoint(),
+        &finalized_state,
+        finalized_ancestor,
     );
+
+    assert!(matches!(
+        result
+            .expect_err("finalized state has no shard states")
+            .downcast_ref::<Error>(),
+        Some(Error::UnknownFinalizedShardState { .. }),
+    ));
 }
-*/
\ No newline at end of file
@@ -0,0 +1,136 @@
+use core::convert::TryFrom as _;
+
+use anyhow::Result;
+use helper_functions::accessors::get_attesting_indices;
+use types::{arc_ext::ArcExt as _, beacon_state::BeaconState, config::Config};
+
+use crate::error::Error;
+
+pub fn process_online_tracking<C: Config>(state: &mut BeaconState<C>) -> Result<()> {
+    // Slowly remove validators from the "online" set if they do not show up
+    for countdown in state.online_countdown.make_mut().iter_mut() {
+        *countdown = countdown.saturating_sub(1);
+    }
+
+    // Process pending attestations
+    let mut online_indices = vec![];
+
+    for pending_attestation in state
+        .current_epoch_attestations
+        .iter()
+        .chain(state.previous_epoch_attestations.iter())
+    {
+        online_indices.extend(get_attesting_indices(
+            state,
+            &pending_attestation.data,
+            &pending_attestation.aggregation_bits,
+        )?);
+    }
+
+    for index in online_indices {
+        *state
+            .online_countdown
+            .make_mut()
+            .get_mut(usize::try_from(index)?)
+            .ok_or(Error::IndexOutOfBounds { index })? = C::ONLINE_PERIOD;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use helper_functions::accessors::get_beacon_committee;
+    use ssz_new::types::{BitList, VariableList};
+    use types::{
+        config::MainnetConfig,
+        containers::{AttestationData, PendingAttestation},
+        primitives::{Slot, ValidatorIndex},
+    };
+
+    use crate::tests::state;
+
+    use super::*;
+
+    // With 8 validators in the mainnet preset only every 4th slot has a nonempty committee.
+    fn pending_attestation(
+        state: &BeaconState<MainnetConfig>,
+        slot: Slot,
+    ) -> (PendingAttestation<MainnetConfig>, Vec<ValidatorIndex>) {
+        let committee =
+            get_beacon_committee(state, slot, 0).expect("the test state has active validators");
+        let mut aggregation_bits =
+            BitList::with_capacity(committee.len()).expect("committee fits in a bitlist");
+        for position in 0..committee.len() {
+            aggregation_bits
+                .set(position, true)
+                .expect("position is within the committee");
+        }
+
+        let pending_attestation = PendingAttestation {
+            aggregation_bits,
+            data: AttestationData {
+                slot,
+                index: 0,
+                ..AttestationData::default()
+            },
+            inclusion_delay: 1,
+            proposer_index: 0,
+            crosslink_success: false,
+        };
+
+        (pending_attestation, committee)
+    }
+
+    #[test]
+    fn test_process_online_tracking() {
+        let mut state = state(9);
+        state.online_countdown = Arc::new(VariableList::from(vec![0, 1, 2, 3, 4, 5, 6, 7]));
+        let (current_attestation, current_attesters) = pending_attestation(&state, 7);
+        let (previous_attestation, previous_attesters) = pending_attestation(&state, 3);
+        state.current_epoch_attestations = Arc::new(VariableList::from(vec![current_attestation]));
+        state.previous_epoch_attestations =
+            Arc::new(VariableList::from(vec![previous_attestation]));
+
+        let mut expected_countdown = [0, 0, 1, 2, 3, 4, 5, 6];
+        for index in current_attesters.into_iter().chain(previous_attesters) {
+            expected_countdown[index as usize] = MainnetConfig::ONLINE_PERIOD;
+        }
+
+        process_online_tracking(&mut state).expect("attesters have countdowns");
+
+        assert_eq!(state.online_countdown[..], expected_countdown[..]);
+        assert_eq!(
+            state
+                .online_countdown
+                .iter()
+                .filter(|countdown| **countdown == MainnetConfig::ONLINE_PERIOD)
+                .count(),
+            2,
+        );
+    }
+
+    #[test]
+    fn test_process_online_tracking_without_attestations() {
+        let mut state = state(9);
+
+        process_online_tracking(&mut state).expect("there are no attestations to process");
+
+        assert!(state
+            .online_countdown
+            .iter()
+            .all(|countdown| *countdown == MainnetConfig::ONLINE_PERIOD - 1));
+    }
+
+    #[test]
+    fn test_process_online_tracking_with_missing_countdown() {
+        let mut state = state(9);
+        state.online_countdown = Arc::new(VariableList::default());
+        let (current_attestation, _) = pending_attestation(&state, 7);
+        state.current_epoch_attestations = Arc::new(VariableList::from(vec![current_attestation]));
+
+        assert!(process_online_tracking(&mut state).is_err());
+    }
+}
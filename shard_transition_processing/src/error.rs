@@ -1,9 +1,9 @@
 use thiserror::Error;
-use types::primitives::{Gwei, Root, Slot, H256};
+use types::primitives::{Gwei, Root, Shard, Slot, ValidatorIndex, H256};
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Error)]
-pub enum Error /*<C: Config>*/ {
+pub enum Error {
     #[error("Invalid slot ({slot} <= {genesis_slot})")]
     InvalidSlot { slot: Slot, genesis_slot: Slot },
     #[error("Invalid # of transition data roots ({offset_slots}, {shard_data_roots}, {shard_states}, {shard_block_lengths})")]
@@ -13,6 +13,8 @@ pub enum Error /*<C: Config>*/ {
         shard_states: usize,
         shard_block_lengths: usize,
     },
+    #[error("No offset slots for shard {shard}")]
+    NoOffsetSlots { shard: Shard },
     #[error("Incorrect slot ({slot} != {expected_slot})")]
     IncorrectSlot { slot: Slot, expected_slot: Slot },
     #[error("Incorrect gasprice ({gasprice} != {expected_gasprice})")]
@@ -28,6 +30,31 @@ pub enum Error /*<C: Config>*/ {
     MissingTransitionRoot {},
     #[error("Incorrect data root ({root} != {expected_root})")]
     IncorrectRoot { root: Root, expected_root: Root },
+    #[error("Winning shard transition has no shard states")]
+    NoShardStates {},
+    #[error("No shard transition for shard {shard}")]
+    MissingShardTransition { shard: Shard },
     #[error("Shard transition in Shard transition processing is not empty")]
     NotEmptyShardTransition {},
+    #[error("Incorrect shard parent root ({root} != {expected_root})")]
+    IncorrectShardParentRoot { root: Root, expected_root: Root },
+    #[error("Incorrect beacon parent root ({root} != {expected_root})")]
+    IncorrectBeaconParentRoot { root: Root, expected_root: Root },
+    #[error("Shard block slot is not an offset slot ({slot})")]
+    ShardBlockSlotNotInOffsetSlots { slot: Slot },
+    #[error("Shard block slot is not later than its parent ({slot} <= {parent_slot})")]
+    ShardBlockSlotNotLater { slot: Slot, parent_slot: Slot },
+    #[error("Incorrect shard block proposer ({proposer_index} != {expected_proposer_index})")]
+    IncorrectShardProposer {
+        proposer_index: ValidatorIndex,
+        expected_proposer_index: ValidatorIndex,
+    },
+    #[error("Unknown shard block proposer ({proposer_index})")]
+    UnknownShardProposer { proposer_index: ValidatorIndex },
+    #[error("Shard block body is empty")]
+    EmptyShardBlockBody {},
+    #[error("Shard block signature verification has failed!")]
+    UnverifiedShardBlockSignature {},
+    #[error("Validator index {index} is out of bounds of online_countdown")]
+    IndexOutOfBounds { index: ValidatorIndex },
 }
@@ -1,28 +1,31 @@
+pub mod epoch_processing;
 mod error;
+pub mod shard_block;
+
+use std::collections::{BTreeSet, HashSet};
 
 use anyhow::{ensure, Result};
 use error::Error;
 use helper_functions::{
     accessors::{
-        get_attesting_indices, get_beacon_committee, get_beacon_proposer_index,
-        get_block_root_at_slot, get_total_balance,
+        compute_shard_from_committee_index, get_active_shard_count, get_attesting_indices,
+        get_base_reward, get_beacon_committee, get_beacon_proposer_index, get_block_root_at_slot,
+        get_committee_count_per_slot, get_domain, get_offset_slots, get_online_validator_indices,
+        get_total_balance,
+    },
+    cache::shard_proposer_index,
+    crypto::optional_aggregate_verify,
+    misc::{
+        compute_epoch_at_slot, compute_previous_slot, compute_signing_root,
+        compute_updated_gasprice,
     },
-    misc::{compute_epoch_at_slot, compute_previous_slot, compute_signing_root},
     mutators::{decrease_balance, increase_balance},
-    predicates::is_winning_attestation,
+    predicates::{is_on_time_attestation, is_winning_attestation},
 };
 use itertools::izip;
-use std::collections::{BTreeSet, HashSet};
-use std::iter::FromIterator;
-use std::vec::Vec;
-use stubs::beacon_chain::{
-    compute_shard_from_committee_index, compute_updated_gasprice, get_active_shard_count,
-    get_base_reward, get_committee_count_per_slot, get_domain, get_offset_slots,
-    get_online_validator_indices, get_shard_proposer_index, is_on_time_attestation,
-    optional_aggregate_verify,
-};
-use tree_hash::TreeHash;
+use ssz_new::SszHash as _;
 use types::{
+    arc_ext::ArcExt as _,
     beacon_state::BeaconState,
     config::Config,
     consts::GENESIS_SLOT,
@@ -30,23 +33,23 @@ use types::{
     primitives::{CommitteeIndex, Gwei, Root, Shard, ValidatorIndex, H256},
 };
 
-// TODO Should be implemented as a method (impl) for BeaconState?
 pub fn apply_shard_transition<C: Config>(
     state: &mut BeaconState<C>,
     shard: Shard,
     transition: &ShardTransition<C>,
+    verify_signatures: bool,
 ) -> Result<()> {
     ensure!(
-        state.slot <= GENESIS_SLOT,
+        state.slot > GENESIS_SLOT,
         Error::InvalidSlot {
             slot: state.slot,
             genesis_slot: GENESIS_SLOT,
         }
     );
+
     // Correct data root count
-    let offset_slots = get_offset_slots(state, shard);
+    let offset_slots = get_offset_slots(state, shard)?;
     ensure!(
-        // TODO Find a shorter way to write this?
         offset_slots.len() == transition.shard_data_roots.len()
             && offset_slots.len() == transition.shard_states.len()
             && offset_slots.len() == transition.shard_block_lengths.len(),
@@ -57,99 +60,103 @@ pub fn apply_shard_transition<C: Config>(
             shard_block_lengths: transition.shard_block_lengths.len(),
         }
     );
+    let start_slot = *offset_slots.first().ok_or(Error::NoOffsetSlots { shard })?;
     ensure!(
-        transition.start_slot == offset_slots[0],
+        transition.start_slot == start_slot,
         Error::IncorrectSlot {
             slot: transition.start_slot,
-            expected_slot: offset_slots[0],
+            expected_slot: start_slot,
         }
     );
 
-    let headers = Vec::new();
-    let proposers = Vec::new();
+    let mut headers = Vec::new();
+    let mut proposers = Vec::new();
     let mut prev_gasprice = state.shard_states[shard as usize].gasprice;
     let mut shard_parent_root = state.shard_states[shard as usize].latest_block_root;
-    for (i, offset_slot) in offset_slots.iter().enumerate() {
-        let shard_block_length = transition.shard_block_lengths[i];
-        let shard_state = transition.shard_states[i];
-
+    let states_slots_lengths = izip!(
+        transition.shard_states.iter(),
+        offset_slots,
+        transition.shard_block_lengths.iter().copied(),
+        transition.shard_data_roots.iter().copied(),
+    );
+    for (shard_state, offset_slot, shard_block_length, shard_data_root) in states_slots_lengths {
         // Verify correct calculation of gas prices and slots
+        let expected_gasprice = compute_updated_gasprice::<C>(prev_gasprice, shard_block_length);
         ensure!(
-            shard_state.gasprice == compute_updated_gasprice(prev_gasprice, shard_block_length),
+            shard_state.gasprice == expected_gasprice,
             Error::IncorrectGasprice {
                 gasprice: shard_state.gasprice,
-                expected_gasprice: compute_updated_gasprice(prev_gasprice, shard_block_length),
+                expected_gasprice,
             }
         );
         ensure!(
-            shard_state.slot == *offset_slot,
+            shard_state.slot == offset_slot,
             Error::IncorrectSlot {
                 slot: shard_state.slot,
-                expected_slot: *offset_slot,
+                expected_slot: offset_slot,
             }
         );
 
         // Collect the non-empty proposals result
-        match shard_block_length {
-            0 => {
-                // Must have a stub for `shard_data_root` if empty slot
-                ensure!(
-                    transition.shard_data_roots[i] == H256::default(),
-                    Error::NonEmptyRoot {
-                        root: transition.shard_data_roots[i]
-                    }
-                );
-            }
-            _ => {
-                let proposer_index = get_shard_proposer_index(&*state, *offset_slot, shard);
-                // Reconstruct shard headers
-                let header = ShardBlockHeader {
-                    shard_parent_root,
-                    beacon_parent_root: get_block_root_at_slot(state, *offset_slot)?,
-                    slot: *offset_slot,
-                    shard,
-                    proposer_index,
-                    body_root: transition.shard_data_roots[i],
-                };
-                shard_parent_root = header.tree_hash_root();
-                headers.push(header);
-                proposers.push(proposer_index);
-            }
+        if shard_block_length == 0 {
+            // Must have a stub for `shard_data_root` if empty slot
+            ensure!(
+                shard_data_root == H256::zero(),
+                Error::NonEmptyRoot {
+                    root: shard_data_root
+                }
+            );
+        } else {
+            let proposer_index = shard_proposer_index(state, offset_slot, shard)?;
+            // Reconstruct shard headers
+            let header = ShardBlockHeader {
+                shard_parent_root,
+                beacon_parent_root: get_block_root_at_slot(state, offset_slot)?,
+                slot: offset_slot,
+                shard,
+                proposer_index,
+                body_root: shard_data_root,
+            };
+            shard_parent_root = header.tree_hash_root();
+            headers.push(header);
+            proposers.push(proposer_index);
         }
+
         prev_gasprice = shard_state.gasprice;
     }
 
-    let pubkeys = Vec::new();
-    let signing_roots = Vec::new();
-    for proposer in proposers {
-        pubkeys.push(state.validators[proposer as usize].pubkey);
-    }
-    for header in headers {
-        /* TODO: Create new const DOMAIN_SHARD_PROPOSAL in Config */
-        signing_roots.push(compute_signing_root(
-            &header,
-            get_domain(
+    if verify_signatures {
+        let pubkeys = proposers
+            .into_iter()
+            .map(|proposer| state.validators[proposer as usize].pubkey);
+        let signing_roots = headers.iter().map(|header| {
+            let domain = get_domain(
                 state,
                 C::DOMAIN_SHARD_PROPOSAL,
-                compute_epoch_at_slot::<C>(header.slot),
-            ),
-        ));
-    }
+                Some(compute_epoch_at_slot::<C>(header.slot)),
+            );
+            compute_signing_root(header, domain)
+        });
 
-    // Verify combined proposer signature
-    ensure!(
-        optional_aggregate_verify(
-            pubkeys,
-            signing_roots,
-            transition.proposer_signature_aggregate
-        ),
-        Error::UnverifiedAggregateSignature {}
-    );
+        // Verify combined proposer signature
+        ensure!(
+            optional_aggregate_verify(
+                pubkeys,
+                signing_roots,
+                transition.proposer_signature_aggregate
+            )?,
+            Error::UnverifiedAggregateSignature {}
+        );
+    }
 
     // Copy and save updated shard state
-    let shard_state = transition.shard_states[transition.shard_states.len() - 1].clone();
+    let mut shard_state = transition
+        .shard_states
+        .last()
+        .expect("offset slots and shard states were checked to be nonempty above")
+        .clone();
     shard_state.slot = compute_previous_slot(state.slot);
-    state.shard_states[shard as usize] = shard_state;
+    state.shard_states.make_mut()[shard as usize] = shard_state;
 
     Ok(())
 }
@@ -157,89 +164,95 @@ pub fn apply_shard_transition<C: Config>(
 pub fn process_crosslink_for_shard<C: Config>(
     state: &mut BeaconState<C>,
     committee_index: CommitteeIndex,
-    shard_transition: ShardTransition<C>,
-    attestations: Vec<Attestation<C>>,
+    shard_transition: &ShardTransition<C>,
+    attestations: &[&Attestation<C>],
+    verify_signatures: bool,
 ) -> Result<Root> {
     let on_time_attestation_slot = compute_previous_slot(state.slot);
-    let committee = HashSet::from_iter(get_beacon_committee(
-        state,
-        on_time_attestation_slot,
-        committee_index,
-    )?);
-    let online_indices = get_online_validator_indices(state);
+    let committee = get_beacon_committee(state, on_time_attestation_slot, committee_index)?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let online_indices = get_online_validator_indices(state)?;
     let shard =
-        compute_shard_from_committee_index(state, committee_index, on_time_attestation_slot);
+        compute_shard_from_committee_index(state, committee_index, on_time_attestation_slot)?;
 
     // Loop over all shard transition roots
-    let shard_transition_roots = BTreeSet::new();
-    for a in attestations {
-        shard_transition_roots.insert(a.data.shard_transition_root);
-    }
+    let shard_transition_roots = attestations
+        .iter()
+        .map(|attestation| attestation.data.shard_transition_root)
+        .collect::<BTreeSet<_>>();
+
     for shard_transition_root in shard_transition_roots {
-        let transition_attestations = Vec::new();
-        for a in attestations {
-            if a.data.shard_transition_root == shard_transition_root {
-                transition_attestations.push(a);
-            }
-        }
+        let transition_attestations = attestations
+            .iter()
+            .filter(|attestation| attestation.data.shard_transition_root == shard_transition_root)
+            .collect::<Vec<_>>();
+
         let mut transition_participants = HashSet::<ValidatorIndex>::new();
-        for attestation in transition_attestations {
-            let participants: Vec<ValidatorIndex> =
-                get_attesting_indices(state, &attestation.data, &attestation.aggregation_bits)?
-                    .collect();
-            transition_participants.extend(&participants);
+        for attestation in &transition_attestations {
+            transition_participants.extend(get_attesting_indices(
+                state,
+                &attestation.data,
+                &attestation.aggregation_bits,
+            )?);
         }
 
-        let stake = get_total_balance(state, online_indices.intersection(&transition_participants))?
-            as u64
-            * 3;
-        let min_stake =
-            get_total_balance(state, online_indices.intersection(&committee).collect())? as u64 * 2;
+        let online_participants = online_indices.intersection(&transition_participants);
+        let online_committee = online_indices.intersection(&committee);
+        let enough_online_stake = get_total_balance(state, online_participants.copied())? * 3
+            >= get_total_balance(state, online_committee.copied())? * 2;
+
         // If not enough stake, try next transition root
-        if stake < min_stake {
+        if !enough_online_stake {
             continue;
         }
 
         // Attestation <-> shard transition consistency
+        let expected_root = shard_transition.tree_hash_root();
         ensure!(
-            shard_transition_root == shard_transition.tree_hash_root(),
+            shard_transition_root == expected_root,
             Error::IncorrectRoot {
                 root: shard_transition_root,
-                expected_root: shard_transition.tree_hash_root()
+                expected_root,
             }
         );
 
         // Check `shard_head_root` of the winning root
-        let last_offset_index = shard_transition.shard_states.len() - 1;
-        let shard_head_root = shard_transition.shard_states[last_offset_index].latest_block_root;
+        let shard_head_root = shard_transition
+            .shard_states
+            .last()
+            .ok_or(Error::NoShardStates {})?
+            .latest_block_root;
         for attestation in transition_attestations {
             ensure!(
                 attestation.data.shard_head_root == shard_head_root,
                 Error::IncorrectRoot {
                     root: attestation.data.shard_head_root,
-                    expected_root: shard_head_root
+                    expected_root: shard_head_root,
                 }
             );
         }
 
         // Apply transition
-        apply_shard_transition(&mut state, shard, &shard_transition);
+        apply_shard_transition(state, shard, shard_transition, verify_signatures)?;
+
         // Apply proposer reward and cost
-        let beacon_proposer_index = get_beacon_proposer_index(&state)?;
-        let mut estimated_attester_reward = 0;
-        for attester in transition_participants {
-            estimated_attester_reward += get_base_reward(&state, attester);
-        }
-        let proposer_reward = (estimated_attester_reward / C::PROPOSER_REWARD_QUOTIENT) as Gwei;
-        increase_balance(state, beacon_proposer_index, proposer_reward);
+        let beacon_proposer_index = get_beacon_proposer_index(state)?;
+        let estimated_attester_reward = transition_participants
+            .into_iter()
+            .map(|attester| get_base_reward(state, attester))
+            .sum::<Result<Gwei>>()?;
+        let proposer_reward = estimated_attester_reward / C::PROPOSER_REWARD_QUOTIENT;
+        increase_balance(state, beacon_proposer_index, proposer_reward)?;
+
         let states_slots_lengths = izip!(
             shard_transition.shard_states.iter(),
-            get_offset_slots(&state, shard),
-            shard_transition.shard_block_lengths.iter()
+            get_offset_slots(state, shard)?,
+            shard_transition.shard_block_lengths.iter(),
         );
         for (shard_state, slot, length) in states_slots_lengths {
-            let proposer_index = get_shard_proposer_index(state, slot, shard);
-            decrease_balance(state, proposer_index, shard_state.gasprice * length);
+            let proposer_index = shard_proposer_index(state, slot, shard)?;
+            decrease_balance(state, proposer_index, shard_state.gasprice * length)?;
         }
 
         // Return winning transition root
@@ -248,91 +261,419 @@ pub fn process_crosslink_for_shard<C: Config>(
 
     // No winning transition root, ensure empty and return empty root
     ensure!(
-        shard_transition
-            == ShardTransition {
-                ..Default::default()
-            },
+        *shard_transition == ShardTransition::default(),
         Error::MissingTransitionRoot {}
     );
-    Ok(Root {
-        ..Default::default()
-    })
-}
 
-pub fn proccess_shard_transitions<C: Config>(
-    state: &mut BeaconState<C>,
-    shard_transitions: Vec<ShardTransition<C>>,
-    attestations: Vec<Attestation<C>>,
-) {
-    if compute_previous_slot(state.slot) > GENESIS_SLOT {
-        proccess_crosslinks(state, shard_transitions, attestations);
-    }
-    ensure!(
-        verify_empty_shard_transitions(state, shard_transitions),
-        Error::NotEmptyShardTransition {}
-    );
+    Ok(Root::zero())
 }
 
-pub fn verify_empty_shard_transitions<C: Config>(
+pub fn process_crosslinks<C: Config>(
     state: &mut BeaconState<C>,
-    shard_transitions: Vec<ShardTransition<C>>,
-) -> bool {
-    for shard in 1..get_active_shard_count(state) {
-        if (state.shard_states[shard as usize].slot != compute_previous_slot(state.slot)
-            && shard_transitions[shard as usize]
-                != ShardTransition {
-                    ..Default::default()
-                })
-        {
-            return false;
-        }
-    }
-    true
-}
-
-fn proccess_crosslinks<C: Config>(
-    state: &mut BeaconState<C>,
-    shard_transitions: Vec<ShardTransition<C>>,
-    attestations: Vec<Attestation<C>>,
+    shard_transitions: &[ShardTransition<C>],
+    attestations: &[Attestation<C>],
+    verify_signatures: bool,
 ) -> Result<()> {
     let on_time_attestation_slot = compute_previous_slot(state.slot);
     let committee_count =
-        get_committee_count_per_slot(state, compute_epoch_at_slot::<C>(on_time_attestation_slot));
+        get_committee_count_per_slot(state, compute_epoch_at_slot::<C>(on_time_attestation_slot))?;
+
     for committee_index in 0..committee_count {
-        // nezinau ar cia gerai, ten mapina, o cia tiesiog praiteratinu, no idea...
+        // All attestations in the block for this committee/shard and current slot
         let shard =
-            compute_shard_from_committee_index(state, committee_index, on_time_attestation_slot);
-
-        let shard_attestations = Vec::new();
-        for attestation in attestations {
-            if is_on_time_attestation(state, &attestation.data)
-                && attestation.data.index == committee_index
-            {
-                // cia gal kitaip attestation data reikia paduot, gal pati argumenta su referencu perduot
-                shard_attestations.push(attestation)
-            }
-        }
+            compute_shard_from_committee_index(state, committee_index, on_time_attestation_slot)?;
+        let shard_transition = shard_transitions
+            .get(shard as usize)
+            .ok_or(Error::MissingShardTransition { shard })?;
+
+        // Since the attestations are validated, all `shard_attestations` satisfy
+        // `attestation.data.shard == shard`.
+        let shard_attestations = attestations
+            .iter()
+            .filter(|attestation| {
+                is_on_time_attestation(state, &attestation.data)
+                    && attestation.data.index == committee_index
+            })
+            .collect::<Vec<_>>();
 
         let winning_root = process_crosslink_for_shard(
             state,
             committee_index,
-            shard_transitions[shard as usize],
-            shard_attestations,
+            shard_transition,
+            &shard_attestations,
+            verify_signatures,
         )?;
 
-        if winning_root != Root::default() {
-            // # Mark relevant pending attestations as creating a successful crosslink
-            for pending_attestation in state.current_epoch_attestations.into_iter() {
-                if is_winning_attestation(
-                    state,
-                    *pending_attestation,
-                    committee_index,
-                    winning_root,
-                ) {
-                    pending_attestation.crosslink_success = true;
-                }
+        if winning_root != Root::zero() {
+            // Mark relevant pending attestations as creating a successful crosslink
+            let winning_positions = state
+                .current_epoch_attestations
+                .iter()
+                .enumerate()
+                .filter(|(_, pending_attestation)| {
+                    is_winning_attestation(
+                        state,
+                        pending_attestation,
+                        committee_index,
+                        winning_root,
+                    )
+                })
+                .map(|(position, _)| position)
+                .collect::<Vec<_>>();
+
+            for position in winning_positions {
+                state.current_epoch_attestations.make_mut()[position].crosslink_success = true;
             }
         }
     }
+
     Ok(())
 }
+
+pub fn verify_empty_shard_transitions<C: Config>(
+    state: &BeaconState<C>,
+    shard_transitions: &[ShardTransition<C>],
+) -> Result<bool> {
+    for shard in 0..get_active_shard_count(state) {
+        if state.shard_states[shard as usize].slot != compute_previous_slot(state.slot) {
+            let shard_transition = shard_transitions
+                .get(shard as usize)
+                .ok_or(Error::MissingShardTransition { shard })?;
+
+            if *shard_transition != ShardTransition::default() {
+                return Ok(false);
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+pub fn process_shard_transitions<C: Config>(
+    state: &mut BeaconState<C>,
+    shard_transitions: &[ShardTransition<C>],
+    attestations: &[Attestation<C>],
+    verify_signatures: bool,
+) -> Result<()> {
+    // Process crosslinks
+    if compute_previous_slot(state.slot) > GENESIS_SLOT {
+        process_crosslinks(state, shard_transitions, attestations, verify_signatures)?;
+    }
+
+    // Verify the empty proposal shard states
+    ensure!(
+        verify_empty_shard_transitions(state, shard_transitions)?,
+        Error::NotEmptyShardTransition {}
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use spec_test_utils::testing::{self, assert_error};
+    use ssz_new::types::{BitList, VariableList};
+    use types::{
+        config::MainnetConfig,
+        containers::{AttestationData, ShardState},
+        primitives::AggregateSignatureBytes,
+    };
+
+    use super::*;
+
+    const TEST_SHARD: Shard = 0;
+
+    pub(crate) fn state(slot: u64) -> BeaconState<MainnetConfig> {
+        BeaconState {
+            slot,
+            online_countdown: Arc::new(VariableList::from(vec![MainnetConfig::ONLINE_PERIOD; 8])),
+            shard_states: Arc::new(VariableList::from(vec![ShardState::default(); 64])),
+            ..testing::state(8)
+        }
+    }
+
+    fn g2_point_at_infinity() -> bls::SignatureBytes {
+        let mut bytes = [0; 96];
+        bytes[0] = 0b1100_0000;
+        bls::SignatureBytes(bytes)
+    }
+
+    // A transition over the single offset slot of a state at slot 2 with no shard block in it.
+    fn empty_proposal_transition() -> ShardTransition<MainnetConfig> {
+        ShardTransition {
+            start_slot: 1,
+            shard_block_lengths: VariableList::from(vec![0]),
+            shard_data_roots: VariableList::from(vec![H256::zero()]),
+            shard_states: VariableList::from(vec![ShardState {
+                slot: 1,
+                gasprice: compute_updated_gasprice::<MainnetConfig>(0, 0),
+                latest_block_root: H256::zero(),
+            }]),
+            proposer_signature_aggregate: g2_point_at_infinity(),
+        }
+    }
+
+    fn on_time_attestation(
+        state: &BeaconState<MainnetConfig>,
+        shard_transition_root: Root,
+        shard_head_root: Root,
+    ) -> Attestation<MainnetConfig> {
+        let committee_size = get_beacon_committee(state, compute_previous_slot(state.slot), 0)
+            .expect("the test state has active validators")
+            .len();
+        let mut aggregation_bits =
+            BitList::with_capacity(committee_size).expect("committee fits in a bitlist");
+        for position in 0..committee_size {
+            aggregation_bits
+                .set(position, true)
+                .expect("position is within the committee");
+        }
+
+        Attestation {
+            aggregation_bits,
+            data: AttestationData {
+                slot: compute_previous_slot(state.slot),
+                index: 0,
+                shard_transition_root,
+                shard_head_root,
+                ..AttestationData::default()
+            },
+            signature: AggregateSignatureBytes::default(),
+        }
+    }
+
+    #[test]
+    fn test_apply_empty_proposal_transition() {
+        let mut state = state(2);
+        let transition = empty_proposal_transition();
+
+        apply_shard_transition(&mut state, TEST_SHARD, &transition, true)
+            .expect("transition is valid");
+
+        assert_eq!(
+            state.shard_states[TEST_SHARD as usize],
+            ShardState {
+                slot: 1,
+                ..transition.shard_states[0].clone()
+            },
+        );
+    }
+
+    #[test]
+    fn test_apply_shard_transition_at_genesis() {
+        let transition = empty_proposal_transition();
+
+        assert_error(
+            apply_shard_transition(&mut state(GENESIS_SLOT), TEST_SHARD, &transition, true),
+            |error: &Error| matches!(error, Error::InvalidSlot { .. }),
+        );
+    }
+
+    #[test]
+    fn test_apply_shard_transition_without_offset_slots() {
+        let transition = ShardTransition::default();
+
+        assert_error(
+            apply_shard_transition(&mut state(1), TEST_SHARD, &transition, true),
+            |error: &Error| matches!(error, Error::NoOffsetSlots { .. }),
+        );
+    }
+
+    #[test]
+    fn test_apply_shard_transition_with_incorrect_data_root_count() {
+        let transition = ShardTransition {
+            shard_data_roots: VariableList::from(vec![]),
+            ..empty_proposal_transition()
+        };
+
+        assert_error(
+            apply_shard_transition(&mut state(2), TEST_SHARD, &transition, true),
+            |error: &Error| matches!(error, Error::IncorrectDataRootCount { .. }),
+        );
+    }
+
+    #[test]
+    fn test_apply_shard_transition_with_incorrect_start_slot() {
+        let transition = ShardTransition {
+            start_slot: 0,
+            ..empty_proposal_transition()
+        };
+
+        assert_error(
+            apply_shard_transition(&mut state(2), TEST_SHARD, &transition, true),
+            |error: &Error| matches!(error, Error::IncorrectSlot { .. }),
+        );
+    }
+
+    #[test]
+    fn test_apply_shard_transition_with_incorrect_gasprice() {
+        let mut transition = empty_proposal_transition();
+        transition.shard_states[0].gasprice += 1;
+
+        assert_error(
+            apply_shard_transition(&mut state(2), TEST_SHARD, &transition, true),
+            |error: &Error| matches!(error, Error::IncorrectGasprice { .. }),
+        );
+    }
+
+    #[test]
+    fn test_apply_shard_transition_with_nonempty_root_for_empty_slot() {
+        let mut transition = empty_proposal_transition();
+        transition.shard_data_roots[0] = H256::repeat_byte(1);
+
+        assert_error(
+            apply_shard_transition(&mut state(2), TEST_SHARD, &transition, true),
+            |error: &Error| matches!(error, Error::NonEmptyRoot { .. }),
+        );
+    }
+
+    #[test]
+    fn test_apply_shard_transition_with_invalid_signature() {
+        let transition = ShardTransition {
+            proposer_signature_aggregate: bls::SignatureBytes::zero(),
+            ..empty_proposal_transition()
+        };
+
+        assert_error(
+            apply_shard_transition(&mut state(2), TEST_SHARD, &transition, true),
+            |error: &Error| matches!(error, Error::UnverifiedAggregateSignature {}),
+        );
+    }
+
+    #[test]
+    fn test_apply_shard_transition_without_verifying_signatures() {
+        let mut state = state(2);
+        let transition = ShardTransition {
+            proposer_signature_aggregate: bls::SignatureBytes::zero(),
+            ..empty_proposal_transition()
+        };
+
+        apply_shard_transition(&mut state, TEST_SHARD, &transition, false)
+            .expect("the invalid signature is not checked");
+
+        assert_eq!(state.shard_states[TEST_SHARD as usize].slot, 1);
+    }
+
+    #[test]
+    fn test_crosslink_without_attestations_for_nonempty_transition() {
+        let transition = empty_proposal_transition();
+
+        assert_error(
+            process_crosslink_for_shard(&mut state(2), 0, &transition, &[], true),
+            |error: &Error| matches!(error, Error::MissingTransitionRoot {}),
+        );
+    }
+
+    #[test]
+    fn test_crosslink_without_attestations_for_empty_transition() {
+        let root =
+            process_crosslink_for_shard(&mut state(2), 0, &ShardTransition::default(), &[], true)
+                .expect("empty transition needs no attestations");
+
+        assert_eq!(root, Root::zero());
+    }
+
+    #[test]
+    fn test_crosslink_with_inconsistent_transition_root() {
+        let mut state = state(2);
+        let transition = empty_proposal_transition();
+        let attestation = on_time_attestation(&state, H256::repeat_byte(1), H256::zero());
+
+        assert_error(
+            process_crosslink_for_shard(&mut state, 0, &transition, &[&attestation], true),
+            |error: &Error| matches!(error, Error::IncorrectRoot { .. }),
+        );
+    }
+
+    #[test]
+    fn test_crosslink_with_incorrect_shard_head_root() {
+        let mut state = state(2);
+        let transition = empty_proposal_transition();
+        let attestation =
+            on_time_attestation(&state, transition.tree_hash_root(), H256::repeat_byte(1));
+
+        assert_error(
+            process_crosslink_for_shard(&mut state, 0, &transition, &[&attestation], true),
+            |error: &Error| matches!(error, Error::IncorrectRoot { .. }),
+        );
+    }
+
+    #[test]
+    fn test_crosslink_with_transition_without_shard_states() {
+        let mut state = state(2);
+        let transition = ShardTransition::default();
+        let attestation = on_time_attestation(&state, transition.tree_hash_root(), H256::zero());
+
+        assert_error(
+            process_crosslink_for_shard(&mut state, 0, &transition, &[&attestation], true),
+            |error: &Error| matches!(error, Error::NoShardStates {}),
+        );
+    }
+
+    #[test]
+    fn test_verify_empty_shard_transitions_with_missing_transitions() {
+        assert_error(
+            verify_empty_shard_transitions(&state(2), &[]),
+            |error: &Error| matches!(error, Error::MissingShardTransition { .. }),
+        );
+    }
+
+    #[test]
+    fn test_process_shard_transitions_with_unprocessed_transition() {
+        let mut state = state(1);
+        state.shard_states.make_mut()[3].slot = 5;
+        let mut shard_transitions = vec![ShardTransition::default(); 64];
+        shard_transitions[3] = empty_proposal_transition();
+
+        assert_error(
+            process_shard_transitions(&mut state, &shard_transitions, &[], true),
+            |error: &Error| matches!(error, Error::NotEmptyShardTransition {}),
+        );
+    }
+
+    #[test]
+    fn test_process_shard_transitions_without_transitions() {
+        let mut state = state(1);
+        let shard_transitions = vec![ShardTransition::default(); 64];
+
+        process_shard_transitions(&mut state, &shard_transitions, &[], true)
+            .expect("empty transitions are valid");
+    }
+}
+
+#[cfg(test)]
+mod spec_tests {
+    use spec_test_utils::{testing, BlsSetting, Case};
+    use test_generator::test_resources;
+    use types::config::{MainnetConfig, MinimalConfig};
+
+    use super::*;
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/operations/shard_transition/*/*")]
+    fn mainnet_shard_transition(case: Case) {
+        run_shard_transition_case::<MainnetConfig>(case);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/operations/shard_transition/*/*")]
+    fn minimal_shard_transition(case: Case) {
+        run_shard_transition_case::<MinimalConfig>(case);
+    }
+
+    fn run_shard_transition_case<C: Config>(case: Case) {
+        let meta = case.meta();
+        let verify_signatures = meta.bls_setting != BlsSetting::Ignored;
+
+        let shard_transitions = case
+            .iterator("shard_transitions", meta.shard_transitions_count)
+            .collect::<Vec<ShardTransition<C>>>();
+        let attestations = case
+            .iterator("attestations", meta.attestations_count)
+            .collect::<Vec<Attestation<C>>>();
+
+        testing::run_case(case, |state: &mut BeaconState<C>| {
+            process_shard_transitions(state, &shard_transitions, &attestations, verify_signatures)
+        });
+    }
+}
@@ -0,0 +1,401 @@
+use anyhow::{ensure, Result};
+use helper_functions::{
+    accessors::{get_domain, get_latest_slot_for_shard},
+    cache::{beacon_state_hash_tree_root, shard_proposer_index},
+    crypto::bls_verify,
+    misc::{
+        compute_epoch_at_slot, compute_offset_slots, compute_signing_root, compute_updated_gasprice,
+    },
+};
+use ssz_new::SszHash as _;
+use types::{
+    beacon_state::BeaconState,
+    config::Config,
+    containers::{ShardBlock, ShardState, SignedShardBlock},
+    primitives::{Root, H256},
+};
+
+use crate::error::Error;
+
+pub fn shard_state_transition<C: Config>(
+    shard_state: &mut ShardState,
+    signed_block: &SignedShardBlock<C>,
+    beacon_parent_state: &BeaconState<C>,
+    validate_result: bool,
+) -> Result<()> {
+    verify_shard_block_message(beacon_parent_state, shard_state, &signed_block.message)?;
+    if validate_result {
+        verify_shard_block_signature(beacon_parent_state, signed_block)?;
+    }
+    process_shard_block(shard_state, &signed_block.message)
+}
+
+pub fn verify_shard_block_message<C: Config>(
+    beacon_parent_state: &BeaconState<C>,
+    shard_parent_state: &ShardState,
+    block: &ShardBlock<C>,
+) -> Result<()> {
+    // Check `shard_parent_root` field
+    ensure!(
+        block.shard_parent_root == shard_parent_state.latest_block_root,
+        Error::IncorrectShardParentRoot {
+            root: block.shard_parent_root,
+            expected_root: shard_parent_state.latest_block_root,
+        }
+    );
+
+    // Check `beacon_parent_root` field
+    let expected_beacon_parent_root = beacon_parent_root(beacon_parent_state);
+    ensure!(
+        block.beacon_parent_root == expected_beacon_parent_root,
+        Error::IncorrectBeaconParentRoot {
+            root: block.beacon_parent_root,
+            expected_root: expected_beacon_parent_root,
+        }
+    );
+
+    // Check `slot` field
+    let offset_slots = compute_offset_slots::<C>(
+        get_latest_slot_for_shard(beacon_parent_state, block.shard)?,
+        block.slot + 1,
+    );
+    ensure!(
+        offset_slots.contains(&block.slot),
+        Error::ShardBlockSlotNotInOffsetSlots { slot: block.slot }
+    );
+
+    // Check `proposer_index` field
+    let expected_proposer_index =
+        shard_proposer_index(beacon_parent_state, block.slot, block.shard)?;
+    ensure!(
+        block.proposer_index == expected_proposer_index,
+        Error::IncorrectShardProposer {
+            proposer_index: block.proposer_index,
+            expected_proposer_index,
+        }
+    );
+
+    // Check `body` field. `ByteList` already enforces `MAX_SHARD_BLOCK_SIZE`.
+    ensure!(!block.body.is_empty(), Error::EmptyShardBlockBody {});
+
+    Ok(())
+}
+
+// The latest block header of a state only gets its state root in the next `process_slot`.
+pub fn beacon_parent_root<C: Config>(beacon_parent_state: &BeaconState<C>) -> Root {
+    let mut beacon_parent_block_header = beacon_parent_state.latest_block_header;
+    if beacon_parent_block_header.state_root == H256::zero() {
+        beacon_parent_block_header.state_root = beacon_state_hash_tree_root(beacon_parent_state);
+    }
+    beacon_parent_block_header.tree_hash_root()
+}
+
+pub fn verify_shard_block_signature<C: Config>(
+    beacon_parent_state: &BeaconState<C>,
+    signed_block: &SignedShardBlock<C>,
+) -> Result<()> {
+    let block = &signed_block.message;
+    let proposer = beacon_parent_state
+        .validators
+        .get(block.proposer_index as usize)
+        .ok_or(Error::UnknownShardProposer {
+            proposer_index: block.proposer_index,
+        })?;
+    let domain = get_domain(
+        beacon_parent_state,
+        C::DOMAIN_SHARD_PROPOSAL,
+        Some(compute_epoch_at_slot::<C>(block.slot)),
+    );
+    let signing_root = compute_signing_root(block, domain);
+
+    ensure!(
+        bls_verify(
+            proposer.pubkey,
+            signing_root.as_bytes(),
+            signed_block.signature
+        )?,
+        Error::UnverifiedShardBlockSignature {}
+    );
+
+    Ok(())
+}
+
+// Blocks with an empty body stand in for slots in which no shard block was proposed.
+// They only lower the gasprice, so their parent root is not checked.
+pub fn process_shard_block<C: Config>(
+    shard_state: &mut ShardState,
+    block: &ShardBlock<C>,
+) -> Result<()> {
+    ensure!(
+        block.slot > shard_state.slot,
+        Error::ShardBlockSlotNotLater {
+            slot: block.slot,
+            parent_slot: shard_state.slot,
+        }
+    );
+
+    let shard_block_length = block.body.len() as u64;
+
+    if shard_block_length != 0 {
+        ensure!(
+            block.shard_parent_root == shard_state.latest_block_root,
+            Error::IncorrectShardParentRoot {
+                root: block.shard_parent_root,
+                expected_root: shard_state.latest_block_root,
+            }
+        );
+    }
+
+    shard_state.slot = block.slot;
+    shard_state.gasprice = compute_updated_gasprice::<C>(shard_state.gasprice, shard_block_length);
+
+    if shard_block_length != 0 {
+        shard_state.latest_block_root = block.tree_hash_root();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use spec_test_utils::testing::{self, assert_error, secret_key};
+    use ssz_new::types::{ByteList, VariableList};
+    use types::config::MinimalConfig;
+
+    use super::*;
+
+    fn beacon_parent_state() -> BeaconState<MinimalConfig> {
+        BeaconState {
+            slot: 1,
+            shard_states: Arc::new(VariableList::from(vec![ShardState::default()])),
+            ..testing::state(8)
+        }
+    }
+
+    fn parent_state() -> ShardState {
+        ShardState {
+            slot: 3,
+            gasprice: 800,
+            latest_block_root: H256::repeat_byte(1),
+        }
+    }
+
+    fn block(slot: u64, body: Vec<u8>) -> ShardBlock<MinimalConfig> {
+        ShardBlock {
+            shard_parent_root: H256::repeat_byte(1),
+            slot,
+            body: ByteList::from_bytes(body).expect("body is shorter than the limit"),
+            ..ShardBlock::default()
+        }
+    }
+
+    fn proposed_block(
+        beacon_parent_state: &BeaconState<MinimalConfig>,
+        body: Vec<u8>,
+    ) -> ShardBlock<MinimalConfig> {
+        ShardBlock {
+            beacon_parent_root: beacon_parent_root(beacon_parent_state),
+            proposer_index: shard_proposer_index(beacon_parent_state, 1, 0)
+                .expect("the test state has active validators"),
+            ..block(1, body)
+        }
+    }
+
+    fn sign(
+        beacon_parent_state: &BeaconState<MinimalConfig>,
+        block: ShardBlock<MinimalConfig>,
+    ) -> SignedShardBlock<MinimalConfig> {
+        let domain = get_domain(
+            beacon_parent_state,
+            MinimalConfig::DOMAIN_SHARD_PROPOSAL,
+            Some(compute_epoch_at_slot::<MinimalConfig>(block.slot)),
+        );
+        let signature = secret_key(block.proposer_index)
+            .sign(compute_signing_root(&block, domain))
+            .into();
+
+        SignedShardBlock {
+            message: block,
+            signature,
+        }
+    }
+
+    #[test]
+    fn test_process_shard_block() {
+        let mut shard_state = parent_state();
+        let block = block(5, vec![1, 2, 3]);
+
+        process_shard_block(&mut shard_state, &block).expect("block is valid");
+
+        assert_eq!(
+            shard_state,
+            ShardState {
+                slot: 5,
+                gasprice: compute_updated_gasprice::<MinimalConfig>(800, 3),
+                latest_block_root: block.tree_hash_root(),
+            },
+        );
+    }
+
+    #[test]
+    fn test_process_empty_shard_block() {
+        let mut shard_state = parent_state();
+        let block = ShardBlock {
+            shard_parent_root: H256::zero(),
+            ..block(4, vec![])
+        };
+
+        process_shard_block(&mut shard_state, &block).expect("empty block is valid");
+
+        assert_eq!(
+            shard_state,
+            ShardState {
+                slot: 4,
+                gasprice: 700,
+                latest_block_root: H256::repeat_byte(1),
+            },
+        );
+    }
+
+    #[test]
+    fn test_process_shard_block_wrong_parent() {
+        let mut shard_state = parent_state();
+        let block = ShardBlock {
+            shard_parent_root: H256::repeat_byte(2),
+            ..block(5, vec![1])
+        };
+
+        assert_error(
+            process_shard_block(&mut shard_state, &block),
+            |error: &Error| matches!(error, Error::IncorrectShardParentRoot { .. }),
+        );
+        assert_eq!(shard_state, parent_state());
+    }
+
+    #[test]
+    fn test_process_shard_block_old_slot() {
+        let mut shard_state = parent_state();
+
+        assert_error(
+            process_shard_block(&mut shard_state, &block(3, vec![1])),
+            |error: &Error| matches!(error, Error::ShardBlockSlotNotLater { .. }),
+        );
+        assert_eq!(shard_state, parent_state());
+    }
+
+    #[test]
+    fn test_shard_state_transition() {
+        let beacon_parent_state = beacon_parent_state();
+        let signed_block = sign(
+            &beacon_parent_state,
+            proposed_block(&beacon_parent_state, vec![1]),
+        );
+        let mut shard_state = ShardState {
+            latest_block_root: H256::repeat_byte(1),
+            ..ShardState::default()
+        };
+
+        shard_state_transition(&mut shard_state, &signed_block, &beacon_parent_state, true)
+            .expect("block is valid");
+
+        assert_eq!(shard_state.slot, 1);
+        assert_eq!(
+            shard_state.latest_block_root,
+            signed_block.message.tree_hash_root(),
+        );
+    }
+
+    #[test]
+    fn test_verify_shard_block_message_with_wrong_beacon_parent() {
+        let beacon_parent_state = beacon_parent_state();
+        let block = ShardBlock {
+            beacon_parent_root: H256::repeat_byte(2),
+            ..proposed_block(&beacon_parent_state, vec![1])
+        };
+
+        assert_error(
+            verify_shard_block_message(&beacon_parent_state, &parent_state(), &block),
+            |error: &Error| matches!(error, Error::IncorrectBeaconParentRoot { .. }),
+        );
+    }
+
+    #[test]
+    fn test_verify_shard_block_message_with_processed_beacon_parent() {
+        let mut beacon_parent_state = beacon_parent_state();
+        let block = proposed_block(&beacon_parent_state, vec![1]);
+        beacon_parent_state.latest_block_header.state_root =
+            beacon_state_hash_tree_root(&beacon_parent_state);
+
+        verify_shard_block_message(&beacon_parent_state, &parent_state(), &block)
+            .expect("the filled in state root matches the one used for the block");
+    }
+
+    #[test]
+    fn test_verify_shard_block_message_with_slot_outside_offsets() {
+        let beacon_parent_state = beacon_parent_state();
+        let block = ShardBlock {
+            slot: 4,
+            ..proposed_block(&beacon_parent_state, vec![1])
+        };
+
+        assert_error(
+            verify_shard_block_message(&beacon_parent_state, &parent_state(), &block),
+            |error: &Error| matches!(error, Error::ShardBlockSlotNotInOffsetSlots { .. }),
+        );
+    }
+
+    #[test]
+    fn test_verify_shard_block_message_with_wrong_proposer() {
+        let beacon_parent_state = beacon_parent_state();
+        let mut block = proposed_block(&beacon_parent_state, vec![1]);
+        block.proposer_index = (block.proposer_index + 1) % 8;
+
+        assert_error(
+            verify_shard_block_message(&beacon_parent_state, &parent_state(), &block),
+            |error: &Error| matches!(error, Error::IncorrectShardProposer { .. }),
+        );
+    }
+
+    #[test]
+    fn test_verify_shard_block_message_with_empty_body() {
+        let beacon_parent_state = beacon_parent_state();
+        let block = proposed_block(&beacon_parent_state, vec![]);
+
+        assert_error(
+            verify_shard_block_message(&beacon_parent_state, &parent_state(), &block),
+            |error: &Error| matches!(error, Error::EmptyShardBlockBody {}),
+        );
+    }
+
+    #[test]
+    fn test_verify_shard_block_signature_with_unknown_proposer() {
+        let beacon_parent_state = beacon_parent_state();
+        let mut signed_block = sign(
+            &beacon_parent_state,
+            proposed_block(&beacon_parent_state, vec![1]),
+        );
+        signed_block.message.proposer_index = 100;
+
+        assert_error(
+            verify_shard_block_signature(&beacon_parent_state, &signed_block),
+            |error: &Error| matches!(error, Error::UnknownShardProposer { .. }),
+        );
+    }
+
+    #[test]
+    fn test_verify_shard_block_signature_with_wrong_signature() {
+        let beacon_parent_state = beacon_parent_state();
+        let mut signed_block = sign(
+            &beacon_parent_state,
+            proposed_block(&beacon_parent_state, vec![1]),
+        );
+        signed_block.message.body = ByteList::from_bytes(vec![2]).expect("body fits");
+
+        assert_error(
+            verify_shard_block_signature(&beacon_parent_state, &signed_block),
+            |error: &Error| matches!(error, Error::UnverifiedShardBlockSignature {}),
+        );
+    }
+}
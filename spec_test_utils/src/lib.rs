@@ -8,6 +8,9 @@ use serde::{de::DeserializeOwned, Deserialize};
 use serde_repr::Deserialize_repr;
 use ssz_new::SszDecode;
 
+#[cfg(feature = "testing")]
+pub mod testing;
+
 #[derive(PartialEq, Eq, Deserialize_repr)]
 #[repr(u8)]
 pub enum BlsSetting {
@@ -26,8 +29,10 @@ impl Default for BlsSetting {
 #[serde(default)]
 pub struct Meta {
     pub bls_setting: BlsSetting,
+    pub attestations_count: usize,
     pub blocks_count: usize,
     pub deposits_count: usize,
+    pub shard_transitions_count: usize,
 }
 
 #[derive(Clone, Copy, From)]
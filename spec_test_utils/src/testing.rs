@@ -0,0 +1,84 @@
+//! Fixtures and assertions shared by the tests of crates that process `BeaconState`s.
+
+use core::{convert::TryFrom as _, fmt::Debug};
+use std::{error::Error as StdError, sync::Arc};
+
+use anyhow::Result;
+use bls::{SecretKey, SecretKeyBytes};
+use ssz_new::{types::VariableList, SszDecode};
+use types::{
+    beacon_state::BeaconState, config::Config, consts::FAR_FUTURE_EPOCH, containers::Validator,
+    primitives::ValidatorIndex,
+};
+
+use crate::Case;
+
+/// Secret key of the validator at `index` in [`validators`].
+#[must_use]
+pub fn secret_key(index: ValidatorIndex) -> SecretKey {
+    let mut bytes = [0; 32];
+    bytes[24..].copy_from_slice(&(index + 1).to_be_bytes());
+    SecretKey::try_from(SecretKeyBytes::from(bytes)).expect("bytes represent a valid secret key")
+}
+
+/// Validators that are active from genesis with the maximum effective balance and have not
+/// revealed all of their custody secrets.
+#[must_use]
+pub fn validators<C: Config>(count: usize) -> Vec<Validator> {
+    (0..count as ValidatorIndex)
+        .map(|index| Validator {
+            pubkey: secret_key(index).to_public_key().into(),
+            effective_balance: C::MAX_EFFECTIVE_BALANCE,
+            activation_epoch: 0,
+            exit_epoch: FAR_FUTURE_EPOCH,
+            withdrawable_epoch: FAR_FUTURE_EPOCH,
+            all_custody_secrets_revealed_epoch: FAR_FUTURE_EPOCH,
+            ..Validator::default()
+        })
+        .collect()
+}
+
+/// Genesis state with `validator_count` [`validators`], each with the maximum balance.
+#[must_use]
+pub fn state<C: Config>(validator_count: usize) -> BeaconState<C> {
+    BeaconState {
+        validators: Arc::new(VariableList::from(validators::<C>(validator_count))),
+        balances: Arc::new(VariableList::from(vec![
+            C::MAX_EFFECTIVE_BALANCE;
+            validator_count
+        ])),
+        ..BeaconState::default()
+    }
+}
+
+/// Asserts that `result` failed with an error of type `E` that satisfies `is_expected`.
+pub fn assert_error<T: Debug, E: StdError + Send + Sync + 'static>(
+    result: Result<T>,
+    is_expected: impl FnOnce(&E) -> bool,
+) {
+    let error = result.expect_err("result should be an error");
+    let error = error
+        .downcast_ref::<E>()
+        .unwrap_or_else(|| panic!("error has an unexpected type: {:?}", error));
+    assert!(is_expected(error), "unexpected error: {:?}", error);
+}
+
+/// Runs `process` on the `pre` state of `case`. Processing must succeed and produce the `post`
+/// state if the case has one and must fail otherwise.
+pub fn run_case<S: SszDecode + PartialEq + Debug>(
+    case: Case,
+    process: impl FnOnce(&mut S) -> Result<()>,
+) {
+    let mut state: S = case.ssz("pre");
+    let post = case.try_ssz("post");
+
+    let result = process(&mut state);
+
+    match post {
+        Some(expected_post) => {
+            result.expect("processing should succeed when there is a post-state");
+            assert_eq!(state, expected_post);
+        }
+        None => assert!(result.is_err()),
+    }
+}
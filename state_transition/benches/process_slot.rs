@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use helper_functions::tree_hash_cache::BeaconStateTreeHashCache;
+use spec_test_utils::testing;
+use state_transition::process_slot;
+use types::{beacon_state::BeaconState, config::MainnetConfig};
+
+const VALIDATOR_COUNT: usize = 4096;
+
+fn process_slot_after_clone(criterion: &mut Criterion) {
+    let state = testing::state::<MainnetConfig>(VALIDATOR_COUNT);
+    let mut tree_hash_cache = BeaconStateTreeHashCache::new();
+    tree_hash_cache.tree_hash_root(&state);
+
+    let mut group = criterion.benchmark_group("process_slot");
+
+    group.bench_function("unshared state", |bencher| {
+        bencher.iter_batched(
+            || {
+                let mut unshared = state.clone();
+                unshared.block_roots = Arc::new((*state.block_roots).clone());
+                unshared.state_roots = Arc::new((*state.state_roots).clone());
+                let mut unshared_cache = BeaconStateTreeHashCache::new();
+                unshared_cache.tree_hash_root(&unshared);
+                (unshared, unshared_cache)
+            },
+            |(mut unshared, mut unshared_cache): (BeaconState<MainnetConfig>, _)| {
+                process_slot(&mut unshared, &mut unshared_cache)
+                    .expect("slot processing cannot fail");
+                unshared
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    // `state` outlives the clones, so the first write to `block_roots` and `state_roots` copies
+    // them like it would after a clone in fork choice.
+    group.bench_function("state shared with a clone", |bencher| {
+        bencher.iter_batched(
+            || (state.clone(), tree_hash_cache.clone()),
+            |(mut clone, mut clone_cache)| {
+                process_slot(&mut clone, &mut clone_cache).expect("slot processing cannot fail");
+                clone
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, process_slot_after_clone);
+criterion_main!(benches);
@@ -0,0 +1,610 @@
+use core::convert::TryFrom as _;
+use std::collections::BTreeSet;
+
+use anyhow::{ensure, Result};
+use custody_game::{get_custody_period_for_validator, process_custody_game_operations};
+use helper_functions::{
+    accessors::{
+        compute_shard_from_committee_index, get_active_shard_count, get_beacon_committee,
+        get_beacon_proposer_index, get_block_root_at_slot, get_committee_count_per_slot,
+        get_current_epoch, get_domain, get_indexed_attestation, get_previous_epoch, get_randao_mix,
+    },
+    crypto::bls_verify,
+    misc::{compute_domain, compute_epoch_at_slot, compute_previous_slot, compute_signing_root},
+    mutators::{increase_balance, initiate_validator_exit, slash_validator},
+    predicates::{
+        is_active_validator, is_on_time_attestation, is_slashable_attestation_data,
+        is_slashable_validator, is_valid_merkle_branch, validate_indexed_attestation,
+    },
+};
+use light_client::process_light_client_aggregate;
+use shard_transition_processing::process_shard_transitions;
+use ssz_new::SszHash as _;
+use typenum::Unsigned as _;
+use types::{
+    arc_ext::ArcExt as _,
+    beacon_state::BeaconState,
+    chain_spec::ChainSpec,
+    config::Config,
+    consts::{DepositContractTreeDepth, FAR_FUTURE_EPOCH, GENESIS_SLOT},
+    containers::{
+        Attestation, AttesterSlashing, BeaconBlock, BeaconBlockBody, BeaconBlockHeader, Deposit,
+        DepositData, DepositMessage, PendingAttestation, ProposerSlashing, ShardTransition,
+        SignedVoluntaryExit, Validator,
+    },
+    primitives::{ValidatorIndex, H256},
+};
+
+use crate::error::Error;
+
+pub fn process_block<C: Config>(
+    state: &mut BeaconState<C>,
+    block: &BeaconBlock<C>,
+    verify_signatures: bool,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    process_block_header(state, block)?;
+    process_randao(state, &block.body, verify_signatures)?;
+    process_eth1_data(state, &block.body)?;
+    process_light_client_aggregate(state, &block.body, verify_signatures)?;
+    process_operations(state, &block.body, verify_signatures, chain_spec)
+}
+
+pub fn process_block_header<C: Config>(
+    state: &mut BeaconState<C>,
+    block: &BeaconBlock<C>,
+) -> Result<()> {
+    ensure!(
+        block.slot == state.slot,
+        Error::IncorrectBlockSlot {
+            slot: block.slot,
+            state_slot: state.slot,
+        },
+    );
+
+    ensure!(
+        block.slot > state.latest_block_header.slot,
+        Error::BlockNotNewer {
+            slot: block.slot,
+            latest_slot: state.latest_block_header.slot,
+        },
+    );
+
+    let expected_proposer_index = get_beacon_proposer_index(state)?;
+    ensure!(
+        block.proposer_index == expected_proposer_index,
+        Error::IncorrectProposer {
+            proposer_index: block.proposer_index,
+            expected_proposer_index,
+        },
+    );
+
+    let expected_root = state.latest_block_header.tree_hash_root();
+    ensure!(
+        block.parent_root == expected_root,
+        Error::IncorrectParentRoot {
+            root: block.parent_root,
+            expected_root,
+        },
+    );
+
+    // The state root is filled in by the next call to `process_slot`.
+    state.latest_block_header = BeaconBlockHeader {
+        slot: block.slot,
+        proposer_index: block.proposer_index,
+        parent_root: block.parent_root,
+        state_root: H256::zero(),
+        body_root: block.body.tree_hash_root(),
+    };
+
+    let proposer = validator(state, block.proposer_index)?;
+    ensure!(
+        !proposer.slashed,
+        Error::ProposerSlashed {
+            index: block.proposer_index,
+        },
+    );
+
+    Ok(())
+}
+
+pub fn process_randao<C: Config>(
+    state: &mut BeaconState<C>,
+    body: &BeaconBlockBody<C>,
+    verify_signatures: bool,
+) -> Result<()> {
+    let epoch = get_current_epoch(state);
+
+    if verify_signatures {
+        let proposer = validator(state, get_beacon_proposer_index(state)?)?;
+        let signing_root = compute_signing_root(&epoch, get_domain(state, C::DOMAIN_RANDAO, None));
+        ensure!(
+            bls_verify(proposer.pubkey, signing_root.as_bytes(), body.randao_reveal)?,
+            Error::InvalidRandaoReveal {},
+        );
+    }
+
+    let mix = get_randao_mix(state, epoch)? ^ hashing::hash(body.randao_reveal.as_bytes());
+    let index = usize::try_from(epoch % C::EpochsPerHistoricalVector::U64)?;
+    state.randao_mixes.make_mut()[index] = mix;
+
+    Ok(())
+}
+
+pub fn process_eth1_data<C: Config>(
+    state: &mut BeaconState<C>,
+    body: &BeaconBlockBody<C>,
+) -> Result<()> {
+    state
+        .eth1_data_votes
+        .push(body.eth1_data)
+        .map_err(|_| Error::Eth1DataVotesFull {})?;
+
+    let vote_count = state
+        .eth1_data_votes
+        .iter()
+        .filter(|vote| **vote == body.eth1_data)
+        .count();
+
+    if vote_count * 2 > C::SlotsPerEth1VotingPeriod::USIZE {
+        state.eth1_data = body.eth1_data;
+    }
+
+    Ok(())
+}
+
+pub fn process_operations<C: Config>(
+    state: &mut BeaconState<C>,
+    body: &BeaconBlockBody<C>,
+    verify_signatures: bool,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    // Verify that outstanding deposits are processed up to the maximum number of deposits
+    let outstanding_deposits = state
+        .eth1_data
+        .deposit_count
+        .saturating_sub(state.eth1_deposit_index);
+    let expected_count = C::MaxDeposits::U64.min(outstanding_deposits);
+    let count = u64::try_from(body.deposits.len())?;
+    ensure!(
+        count == expected_count,
+        Error::IncorrectDepositCount {
+            count,
+            expected_count,
+        },
+    );
+
+    for proposer_slashing in body.proposer_slashings.iter() {
+        process_proposer_slashing(state, proposer_slashing, verify_signatures, chain_spec)?;
+    }
+    for attester_slashing in body.attester_slashings.iter() {
+        process_attester_slashing(state, attester_slashing, verify_signatures, chain_spec)?;
+    }
+    for attestation in body.attestations.iter() {
+        process_attestation(state, attestation, verify_signatures)?;
+    }
+    for deposit in body.deposits.iter() {
+        process_deposit(state, deposit, chain_spec)?;
+    }
+    for voluntary_exit in body.voluntary_exits.iter() {
+        process_voluntary_exit(state, voluntary_exit, verify_signatures, chain_spec)?;
+    }
+
+    process_custody_game_operations(state, body, verify_signatures, chain_spec)?;
+    process_shard_transitions(
+        state,
+        &body.shard_transitions,
+        &body.attestations,
+        verify_signatures,
+    )
+}
+
+pub fn process_proposer_slashing<C: Config>(
+    state: &mut BeaconState<C>,
+    proposer_slashing: &ProposerSlashing,
+    verify_signatures: bool,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let header_1 = proposer_slashing.signed_header_1.message;
+    let header_2 = proposer_slashing.signed_header_2.message;
+
+    ensure!(
+        header_1.slot == header_2.slot,
+        Error::ProposerSlashingSlotMismatch {
+            slot_1: header_1.slot,
+            slot_2: header_2.slot,
+        },
+    );
+
+    ensure!(
+        header_1.proposer_index == header_2.proposer_index,
+        Error::ProposerSlashingProposerMismatch {
+            index_1: header_1.proposer_index,
+            index_2: header_2.proposer_index,
+        },
+    );
+
+    ensure!(header_1 != header_2, Error::ProposerSlashingSameHeader {});
+
+    let index = header_1.proposer_index;
+    let proposer = validator(state, index)?;
+    ensure!(
+        is_slashable_validator(proposer, get_current_epoch(state)),
+        Error::ValidatorNotSlashable { index },
+    );
+
+    if verify_signatures {
+        for signed_header in &[
+            proposer_slashing.signed_header_1,
+            proposer_slashing.signed_header_2,
+        ] {
+            let domain = get_domain(
+                state,
+                C::DOMAIN_BEACON_PROPOSER,
+                Some(compute_epoch_at_slot::<C>(signed_header.message.slot)),
+            );
+            let signing_root = compute_signing_root(&signed_header.message, domain);
+            ensure!(
+                bls_verify(
+                    proposer.pubkey,
+                    signing_root.as_bytes(),
+                    signed_header.signature,
+                )?,
+                Error::InvalidProposerSlashingSignature {},
+            );
+        }
+    }
+
+    slash_validator(state, index, None, chain_spec)
+}
+
+pub fn process_attester_slashing<C: Config>(
+    state: &mut BeaconState<C>,
+    attester_slashing: &AttesterSlashing<C>,
+    verify_signatures: bool,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let attestation_1 = &attester_slashing.attestation_1;
+    let attestation_2 = &attester_slashing.attestation_2;
+
+    ensure!(
+        is_slashable_attestation_data(&attestation_1.data, &attestation_2.data),
+        Error::AttestationDataNotSlashable {},
+    );
+
+    validate_indexed_attestation(state, attestation_1, verify_signatures)?;
+    validate_indexed_attestation(state, attestation_2, verify_signatures)?;
+
+    let indices_1 = attestation_1
+        .attesting_indices
+        .iter()
+        .copied()
+        .collect::<BTreeSet<_>>();
+    let indices_2 = attestation_2
+        .attesting_indices
+        .iter()
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    let mut slashed_any = false;
+
+    for &index in indices_1.intersection(&indices_2) {
+        if is_slashable_validator(validator(state, index)?, get_current_epoch(state)) {
+            slash_validator(state, index, None, chain_spec)?;
+            slashed_any = true;
+        }
+    }
+
+    ensure!(slashed_any, Error::NoValidatorSlashed {});
+
+    Ok(())
+}
+
+pub fn validate_attestation<C: Config>(
+    state: &BeaconState<C>,
+    attestation: &Attestation<C>,
+    verify_signatures: bool,
+) -> Result<()> {
+    let data = attestation.data;
+
+    let committee_count = get_committee_count_per_slot(state, data.target.epoch)?;
+    ensure!(
+        data.index < committee_count,
+        Error::CommitteeIndexOutOfRange {
+            index: data.index,
+            committee_count,
+        },
+    );
+
+    let active_shard_count = get_active_shard_count(state);
+    ensure!(
+        data.index < active_shard_count,
+        Error::CommitteeIndexOutOfRange {
+            index: data.index,
+            committee_count: active_shard_count,
+        },
+    );
+
+    let current_epoch = get_current_epoch(state);
+    ensure!(
+        data.target.epoch == get_previous_epoch(state) || data.target.epoch == current_epoch,
+        Error::TargetEpochOutOfRange {
+            epoch: data.target.epoch,
+        },
+    );
+
+    let slot_epoch = compute_epoch_at_slot::<C>(data.slot);
+    ensure!(
+        data.target.epoch == slot_epoch,
+        Error::TargetEpochSlotMismatch {
+            epoch: data.target.epoch,
+            slot_epoch,
+        },
+    );
+
+    ensure!(
+        data.slot + C::MIN_ATTESTATION_INCLUSION_DELAY <= state.slot
+            && state.slot <= data.slot + C::SlotsPerEpoch::U64,
+        Error::AttestationInclusionOutOfRange {
+            slot: data.slot,
+            state_slot: state.slot,
+        },
+    );
+
+    let committee = get_beacon_committee(state, data.slot, data.index)?;
+    ensure!(
+        attestation.aggregation_bits.len() == committee.len(),
+        Error::AggregationBitsLengthMismatch {
+            length: attestation.aggregation_bits.len(),
+            committee_length: committee.len(),
+        },
+    );
+
+    let justified_checkpoint = if data.target.epoch == current_epoch {
+        state.current_justified_checkpoint
+    } else {
+        state.previous_justified_checkpoint
+    };
+    ensure!(
+        data.source == justified_checkpoint,
+        Error::IncorrectAttestationSource {},
+    );
+
+    if is_on_time_attestation(state, &data) {
+        let expected_root = get_block_root_at_slot(state, compute_previous_slot(state.slot))?;
+        ensure!(
+            data.beacon_block_root == expected_root,
+            Error::IncorrectAttestationBlockRoot {
+                root: data.beacon_block_root,
+                expected_root,
+            },
+        );
+
+        let expected_shard = compute_shard_from_committee_index(state, data.index, data.slot)?;
+        ensure!(
+            data.shard == expected_shard,
+            Error::IncorrectAttestationShard {
+                shard: data.shard,
+                expected_shard,
+            },
+        );
+
+        // On-time attestations have a nonempty shard transition root except at genesis.
+        let empty_transition_root = ShardTransition::<C>::default().tree_hash_root();
+        let has_empty_transition = data.shard_transition_root == empty_transition_root;
+        ensure!(
+            has_empty_transition == (data.slot == GENESIS_SLOT),
+            Error::IncorrectShardTransitionRoot {
+                root: data.shard_transition_root,
+            },
+        );
+    } else {
+        ensure!(
+            data.slot < compute_previous_slot(state.slot),
+            Error::AttestationNotLate {
+                slot: data.slot,
+                state_slot: state.slot,
+            },
+        );
+
+        // Late attestations cannot have a shard transition root
+        ensure!(
+            data.shard_transition_root == H256::zero(),
+            Error::IncorrectShardTransitionRoot {
+                root: data.shard_transition_root,
+            },
+        );
+    }
+
+    validate_indexed_attestation(
+        state,
+        &get_indexed_attestation(state, attestation)?,
+        verify_signatures,
+    )
+}
+
+pub fn process_attestation<C: Config>(
+    state: &mut BeaconState<C>,
+    attestation: &Attestation<C>,
+    verify_signatures: bool,
+) -> Result<()> {
+    validate_attestation(state, attestation, verify_signatures)?;
+
+    let pending_attestation = PendingAttestation {
+        aggregation_bits: attestation.aggregation_bits.clone(),
+        data: attestation.data,
+        inclusion_delay: state.slot - attestation.data.slot,
+        proposer_index: get_beacon_proposer_index(state)?,
+        // Filled in by `process_shard_transitions`.
+        crosslink_success: false,
+    };
+
+    let epoch_attestations = if attestation.data.target.epoch == get_current_epoch(state) {
+        &mut state.current_epoch_attestations
+    } else {
+        &mut state.previous_epoch_attestations
+    };
+
+    epoch_attestations
+        .make_mut()
+        .push(pending_attestation)
+        .map_err(|_| Error::EpochAttestationsFull {})?;
+
+    Ok(())
+}
+
+#[must_use]
+pub fn get_validator_from_deposit<C: Config>(
+    state: &BeaconState<C>,
+    deposit_data: &DepositData,
+) -> Validator {
+    let amount = deposit_data.amount;
+    let effective_balance =
+        (amount - amount % C::EFFECTIVE_BALANCE_INCREMENT).min(C::MAX_EFFECTIVE_BALANCE);
+
+    // The new validator will be appended to the registry.
+    let validator_index = state.validators.len() as ValidatorIndex;
+    let next_custody_secret_to_reveal =
+        get_custody_period_for_validator::<C>(validator_index, get_current_epoch(state));
+
+    Validator {
+        pubkey: deposit_data.pubkey,
+        withdrawal_credentials: deposit_data.withdrawal_credentials,
+        effective_balance,
+        slashed: false,
+        activation_eligibility_epoch: FAR_FUTURE_EPOCH,
+        activation_epoch: FAR_FUTURE_EPOCH,
+        exit_epoch: FAR_FUTURE_EPOCH,
+        withdrawable_epoch: FAR_FUTURE_EPOCH,
+        next_custody_secret_to_reveal,
+        all_custody_secrets_revealed_epoch: FAR_FUTURE_EPOCH,
+    }
+}
+
+pub fn process_deposit<C: Config>(
+    state: &mut BeaconState<C>,
+    deposit: &Deposit,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    ensure!(
+        is_valid_merkle_branch(
+            deposit.data.tree_hash_root(),
+            &deposit.proof,
+            DepositContractTreeDepth::USIZE + 1,
+            state.eth1_deposit_index,
+            state.eth1_data.deposit_root,
+        )?,
+        Error::InvalidDepositBranch {},
+    );
+
+    state.eth1_deposit_index += 1;
+
+    let DepositData {
+        pubkey,
+        withdrawal_credentials,
+        amount,
+        signature,
+    } = deposit.data;
+
+    if let Some(position) = state
+        .validators
+        .iter()
+        .position(|validator| validator.pubkey == pubkey)
+    {
+        return increase_balance(state, ValidatorIndex::try_from(position)?, amount);
+    }
+
+    let deposit_message = DepositMessage {
+        pubkey,
+        withdrawal_credentials,
+        amount,
+    };
+
+    // Deposits are valid across forks, so the domain does not depend on the state.
+    let domain = compute_domain(C::DOMAIN_DEPOSIT, None, None, chain_spec);
+    let signing_root = compute_signing_root(&deposit_message, domain);
+
+    // The deposit contract does not check signatures, so deposits with invalid ones are skipped
+    // instead of making the block invalid. Malformed public keys count as invalid signatures.
+    if !bls_verify(pubkey, signing_root.as_bytes(), signature).unwrap_or(false) {
+        return Ok(());
+    }
+
+    let validator = get_validator_from_deposit(state, &deposit.data);
+
+    state
+        .validators
+        .make_mut()
+        .push(validator)
+        .map_err(|_| Error::ValidatorRegistryFull {})?;
+
+    state
+        .balances
+        .make_mut()
+        .push(amount)
+        .map_err(|_| Error::ValidatorRegistryFull {})?;
+
+    Ok(())
+}
+
+pub fn process_voluntary_exit<C: Config>(
+    state: &mut BeaconState<C>,
+    signed_voluntary_exit: &SignedVoluntaryExit,
+    verify_signatures: bool,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let voluntary_exit = signed_voluntary_exit.message;
+    let index = voluntary_exit.validator_index;
+    let validator = validator(state, index)?;
+    let current_epoch = get_current_epoch(state);
+
+    ensure!(
+        is_active_validator(validator, current_epoch),
+        Error::ValidatorNotActive { index },
+    );
+
+    ensure!(
+        validator.exit_epoch == FAR_FUTURE_EPOCH,
+        Error::ValidatorAlreadyExiting { index },
+    );
+
+    ensure!(
+        current_epoch >= voluntary_exit.epoch,
+        Error::VoluntaryExitTooEarly {
+            epoch: voluntary_exit.epoch,
+            current_epoch,
+        },
+    );
+
+    ensure!(
+        current_epoch >= validator.activation_epoch + C::SHARD_COMMITTEE_PERIOD,
+        Error::ValidatorTooNewToExit { index },
+    );
+
+    if verify_signatures {
+        let domain = get_domain(state, C::DOMAIN_VOLUNTARY_EXIT, Some(voluntary_exit.epoch));
+        let signing_root = compute_signing_root(&voluntary_exit, domain);
+        ensure!(
+            bls_verify(
+                validator.pubkey,
+                signing_root.as_bytes(),
+                signed_voluntary_exit.signature,
+            )?,
+            Error::InvalidVoluntaryExitSignature {},
+        );
+    }
+
+    initiate_validator_exit(state, index, chain_spec)
+}
+
+pub(crate) fn validator<C: Config>(
+    state: &BeaconState<C>,
+    index: ValidatorIndex,
+) -> Result<Validator> {
+    let validator = usize::try_from(index)
+        .ok()
+        .and_then(|position| state.validators.get(position))
+        .copied()
+        .ok_or(Error::UnknownValidator { index })?;
+    Ok(validator)
+}
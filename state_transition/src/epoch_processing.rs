@@ -0,0 +1,1392 @@
+use core::convert::TryFrom as _;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use anyhow::{ensure, Result};
+use custody_game::epoch_processing::{
+    process_challenge_deadlines, process_custody_final_updates, process_reveal_deadlines,
+};
+use helper_functions::{
+    accessors::{
+        get_attesting_indices, get_base_reward, get_block_root, get_block_root_at_slot,
+        get_current_epoch, get_previous_epoch, get_randao_mix, get_start_shard, get_total_balance,
+        get_validator_churn_limit,
+    },
+    cache::total_active_balance,
+    misc::compute_activation_exit_epoch,
+    mutators::{decrease_balance, increase_balance, initiate_validator_exit},
+    predicates::{
+        is_active_validator, is_eligible_for_activation, is_eligible_for_activation_queue,
+    },
+};
+use light_client::epoch_processing::process_light_client_committee_updates;
+use shard_transition_processing::epoch_processing::process_online_tracking;
+use ssz_new::{types::VariableList, SszHash as _};
+use typenum::Unsigned as _;
+use types::{
+    arc_ext::ArcExt as _,
+    beacon_state::BeaconState,
+    chain_spec::ChainSpec,
+    config::Config,
+    consts::{JustificationBitsLength, BASE_REWARDS_PER_EPOCH, GENESIS_EPOCH},
+    containers::{Checkpoint, HistoricalBatch, PendingAttestation},
+    primitives::{Epoch, Gwei, ValidatorIndex},
+};
+
+use crate::error::Error;
+
+pub fn process_epoch<C: Config>(state: &mut BeaconState<C>, chain_spec: &ChainSpec) -> Result<()> {
+    process_justification_and_finalization(state)?;
+    process_rewards_and_penalties(state)?;
+    process_registry_updates(state, chain_spec)?;
+    process_reveal_deadlines(state, chain_spec)?;
+    process_challenge_deadlines(state, chain_spec)?;
+    process_slashings(state)?;
+    process_final_updates(state)?;
+    process_phase_1_final_updates(state, chain_spec)
+}
+
+pub fn get_matching_source_attestations<C: Config>(
+    state: &BeaconState<C>,
+    epoch: Epoch,
+) -> Result<&[PendingAttestation<C>]> {
+    if epoch == get_current_epoch(state) {
+        return Ok(&state.current_epoch_attestations);
+    }
+
+    ensure!(
+        epoch == get_previous_epoch(state),
+        Error::EpochOutOfRange { epoch },
+    );
+
+    Ok(&state.previous_epoch_attestations)
+}
+
+pub fn get_matching_target_attestations<C: Config>(
+    state: &BeaconState<C>,
+    epoch: Epoch,
+) -> Result<Vec<&PendingAttestation<C>>> {
+    let block_root = get_block_root(state, epoch)?;
+
+    let attestations = get_matching_source_attestations(state, epoch)?
+        .iter()
+        .filter(|attestation| attestation.data.target.root == block_root)
+        .collect();
+
+    Ok(attestations)
+}
+
+pub fn get_matching_head_attestations<C: Config>(
+    state: &BeaconState<C>,
+    epoch: Epoch,
+) -> Result<Vec<&PendingAttestation<C>>> {
+    let mut attestations = vec![];
+
+    for attestation in get_matching_target_attestations(state, epoch)? {
+        if attestation.data.beacon_block_root
+            == get_block_root_at_slot(state, attestation.data.slot)?
+        {
+            attestations.push(attestation);
+        }
+    }
+
+    Ok(attestations)
+}
+
+pub fn get_unslashed_attesting_indices<'attestations, C: Config>(
+    state: &BeaconState<C>,
+    attestations: impl IntoIterator<Item = &'attestations PendingAttestation<C>>,
+) -> Result<BTreeSet<ValidatorIndex>> {
+    let mut indices = BTreeSet::new();
+
+    for attestation in attestations {
+        indices.extend(get_attesting_indices(
+            state,
+            &attestation.data,
+            &attestation.aggregation_bits,
+        )?);
+    }
+
+    indices.retain(|index| {
+        usize::try_from(*index)
+            .ok()
+            .and_then(|index| state.validators.get(index))
+            .map(|validator| !validator.slashed)
+            .unwrap_or_default()
+    });
+
+    Ok(indices)
+}
+
+pub fn get_attesting_balance<'attestations, C: Config>(
+    state: &BeaconState<C>,
+    attestations: impl IntoIterator<Item = &'attestations PendingAttestation<C>>,
+) -> Result<Gwei> {
+    get_total_balance(state, get_unslashed_attesting_indices(state, attestations)?)
+}
+
+pub fn process_justification_and_finalization<C: Config>(state: &mut BeaconState<C>) -> Result<()> {
+    // Initial FFG checkpoint values have a `0x00` stub for `root`.
+    // Skip FFG updates in the first two epochs to avoid corner cases that might result in
+    // modifying this stub.
+    if get_current_epoch(state) <= GENESIS_EPOCH + 1 {
+        return Ok(());
+    }
+
+    let previous_epoch = get_previous_epoch(state);
+    let current_epoch = get_current_epoch(state);
+    let old_previous_justified_checkpoint = state.previous_justified_checkpoint;
+    let old_current_justified_checkpoint = state.current_justified_checkpoint;
+
+    // Process justifications
+    state.previous_justified_checkpoint = state.current_justified_checkpoint;
+
+    for index in (1..JustificationBitsLength::USIZE).rev() {
+        let bit = justification_bit(state, index - 1);
+        set_justification_bit(state, index, bit);
+    }
+    set_justification_bit(state, 0, false);
+
+    let total_active_balance = total_active_balance(state)?;
+
+    for (epoch, bit_index) in [(previous_epoch, 1), (current_epoch, 0)].iter().copied() {
+        let matching_target_attestations = get_matching_target_attestations(state, epoch)?;
+        let attesting_balance = get_attesting_balance(state, matching_target_attestations)?;
+
+        if attesting_balance * 3 >= total_active_balance * 2 {
+            state.current_justified_checkpoint = Checkpoint {
+                epoch,
+                root: get_block_root(state, epoch)?,
+            };
+            set_justification_bit(state, bit_index, true);
+        }
+    }
+
+    // Process finalizations
+    let bits = state.justification_bits.iter().collect::<Vec<_>>();
+    let all_justified = |range: core::ops::Range<usize>| bits[range].iter().all(|bit| *bit);
+
+    // The 2nd/3rd/4th most recent epochs are justified, the 2nd using the 4th as source
+    if all_justified(1..4) && old_previous_justified_checkpoint.epoch + 3 == current_epoch {
+        state.finalized_checkpoint = old_previous_justified_checkpoint;
+    }
+    // The 2nd/3rd most recent epochs are justified, the 2nd using the 3rd as source
+    if all_justified(1..3) && old_previous_justified_checkpoint.epoch + 2 == current_epoch {
+        state.finalized_checkpoint = old_previous_justified_checkpoint;
+    }
+    // The 1st/2nd/3rd most recent epochs are justified, the 1st using the 2nd as source
+    if all_justified(0..3) && old_current_justified_checkpoint.epoch + 2 == current_epoch {
+        state.finalized_checkpoint = old_current_justified_checkpoint;
+    }
+    // The 1st/2nd most recent epochs are justified, the 1st using the 2nd as source
+    if all_justified(0..2) && old_current_justified_checkpoint.epoch + 1 == current_epoch {
+        state.finalized_checkpoint = old_current_justified_checkpoint;
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Deltas {
+    pub rewards: Vec<Gwei>,
+    pub penalties: Vec<Gwei>,
+}
+
+impl Deltas {
+    fn new(validator_count: usize) -> Self {
+        Self {
+            rewards: vec![0; validator_count],
+            penalties: vec![0; validator_count],
+        }
+    }
+
+    fn reward(&mut self, index: ValidatorIndex, amount: Gwei) -> Result<()> {
+        *Self::entry(&mut self.rewards, index)? += amount;
+        Ok(())
+    }
+
+    fn penalize(&mut self, index: ValidatorIndex, amount: Gwei) -> Result<()> {
+        *Self::entry(&mut self.penalties, index)? += amount;
+        Ok(())
+    }
+
+    fn entry(amounts: &mut [Gwei], index: ValidatorIndex) -> Result<&mut Gwei> {
+        let amount = usize::try_from(index)
+            .ok()
+            .and_then(move |position| amounts.get_mut(position))
+            .ok_or(Error::UnknownValidator { index })?;
+        Ok(amount)
+    }
+}
+
+pub fn get_proposer_reward<C: Config>(
+    state: &BeaconState<C>,
+    attesting_index: ValidatorIndex,
+) -> Result<Gwei> {
+    Ok(get_base_reward(state, attesting_index)? / C::PROPOSER_REWARD_QUOTIENT)
+}
+
+#[must_use]
+pub fn get_finality_delay<C: Config>(state: &BeaconState<C>) -> u64 {
+    get_previous_epoch(state) - state.finalized_checkpoint.epoch
+}
+
+#[must_use]
+pub fn is_in_inactivity_leak<C: Config>(state: &BeaconState<C>) -> bool {
+    get_finality_delay(state) > C::MIN_EPOCHS_TO_INACTIVITY_PENALTY
+}
+
+pub fn get_eligible_validator_indices<C: Config>(
+    state: &BeaconState<C>,
+) -> Result<Vec<ValidatorIndex>> {
+    let previous_epoch = get_previous_epoch(state);
+    let mut indices = vec![];
+
+    for (index, validator) in state.validators.iter().enumerate() {
+        if is_active_validator(*validator, previous_epoch)
+            || (validator.slashed && previous_epoch + 1 < validator.withdrawable_epoch)
+        {
+            indices.push(ValidatorIndex::try_from(index)?);
+        }
+    }
+
+    Ok(indices)
+}
+
+pub fn get_attestation_component_deltas<'attestations, C: Config>(
+    state: &BeaconState<C>,
+    attestations: impl IntoIterator<Item = &'attestations PendingAttestation<C>>,
+) -> Result<Deltas> {
+    let mut deltas = Deltas::new(state.validators.len());
+    let total_balance = total_active_balance(state)?;
+    let unslashed_attesting_indices = get_unslashed_attesting_indices(state, attestations)?;
+    let attesting_balance = get_total_balance(state, unslashed_attesting_indices.iter().copied())?;
+    let in_inactivity_leak = is_in_inactivity_leak(state);
+
+    // Factored out from balance totals to avoid overflow
+    let increment = C::EFFECTIVE_BALANCE_INCREMENT;
+
+    for index in get_eligible_validator_indices(state)? {
+        let base_reward = get_base_reward(state, index)?;
+
+        if !unslashed_attesting_indices.contains(&index) {
+            deltas.penalize(index, base_reward)?;
+        } else if in_inactivity_leak {
+            // Since full base reward will be canceled out by inactivity penalty deltas,
+            // optimal participation receives full base reward compensation here.
+            deltas.reward(index, base_reward)?;
+        } else {
+            let reward_numerator = base_reward * (attesting_balance / increment);
+            deltas.reward(index, reward_numerator / (total_balance / increment))?;
+        }
+    }
+
+    Ok(deltas)
+}
+
+pub fn get_source_deltas<C: Config>(state: &BeaconState<C>) -> Result<Deltas> {
+    let attestations = get_matching_source_attestations(state, get_previous_epoch(state))?;
+    get_attestation_component_deltas(state, attestations)
+}
+
+pub fn get_target_deltas<C: Config>(state: &BeaconState<C>) -> Result<Deltas> {
+    let attestations = get_matching_target_attestations(state, get_previous_epoch(state))?;
+    get_attestation_component_deltas(state, attestations)
+}
+
+pub fn get_head_deltas<C: Config>(state: &BeaconState<C>) -> Result<Deltas> {
+    let attestations = get_matching_head_attestations(state, get_previous_epoch(state))?;
+    get_attestation_component_deltas(state, attestations)
+}
+
+pub fn get_inclusion_delay_deltas<C: Config>(state: &BeaconState<C>) -> Result<Deltas> {
+    let mut deltas = Deltas::new(state.validators.len());
+    let matching_source_attestations =
+        get_matching_source_attestations(state, get_previous_epoch(state))?;
+
+    // Find the earliest included attestation of every attester in a single pass.
+    // Ties go to the attestation that comes first, like `min` in the specification.
+    let mut earliest_attestations = BTreeMap::<_, &PendingAttestation<C>>::new();
+
+    for attestation in matching_source_attestations {
+        for index in get_attesting_indices(state, &attestation.data, &attestation.aggregation_bits)?
+        {
+            earliest_attestations
+                .entry(index)
+                .and_modify(|earliest| {
+                    if attestation.inclusion_delay < earliest.inclusion_delay {
+                        *earliest = attestation;
+                    }
+                })
+                .or_insert(attestation);
+        }
+    }
+
+    for index in get_unslashed_attesting_indices(state, matching_source_attestations)? {
+        let attestation = earliest_attestations[&index];
+        let proposer_reward = get_proposer_reward(state, index)?;
+        deltas.reward(attestation.proposer_index, proposer_reward)?;
+
+        let max_attester_reward = get_base_reward(state, index)? - proposer_reward;
+        deltas.reward(index, max_attester_reward / attestation.inclusion_delay)?;
+    }
+
+    // No penalties associated with inclusion delay
+    Ok(deltas)
+}
+
+pub fn get_inactivity_penalty_deltas<C: Config>(state: &BeaconState<C>) -> Result<Deltas> {
+    let mut deltas = Deltas::new(state.validators.len());
+
+    if is_in_inactivity_leak(state) {
+        let matching_target_attestations =
+            get_matching_target_attestations(state, get_previous_epoch(state))?;
+        let matching_target_attesting_indices =
+            get_unslashed_attesting_indices(state, matching_target_attestations)?;
+        let finality_delay = get_finality_delay(state);
+
+        for index in get_eligible_validator_indices(state)? {
+            // If validator is performing optimally this cancels all rewards for a neutral balance
+            let base_reward = get_base_reward(state, index)?;
+            let proposer_reward = get_proposer_reward(state, index)?;
+            deltas.penalize(
+                index,
+                BASE_REWARDS_PER_EPOCH * base_reward - proposer_reward,
+            )?;
+
+            if !matching_target_attesting_indices.contains(&index) {
+                let effective_balance = state.validators[usize::try_from(index)?].effective_balance;
+                deltas.penalize(
+                    index,
+                    effective_balance * finality_delay / C::INACTIVITY_PENALTY_QUOTIENT,
+                )?;
+            }
+        }
+    }
+
+    // No rewards associated with inactivity penalties
+    Ok(deltas)
+}
+
+pub fn get_attestation_deltas<C: Config>(state: &BeaconState<C>) -> Result<Deltas> {
+    let components = [
+        get_source_deltas(state)?,
+        get_target_deltas(state)?,
+        get_head_deltas(state)?,
+        get_inclusion_delay_deltas(state)?,
+        get_inactivity_penalty_deltas(state)?,
+    ];
+
+    let mut deltas = Deltas::new(state.validators.len());
+
+    for component in components.iter() {
+        for (total, reward) in deltas.rewards.iter_mut().zip(&component.rewards) {
+            *total += reward;
+        }
+        for (total, penalty) in deltas.penalties.iter_mut().zip(&component.penalties) {
+            *total += penalty;
+        }
+    }
+
+    Ok(deltas)
+}
+
+pub fn process_rewards_and_penalties<C: Config>(state: &mut BeaconState<C>) -> Result<()> {
+    if get_current_epoch(state) == GENESIS_EPOCH {
+        return Ok(());
+    }
+
+    let Deltas { rewards, penalties } = get_attestation_deltas(state)?;
+
+    for (index, (reward, penalty)) in rewards.into_iter().zip(penalties).enumerate() {
+        let index = ValidatorIndex::try_from(index)?;
+        increase_balance(state, index, reward)?;
+        decrease_balance(state, index, penalty)?;
+    }
+
+    Ok(())
+}
+
+pub fn process_registry_updates<C: Config>(
+    state: &mut BeaconState<C>,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let current_epoch = get_current_epoch(state);
+
+    // Process activation eligibility and ejections
+    for index in 0..state.validators.len() {
+        let validator = state.validators[index];
+
+        if is_eligible_for_activation_queue::<C>(validator) {
+            state.validators.make_mut()[index].activation_eligibility_epoch = current_epoch + 1;
+        }
+
+        if is_active_validator(validator, current_epoch)
+            && validator.effective_balance <= chain_spec.ejection_balance
+        {
+            initiate_validator_exit(state, ValidatorIndex::try_from(index)?, chain_spec)?;
+        }
+    }
+
+    // Queue validators eligible for activation and not yet dequeued for activation.
+    // Order by the sequence of activation_eligibility_epoch setting and then index.
+    let mut activation_queue = state
+        .validators
+        .iter()
+        .enumerate()
+        .filter(|(_, validator)| is_eligible_for_activation(state, **validator))
+        .map(|(index, validator)| (validator.activation_eligibility_epoch, index))
+        .collect::<Vec<_>>();
+
+    activation_queue.sort_unstable();
+
+    // Dequeue validators for activation up to churn limit
+    let churn_limit = usize::try_from(get_validator_churn_limit(state, chain_spec)?)?;
+    let activation_epoch = compute_activation_exit_epoch::<C>(current_epoch);
+
+    for (_, index) in activation_queue.into_iter().take(churn_limit) {
+        state.validators.make_mut()[index].activation_epoch = activation_epoch;
+    }
+
+    Ok(())
+}
+
+pub fn process_slashings<C: Config>(state: &mut BeaconState<C>) -> Result<()> {
+    let epoch = get_current_epoch(state);
+    let total_balance = total_active_balance(state)?;
+    let total_slashing_balance = state.slashings.iter().sum::<Gwei>();
+    let adjusted_total_slashing_balance = (total_slashing_balance * 3).min(total_balance);
+    let withdrawable_epoch = epoch + C::EpochsPerSlashingsVector::U64 / 2;
+
+    // Factored out from penalty numerator to avoid overflow
+    let increment = C::EFFECTIVE_BALANCE_INCREMENT;
+
+    for index in 0..state.validators.len() {
+        let validator = state.validators[index];
+
+        if validator.slashed && validator.withdrawable_epoch == withdrawable_epoch {
+            let penalty_numerator =
+                validator.effective_balance / increment * adjusted_total_slashing_balance;
+            let penalty = penalty_numerator / total_balance * increment;
+            decrease_balance(state, ValidatorIndex::try_from(index)?, penalty)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn process_effective_balance_updates<C: Config>(state: &mut BeaconState<C>) -> Result<()> {
+    let hysteresis_increment = C::EFFECTIVE_BALANCE_INCREMENT / C::HYSTERESIS_QUOTIENT;
+    let downward_threshold = hysteresis_increment * C::HYSTERESIS_DOWNWARD_MULTIPLIER;
+    let upward_threshold = hysteresis_increment * C::HYSTERESIS_UPWARD_MULTIPLIER;
+
+    for index in 0..state.validators.len() {
+        let balance = *state.balances.get(index).ok_or(Error::UnknownValidator {
+            index: ValidatorIndex::try_from(index)?,
+        })?;
+        let effective_balance = state.validators[index].effective_balance;
+
+        if balance + downward_threshold < effective_balance
+            || effective_balance + upward_threshold < balance
+        {
+            state.validators.make_mut()[index].effective_balance =
+                (balance - balance % C::EFFECTIVE_BALANCE_INCREMENT).min(C::MAX_EFFECTIVE_BALANCE);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn process_final_updates<C: Config>(state: &mut BeaconState<C>) -> Result<()> {
+    let current_epoch = get_current_epoch(state);
+    let next_epoch = current_epoch + 1;
+
+    // Reset eth1 data votes
+    let epochs_into_voting_period = next_epoch % C::EpochsPerEth1VotingPeriod::U64;
+    if epochs_into_voting_period == 0 {
+        state.eth1_data_votes = VariableList::default();
+    }
+
+    // Update effective balances with hysteresis
+    process_effective_balance_updates(state)?;
+
+    // Reset slashings
+    let slashings_index = usize::try_from(next_epoch % C::EpochsPerSlashingsVector::U64)?;
+    state.slashings.make_mut()[slashings_index] = 0;
+
+    // Set randao mix
+    let randao_index = usize::try_from(next_epoch % C::EpochsPerHistoricalVector::U64)?;
+    state.randao_mixes.make_mut()[randao_index] = get_randao_mix(state, current_epoch)?;
+
+    // Set historical root accumulator
+    let epochs_per_historical_root = C::SlotsPerHistoricalRoot::U64 / C::SlotsPerEpoch::U64;
+    let epochs_into_historical_root = next_epoch % epochs_per_historical_root;
+    if epochs_into_historical_root == 0 {
+        let historical_batch = HistoricalBatch::<C> {
+            block_roots: state.block_roots.as_ref().clone(),
+            state_roots: state.state_roots.as_ref().clone(),
+        };
+
+        state
+            .historical_roots
+            .push(historical_batch.tree_hash_root())
+            .map_err(|_| Error::HistoricalRootsFull {})?;
+    }
+
+    // Rotate current/previous epoch attestations
+    state.previous_epoch_attestations = core::mem::replace(
+        &mut state.current_epoch_attestations,
+        Arc::new(VariableList::default()),
+    );
+
+    Ok(())
+}
+
+pub fn process_phase_1_final_updates<C: Config>(
+    state: &mut BeaconState<C>,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    process_custody_final_updates(state, chain_spec)?;
+    process_online_tracking(state)?;
+    process_light_client_committee_updates(state)?;
+
+    // Update current_epoch_start_shard
+    state.current_epoch_start_shard = get_start_shard(state, state.slot + 1)?;
+
+    Ok(())
+}
+
+fn justification_bit<C: Config>(state: &BeaconState<C>, index: usize) -> bool {
+    state
+        .justification_bits
+        .get(index)
+        .expect("justification bit index should be in bounds")
+}
+
+fn set_justification_bit<C: Config>(state: &mut BeaconState<C>, index: usize, value: bool) {
+    state
+        .justification_bits
+        .set(index, value)
+        .expect("justification bit index should be in bounds")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use helper_functions::{
+        accessors::{get_beacon_committee, get_committee_count_per_slot},
+        misc::compute_start_slot_at_epoch,
+    };
+    use spec_test_utils::testing;
+    use ssz_new::types::{BitList, FixedVector, VariableList};
+    use types::{
+        config::MinimalConfig,
+        consts::FAR_FUTURE_EPOCH,
+        containers::{AttestationData, Eth1Data, Validator},
+        primitives::{Slot, H256},
+    };
+
+    use super::*;
+
+    const VALIDATOR_COUNT: usize = 64;
+    const EPOCH: Epoch = 3;
+
+    fn state() -> BeaconState<MinimalConfig> {
+        let block_roots = (0..)
+            .map(H256::repeat_byte)
+            .take(<MinimalConfig as Config>::SlotsPerHistoricalRoot::USIZE)
+            .collect::<Vec<_>>();
+
+        // The last slot of the epoch, where epoch processing happens.
+        let slot = compute_start_slot_at_epoch::<MinimalConfig>(EPOCH + 1) - 1;
+
+        BeaconState {
+            slot,
+            block_roots: Arc::new(FixedVector::from(block_roots)),
+            ..testing::state(VALIDATOR_COUNT)
+        }
+    }
+
+    // Every committee in the first `slot_count` slots of `epoch` attests to the right target.
+    fn attest(state: &mut BeaconState<MinimalConfig>, epoch: Epoch, slot_count: u64) {
+        let start_slot = compute_start_slot_at_epoch::<MinimalConfig>(epoch);
+        let committee_count = get_committee_count_per_slot(state, epoch)
+            .expect("the test state has active validators");
+        let target = Checkpoint {
+            epoch,
+            root: get_block_root(state, epoch).expect("the epoch is in range"),
+        };
+
+        let attestations = (start_slot..start_slot + slot_count)
+            .flat_map(|slot| (0..committee_count).map(move |index| (slot, index)))
+            .map(|(slot, index): (Slot, _)| {
+                let committee = get_beacon_committee(state, slot, index)
+                    .expect("the test state has active validators");
+                let mut aggregation_bits =
+                    BitList::with_capacity(committee.len()).expect("committee fits in a bitlist");
+                for position in 0..committee.len() {
+                    aggregation_bits
+                        .set(position, true)
+                        .expect("position is within the committee");
+                }
+
+                PendingAttestation {
+                    aggregation_bits,
+                    data: AttestationData {
+                        slot,
+                        index,
+                        beacon_block_root: state.block_roots[slot as usize],
+                        target,
+                        ..AttestationData::default()
+                    },
+                    inclusion_delay: 1,
+                    proposer_index: 0,
+                    crosslink_success: false,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let attestations = Arc::new(VariableList::from(attestations));
+
+        if epoch == get_current_epoch(state) {
+            state.current_epoch_attestations = attestations;
+        } else {
+            state.previous_epoch_attestations = attestations;
+        }
+    }
+
+    fn bits(state: &BeaconState<MinimalConfig>) -> Vec<bool> {
+        state.justification_bits.iter().collect()
+    }
+
+    #[test]
+    fn test_attesting_balance_excludes_slashed_validators() {
+        let mut state = state();
+        attest(&mut state, EPOCH - 1, 8);
+
+        let attestations = get_matching_target_attestations(&state, EPOCH - 1)
+            .expect("the previous epoch is in range")
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let total_balance = VALIDATOR_COUNT as Gwei * MinimalConfig::MAX_EFFECTIVE_BALANCE;
+        assert_eq!(
+            get_attesting_balance(&state, &attestations).expect("attesters are known validators"),
+            total_balance,
+        );
+
+        let mut validators = state.validators.as_ref().clone();
+        validators[0].slashed = true;
+        state.validators = Arc::new(validators);
+
+        assert_eq!(
+            get_attesting_balance(&state, &attestations).expect("attesters are known validators"),
+            total_balance - MinimalConfig::MAX_EFFECTIVE_BALANCE,
+        );
+    }
+
+    #[test]
+    fn test_matching_attestations_reject_old_epoch() {
+        let state = state();
+
+        let error = get_matching_source_attestations(&state, EPOCH - 2)
+            .expect_err("epoch before the previous one should be rejected");
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::EpochOutOfRange { epoch }) if *epoch == EPOCH - 2
+        ));
+    }
+
+    #[test]
+    fn test_process_justification_and_finalization_skips_first_epochs() {
+        let mut state = state();
+        state.slot = compute_start_slot_at_epoch::<MinimalConfig>(GENESIS_EPOCH + 2) - 1;
+        attest(&mut state, GENESIS_EPOCH, 8);
+        let expected_state = state.clone();
+
+        process_justification_and_finalization(&mut state)
+            .expect("processing the first epochs should succeed");
+
+        assert_eq!(state, expected_state);
+    }
+
+    #[test]
+    fn test_process_justification_and_finalization_justifies_previous_epoch() {
+        let mut state = state();
+        attest(&mut state, EPOCH - 1, 8);
+
+        process_justification_and_finalization(&mut state).expect("justification should succeed");
+
+        assert_eq!(
+            state.current_justified_checkpoint,
+            Checkpoint {
+                epoch: EPOCH - 1,
+                root: get_block_root(&state, EPOCH - 1).expect("the epoch is in range"),
+            },
+        );
+        assert_eq!(bits(&state), [false, true, false, false]);
+        assert_eq!(state.finalized_checkpoint, Checkpoint::default());
+    }
+
+    #[test]
+    fn test_process_justification_and_finalization_without_supermajority() {
+        let mut state = state();
+        // 5 of 8 slots fall short of two thirds of the total balance.
+        attest(&mut state, EPOCH - 1, 5);
+        state.justification_bits.set(0, true).expect("bit 0 exists");
+
+        process_justification_and_finalization(&mut state).expect("justification should succeed");
+
+        assert_eq!(state.current_justified_checkpoint, Checkpoint::default());
+        assert_eq!(bits(&state), [false, true, false, false]);
+    }
+
+    #[test]
+    fn test_process_justification_and_finalization_finalizes_previous_justified_checkpoint() {
+        let mut state = state();
+        attest(&mut state, EPOCH - 1, 8);
+
+        let previous_justified_checkpoint = Checkpoint {
+            epoch: EPOCH - 2,
+            root: H256::repeat_byte(1),
+        };
+        state.previous_justified_checkpoint = previous_justified_checkpoint;
+        state.current_justified_checkpoint = Checkpoint {
+            epoch: EPOCH - 1,
+            root: H256::repeat_byte(2),
+        };
+        state.justification_bits.set(0, true).expect("bit 0 exists");
+        state.justification_bits.set(1, true).expect("bit 1 exists");
+
+        process_justification_and_finalization(&mut state).expect("justification should succeed");
+
+        assert_eq!(bits(&state), [false, true, true, false]);
+        assert_eq!(state.finalized_checkpoint, previous_justified_checkpoint);
+    }
+
+    #[test]
+    fn test_process_justification_and_finalization_finalizes_current_justified_checkpoint() {
+        let mut state = state();
+        attest(&mut state, EPOCH, 8);
+
+        let current_justified_checkpoint = Checkpoint {
+            epoch: EPOCH - 1,
+            root: H256::repeat_byte(2),
+        };
+        state.current_justified_checkpoint = current_justified_checkpoint;
+        state.justification_bits.set(0, true).expect("bit 0 exists");
+
+        process_justification_and_finalization(&mut state).expect("justification should succeed");
+
+        assert_eq!(bits(&state), [true, true, false, false]);
+        assert_eq!(state.finalized_checkpoint, current_justified_checkpoint);
+    }
+
+    fn base_reward(state: &BeaconState<MinimalConfig>, index: ValidatorIndex) -> Gwei {
+        get_base_reward(state, index).expect("validator exists")
+    }
+
+    fn attesters(state: &BeaconState<MinimalConfig>) -> BTreeSet<ValidatorIndex> {
+        get_unslashed_attesting_indices(state, state.previous_epoch_attestations.iter())
+            .expect("attesters are known validators")
+    }
+
+    #[test]
+    fn test_component_deltas_with_full_participation() {
+        let mut state = state();
+        attest(&mut state, EPOCH - 1, 8);
+
+        let expected_rewards = (0..VALIDATOR_COUNT as ValidatorIndex)
+            .map(|index| base_reward(&state, index))
+            .collect::<Vec<_>>();
+
+        for get_deltas in &[get_source_deltas, get_target_deltas, get_head_deltas] {
+            let deltas = get_deltas(&state).expect("deltas should be computed");
+            assert_eq!(deltas.rewards, expected_rewards);
+            assert_eq!(deltas.penalties, [0; VALIDATOR_COUNT]);
+        }
+    }
+
+    #[test]
+    fn test_component_deltas_penalize_missing_attesters() {
+        let mut state = state();
+        attest(&mut state, EPOCH - 1, 5);
+        let attesters = attesters(&state);
+
+        let deltas = get_source_deltas(&state).expect("deltas should be computed");
+
+        for index in 0..VALIDATOR_COUNT as ValidatorIndex {
+            let position = index as usize;
+            if attesters.contains(&index) {
+                // 5 of 8 slots attested, so attesters get 5/8 of their base reward.
+                assert_eq!(deltas.rewards[position], base_reward(&state, index) * 5 / 8);
+                assert_eq!(deltas.penalties[position], 0);
+            } else {
+                assert_eq!(deltas.rewards[position], 0);
+                assert_eq!(deltas.penalties[position], base_reward(&state, index));
+            }
+        }
+    }
+
+    #[test]
+    fn test_head_deltas_ignore_wrong_head() {
+        let mut state = state();
+        attest(&mut state, EPOCH - 1, 8);
+        let mut attestations = state.previous_epoch_attestations.as_ref().clone();
+        for attestation in attestations.iter_mut() {
+            attestation.data.beacon_block_root = H256::zero();
+        }
+        state.previous_epoch_attestations = Arc::new(attestations);
+
+        let deltas = get_head_deltas(&state).expect("deltas should be computed");
+
+        assert_eq!(deltas.rewards, [0; VALIDATOR_COUNT]);
+        assert_eq!(deltas.penalties[0], base_reward(&state, 0));
+    }
+
+    #[test]
+    fn test_inclusion_delay_deltas_use_earliest_attestation() {
+        let mut state = state();
+        attest(&mut state, EPOCH - 1, 8);
+
+        // Include every attestation a second time, earlier and by a different proposer.
+        let mut attestations = state.previous_epoch_attestations.as_ref().clone();
+        for attestation in state.previous_epoch_attestations.iter() {
+            attestations
+                .push(PendingAttestation {
+                    inclusion_delay: 2,
+                    proposer_index: 1,
+                    ..attestation.clone()
+                })
+                .expect("the list has room for the copies");
+        }
+        for attestation in attestations
+            .iter_mut()
+            .take(state.previous_epoch_attestations.len())
+        {
+            attestation.inclusion_delay = 3;
+        }
+        state.previous_epoch_attestations = Arc::new(attestations);
+
+        let deltas = get_inclusion_delay_deltas(&state).expect("deltas should be computed");
+        let proposer_reward =
+            |index| base_reward(&state, index) / MinimalConfig::PROPOSER_REWARD_QUOTIENT;
+        let total_proposer_reward = (0..VALIDATOR_COUNT as ValidatorIndex)
+            .map(proposer_reward)
+            .sum::<Gwei>();
+
+        assert_eq!(
+            deltas.rewards[2],
+            (base_reward(&state, 2) - proposer_reward(2)) / 2,
+        );
+        assert_eq!(
+            deltas.rewards[1],
+            (base_reward(&state, 1) - proposer_reward(1)) / 2 + total_proposer_reward,
+        );
+        assert_eq!(
+            deltas.rewards[0],
+            (base_reward(&state, 0) - proposer_reward(0)) / 2,
+        );
+        assert_eq!(deltas.penalties, [0; VALIDATOR_COUNT]);
+    }
+
+    #[test]
+    fn test_inactivity_penalty_deltas() {
+        let mut state = state();
+        let epoch = 7;
+        state.slot = compute_start_slot_at_epoch::<MinimalConfig>(epoch + 1) - 1;
+        attest(&mut state, epoch - 1, 5);
+        let attesters = attesters(&state);
+
+        assert_eq!(get_finality_delay(&state), 6);
+        assert!(is_in_inactivity_leak(&state));
+
+        let deltas = get_inactivity_penalty_deltas(&state).expect("deltas should be computed");
+
+        for index in 0..VALIDATOR_COUNT as ValidatorIndex {
+            let base_reward = base_reward(&state, index);
+            let proposer_reward = base_reward / MinimalConfig::PROPOSER_REWARD_QUOTIENT;
+            let mut expected_penalty = BASE_REWARDS_PER_EPOCH * base_reward - proposer_reward;
+            if !attesters.contains(&index) {
+                expected_penalty += MinimalConfig::MAX_EFFECTIVE_BALANCE * 6
+                    / MinimalConfig::INACTIVITY_PENALTY_QUOTIENT;
+            }
+            assert_eq!(deltas.penalties[index as usize], expected_penalty);
+        }
+        assert_eq!(deltas.rewards, [0; VALIDATOR_COUNT]);
+
+        // Attesters get their full base reward during a leak.
+        let source_deltas = get_source_deltas(&state).expect("deltas should be computed");
+        let attester = *attesters.iter().next().expect("some validators attested");
+        assert_eq!(
+            source_deltas.rewards[attester as usize],
+            base_reward(&state, attester),
+        );
+    }
+
+    #[test]
+    fn test_no_inactivity_penalties_outside_leak() {
+        let mut state = state();
+        attest(&mut state, EPOCH - 1, 5);
+
+        assert!(!is_in_inactivity_leak(&state));
+        let deltas = get_inactivity_penalty_deltas(&state).expect("deltas should be computed");
+        assert_eq!(deltas, Deltas::new(VALIDATOR_COUNT));
+    }
+
+    #[test]
+    fn test_process_rewards_and_penalties() {
+        let mut state = state();
+        attest(&mut state, EPOCH - 1, 5);
+        let attesters = attesters(&state);
+        let deltas = get_attestation_deltas(&state).expect("deltas should be computed");
+
+        process_rewards_and_penalties(&mut state).expect("rewards should be applied");
+
+        for (index, balance) in state.balances.iter().copied().enumerate() {
+            let expected_balance = MinimalConfig::MAX_EFFECTIVE_BALANCE + deltas.rewards[index]
+                - deltas.penalties[index];
+            assert_eq!(balance, expected_balance);
+
+            let attested = attesters.contains(&(index as ValidatorIndex));
+            assert_eq!(balance > MinimalConfig::MAX_EFFECTIVE_BALANCE, attested);
+        }
+    }
+
+    #[test]
+    fn test_process_rewards_and_penalties_skips_genesis_epoch() {
+        let mut state = state();
+        state.slot = compute_start_slot_at_epoch::<MinimalConfig>(GENESIS_EPOCH + 1) - 1;
+        let expected_state = state.clone();
+
+        process_rewards_and_penalties(&mut state).expect("the genesis epoch is skipped");
+
+        assert_eq!(state, expected_state);
+    }
+
+    fn chain_spec() -> ChainSpec {
+        ChainSpec::from_config::<MinimalConfig>()
+    }
+
+    fn update_validators(
+        state: &mut BeaconState<MinimalConfig>,
+        update: impl FnOnce(&mut [Validator]),
+    ) {
+        update(state.validators.make_mut());
+    }
+
+    #[test]
+    fn test_process_registry_updates_queues_and_ejects() {
+        let mut state = state();
+        update_validators(&mut state, |validators| {
+            validators[0].activation_eligibility_epoch = FAR_FUTURE_EPOCH;
+            validators[0].activation_epoch = FAR_FUTURE_EPOCH;
+            validators[1].effective_balance = MinimalConfig::EJECTION_BALANCE;
+            validators[2].effective_balance = MinimalConfig::EJECTION_BALANCE + 1;
+        });
+
+        process_registry_updates(&mut state, &chain_spec())
+            .expect("registry updates should succeed");
+
+        assert_eq!(state.validators[0].activation_eligibility_epoch, EPOCH + 1);
+        // Eligibility is not finalized yet.
+        assert_eq!(state.validators[0].activation_epoch, FAR_FUTURE_EPOCH);
+        assert_eq!(
+            state.validators[1].exit_epoch,
+            compute_activation_exit_epoch::<MinimalConfig>(EPOCH),
+        );
+        assert_eq!(state.validators[2].exit_epoch, FAR_FUTURE_EPOCH);
+    }
+
+    #[test]
+    fn test_process_registry_updates_respects_queue_order_and_churn_limit() {
+        let mut state = state();
+        state.finalized_checkpoint.epoch = EPOCH - 1;
+
+        // Validators 10 to 15 wait in the queue. Later indices became eligible earlier.
+        update_validators(&mut state, |validators| {
+            for (index, validator) in validators.iter_mut().enumerate().skip(10).take(6) {
+                validator.activation_eligibility_epoch = if index < 12 { 2 } else { 1 };
+                validator.activation_epoch = FAR_FUTURE_EPOCH;
+            }
+        });
+
+        let churn_limit = get_validator_churn_limit(&state, &chain_spec())
+            .expect("the test state has active validators");
+        assert_eq!(churn_limit, 4);
+
+        process_registry_updates(&mut state, &chain_spec())
+            .expect("registry updates should succeed");
+
+        let activated = (10..16)
+            .filter(|index| state.validators[*index].activation_epoch != FAR_FUTURE_EPOCH)
+            .collect::<Vec<_>>();
+        assert_eq!(activated, [12, 13, 14, 15]);
+        assert_eq!(
+            state.validators[12].activation_epoch,
+            compute_activation_exit_epoch::<MinimalConfig>(EPOCH),
+        );
+    }
+
+    #[test]
+    fn test_process_slashings() {
+        let mut state = state();
+        let withdrawable_epoch =
+            EPOCH + <MinimalConfig as Config>::EpochsPerSlashingsVector::U64 / 2;
+        update_validators(&mut state, |validators| {
+            validators[0].slashed = true;
+            validators[0].withdrawable_epoch = withdrawable_epoch;
+            // Slashed validators are only penalized halfway to being withdrawable.
+            validators[1].slashed = true;
+            validators[1].withdrawable_epoch = withdrawable_epoch + 1;
+        });
+        state.slashings.make_mut()[0] = 2 * MinimalConfig::MAX_EFFECTIVE_BALANCE;
+
+        process_slashings(&mut state).expect("slashings should be processed");
+
+        // 3 times the slashed balance is 6 of 64 validators' balance.
+        let increment = MinimalConfig::EFFECTIVE_BALANCE_INCREMENT;
+        let total_balance = VALIDATOR_COUNT as Gwei * MinimalConfig::MAX_EFFECTIVE_BALANCE;
+        let penalty = MinimalConfig::MAX_EFFECTIVE_BALANCE / increment
+            * (6 * MinimalConfig::MAX_EFFECTIVE_BALANCE)
+            / total_balance
+            * increment;
+        assert_eq!(penalty, 3 * increment);
+        assert_eq!(
+            state.balances[0],
+            MinimalConfig::MAX_EFFECTIVE_BALANCE - penalty,
+        );
+        assert_eq!(state.balances[1], MinimalConfig::MAX_EFFECTIVE_BALANCE);
+    }
+
+    #[test]
+    fn test_process_slashings_caps_penalty_at_balance() {
+        let mut state = state();
+        let withdrawable_epoch =
+            EPOCH + <MinimalConfig as Config>::EpochsPerSlashingsVector::U64 / 2;
+        update_validators(&mut state, |validators| {
+            validators[0].slashed = true;
+            validators[0].withdrawable_epoch = withdrawable_epoch;
+        });
+        state.slashings.make_mut()[0] =
+            VALIDATOR_COUNT as Gwei * MinimalConfig::MAX_EFFECTIVE_BALANCE;
+
+        process_slashings(&mut state).expect("slashings should be processed");
+
+        assert_eq!(state.balances[0], 0);
+    }
+
+    #[test]
+    fn test_process_effective_balance_updates_with_hysteresis() {
+        let max = MinimalConfig::MAX_EFFECTIVE_BALANCE;
+        let increment = MinimalConfig::EFFECTIVE_BALANCE_INCREMENT;
+        let quarter = increment / 4;
+
+        let mut state = state();
+        update_validators(&mut state, |validators| {
+            validators[4].effective_balance = max - 2 * increment;
+            validators[5].effective_balance = max - 2 * increment;
+        });
+        let mut balances = state.balances.as_ref().clone();
+        // Within the downward threshold
+        balances[0] = max - quarter;
+        // Just past the downward threshold
+        balances[1] = max - quarter - 1;
+        // Above the maximum
+        balances[2] = max + 3 * increment;
+        // Within the upward threshold
+        balances[4] = max - 2 * increment + 5 * quarter;
+        // Just past the upward threshold
+        balances[5] = max - 2 * increment + 5 * quarter + 1;
+        state.balances = Arc::new(balances);
+
+        process_effective_balance_updates(&mut state)
+            .expect("effective balances should be updated");
+
+        let effective_balances = state
+            .validators
+            .iter()
+            .take(6)
+            .map(|validator| validator.effective_balance)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            effective_balances,
+            [
+                max,
+                max - increment,
+                max,
+                max,
+                max - 2 * increment,
+                max - increment,
+            ],
+        );
+    }
+
+    fn at_last_slot_of_epoch(state: &mut BeaconState<MinimalConfig>, epoch: Epoch) {
+        state.slot = compute_start_slot_at_epoch::<MinimalConfig>(epoch + 1) - 1;
+    }
+
+    #[test]
+    fn test_process_final_updates_wraps_randao_mixes_and_slashings() {
+        let mut state = state();
+        let last_epoch = <MinimalConfig as Config>::EpochsPerHistoricalVector::U64 - 1;
+        at_last_slot_of_epoch(&mut state, last_epoch);
+
+        let randao_mixes = (0..)
+            .map(H256::repeat_byte)
+            .take(<MinimalConfig as Config>::EpochsPerHistoricalVector::USIZE)
+            .collect::<Vec<_>>();
+        state.randao_mixes = Arc::new(FixedVector::from(randao_mixes));
+        state.slashings = Arc::new(FixedVector::from(vec![
+            1;
+            <MinimalConfig as Config>::EpochsPerSlashingsVector::USIZE
+        ]));
+
+        process_final_updates(&mut state).expect("final updates should succeed");
+
+        // The next epoch wraps around to the start of both vectors.
+        assert_eq!(state.randao_mixes[0], H256::repeat_byte(63));
+        assert_eq!(state.randao_mixes[1], H256::repeat_byte(1));
+        assert_eq!(state.randao_mixes[63], H256::repeat_byte(63));
+        assert_eq!(state.slashings[0], 0);
+        assert_eq!(state.slashings[1..], [1; 63][..]);
+    }
+
+    #[test]
+    fn test_process_final_updates_in_middle_of_vectors() {
+        let mut state = state();
+        state.randao_mixes.make_mut()[EPOCH as usize] = H256::repeat_byte(1);
+        state.slashings.make_mut()[EPOCH as usize + 1] = 1;
+
+        process_final_updates(&mut state).expect("final updates should succeed");
+
+        assert_eq!(state.randao_mixes[EPOCH as usize + 1], H256::repeat_byte(1));
+        assert_eq!(state.slashings[EPOCH as usize + 1], 0);
+    }
+
+    #[test]
+    fn test_process_final_updates_appends_historical_roots() {
+        let mut state = state();
+        let epochs_per_historical_root = <MinimalConfig as Config>::SlotsPerHistoricalRoot::U64
+            / <MinimalConfig as Config>::SlotsPerEpoch::U64;
+
+        // The epoch before the last one in a historical batch
+        at_last_slot_of_epoch(&mut state, epochs_per_historical_root - 2);
+        process_final_updates(&mut state).expect("final updates should succeed");
+        assert!(state.historical_roots.is_empty());
+
+        at_last_slot_of_epoch(&mut state, epochs_per_historical_root - 1);
+        process_final_updates(&mut state).expect("final updates should succeed");
+
+        let historical_batch = HistoricalBatch::<MinimalConfig> {
+            block_roots: state.block_roots.as_ref().clone(),
+            state_roots: state.state_roots.as_ref().clone(),
+        };
+        assert_eq!(
+            state.historical_roots[..],
+            [historical_batch.tree_hash_root()],
+        );
+    }
+
+    #[test]
+    fn test_process_final_updates_resets_eth1_data_votes_at_period_boundary() {
+        let epochs_per_period = <MinimalConfig as Config>::EpochsPerEth1VotingPeriod::U64;
+        let mut state = state();
+        state.eth1_data_votes = VariableList::from(vec![Eth1Data::default(); 3]);
+
+        at_last_slot_of_epoch(&mut state, epochs_per_period - 2);
+        process_final_updates(&mut state).expect("final updates should succeed");
+        assert_eq!(state.eth1_data_votes.len(), 3);
+
+        at_last_slot_of_epoch(&mut state, epochs_per_period - 1);
+        process_final_updates(&mut state).expect("final updates should succeed");
+        assert!(state.eth1_data_votes.is_empty());
+    }
+
+    #[test]
+    fn test_process_final_updates_rotates_attestations() {
+        let mut state = state();
+        attest(&mut state, EPOCH, 2);
+        let current_epoch_attestations = state.current_epoch_attestations.clone();
+
+        process_final_updates(&mut state).expect("final updates should succeed");
+
+        assert_eq!(
+            state.previous_epoch_attestations,
+            current_epoch_attestations
+        );
+        assert!(state.current_epoch_attestations.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod spec_tests {
+    use spec_test_utils::{testing, Case};
+    use ssz_new_derive::SszDecode;
+    use test_generator::test_resources;
+    use types::config::{MainnetConfig, MinimalConfig};
+
+    use super::*;
+
+    #[derive(SszDecode)]
+    struct SszDeltas<C: Config> {
+        rewards: VariableList<Gwei, C::ValidatorRegistryLimit>,
+        penalties: VariableList<Gwei, C::ValidatorRegistryLimit>,
+    }
+
+    impl<C: Config> From<SszDeltas<C>> for Deltas {
+        fn from(deltas: SszDeltas<C>) -> Self {
+            Self {
+                rewards: deltas.rewards.into(),
+                penalties: deltas.penalties.into(),
+            }
+        }
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/justification_and_finalization/*/*"
+    )]
+    fn mainnet_justification_and_finalization(case: Case) {
+        run_case::<MainnetConfig>(case, process_justification_and_finalization);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/justification_and_finalization/*/*"
+    )]
+    fn minimal_justification_and_finalization(case: Case) {
+        run_case::<MinimalConfig>(case, process_justification_and_finalization);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/rewards_and_penalties/*/*"
+    )]
+    fn mainnet_rewards_and_penalties(case: Case) {
+        run_case::<MainnetConfig>(case, process_rewards_and_penalties);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/rewards_and_penalties/*/*"
+    )]
+    fn minimal_rewards_and_penalties(case: Case) {
+        run_case::<MinimalConfig>(case, process_rewards_and_penalties);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/registry_updates/*/*"
+    )]
+    fn mainnet_registry_updates(case: Case) {
+        run_case::<MainnetConfig>(case, |state| {
+            process_registry_updates(state, &ChainSpec::from_config::<MainnetConfig>())
+        });
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/registry_updates/*/*"
+    )]
+    fn minimal_registry_updates(case: Case) {
+        run_case::<MinimalConfig>(case, |state| {
+            process_registry_updates(state, &ChainSpec::from_config::<MinimalConfig>())
+        });
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/slashings/*/*")]
+    fn mainnet_slashings(case: Case) {
+        run_case::<MainnetConfig>(case, process_slashings);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/slashings/*/*")]
+    fn minimal_slashings(case: Case) {
+        run_case::<MinimalConfig>(case, process_slashings);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/effective_balance_updates/*/*"
+    )]
+    fn mainnet_effective_balance_updates(case: Case) {
+        run_case::<MainnetConfig>(case, process_effective_balance_updates);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/effective_balance_updates/*/*"
+    )]
+    fn minimal_effective_balance_updates(case: Case) {
+        run_case::<MinimalConfig>(case, process_effective_balance_updates);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/final_updates/*/*")]
+    fn mainnet_final_updates(case: Case) {
+        run_case::<MainnetConfig>(case, process_final_updates);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/final_updates/*/*")]
+    fn minimal_final_updates(case: Case) {
+        run_case::<MinimalConfig>(case, process_final_updates);
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/mainnet/phase1/epoch_processing/phase_1_final_updates/*/*"
+    )]
+    fn mainnet_phase_1_final_updates(case: Case) {
+        run_case::<MainnetConfig>(case, |state| {
+            process_phase_1_final_updates(state, &ChainSpec::from_config::<MainnetConfig>())
+        });
+    }
+
+    #[test_resources(
+        "eth2.0-spec-tests/tests/minimal/phase1/epoch_processing/phase_1_final_updates/*/*"
+    )]
+    fn minimal_phase_1_final_updates(case: Case) {
+        run_case::<MinimalConfig>(case, |state| {
+            process_phase_1_final_updates(state, &ChainSpec::from_config::<MinimalConfig>())
+        });
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/rewards/*/*/*")]
+    fn mainnet_rewards(case: Case) {
+        run_rewards_case::<MainnetConfig>(case);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/rewards/*/*/*")]
+    fn minimal_rewards(case: Case) {
+        run_rewards_case::<MinimalConfig>(case);
+    }
+
+    fn run_rewards_case<C: Config>(case: Case) {
+        let state: BeaconState<C> = case.ssz("pre");
+
+        assert_deltas(case, &state, "source_deltas", get_source_deltas);
+        assert_deltas(case, &state, "target_deltas", get_target_deltas);
+        assert_deltas(case, &state, "head_deltas", get_head_deltas);
+        assert_deltas(
+            case,
+            &state,
+            "inclusion_delay_deltas",
+            get_inclusion_delay_deltas,
+        );
+        assert_deltas(
+            case,
+            &state,
+            "inactivity_penalty_deltas",
+            get_inactivity_penalty_deltas,
+        );
+    }
+
+    fn assert_deltas<C: Config>(
+        case: Case,
+        state: &BeaconState<C>,
+        file_name: &str,
+        get_deltas: fn(&BeaconState<C>) -> Result<Deltas>,
+    ) {
+        let expected_deltas = Deltas::from(case.ssz::<SszDeltas<C>>(file_name));
+        let deltas = get_deltas(state).expect("deltas should be computed");
+        assert_eq!(deltas, expected_deltas, "{} do not match", file_name);
+    }
+
+    fn run_case<C: Config>(case: Case, process: fn(&mut BeaconState<C>) -> Result<()>) {
+        testing::run_case(case, process);
+    }
+}
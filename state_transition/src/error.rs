@@ -0,0 +1,96 @@
+use thiserror::Error;
+use types::primitives::{CommitteeIndex, Epoch, Root, Shard, Slot, ValidatorIndex};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("state is already at or past the target slot ({slot} >= {target_slot})")]
+    SlotNotLater { slot: Slot, target_slot: Slot },
+    #[error("block signature is invalid")]
+    InvalidBlockSignature {},
+    #[error("state root is incorrect ({root} != {expected_root})")]
+    IncorrectStateRoot { root: Root, expected_root: Root },
+    #[error("block is for the wrong slot ({slot} != {state_slot})")]
+    IncorrectBlockSlot { slot: Slot, state_slot: Slot },
+    #[error("block is not newer than the latest block header ({slot} <= {latest_slot})")]
+    BlockNotNewer { slot: Slot, latest_slot: Slot },
+    #[error("block has the wrong proposer ({proposer_index} != {expected_proposer_index})")]
+    IncorrectProposer {
+        proposer_index: ValidatorIndex,
+        expected_proposer_index: ValidatorIndex,
+    },
+    #[error("block parent root is incorrect ({root} != {expected_root})")]
+    IncorrectParentRoot { root: Root, expected_root: Root },
+    #[error("unknown validator ({index})")]
+    UnknownValidator { index: ValidatorIndex },
+    #[error("proposer {index} is slashed")]
+    ProposerSlashed { index: ValidatorIndex },
+    #[error("RANDAO reveal is invalid")]
+    InvalidRandaoReveal {},
+    #[error("eth1 data vote list is full")]
+    Eth1DataVotesFull {},
+    #[error("block has the wrong number of deposits ({count} != {expected_count})")]
+    IncorrectDepositCount { count: u64, expected_count: u64 },
+    #[error("proposer slashing headers are for different slots ({slot_1} != {slot_2})")]
+    ProposerSlashingSlotMismatch { slot_1: Slot, slot_2: Slot },
+    #[error("proposer slashing headers are for different proposers ({index_1} != {index_2})")]
+    ProposerSlashingProposerMismatch {
+        index_1: ValidatorIndex,
+        index_2: ValidatorIndex,
+    },
+    #[error("proposer slashing headers are identical")]
+    ProposerSlashingSameHeader {},
+    #[error("validator {index} is not slashable")]
+    ValidatorNotSlashable { index: ValidatorIndex },
+    #[error("proposer slashing signature is invalid")]
+    InvalidProposerSlashingSignature {},
+    #[error("attestation data is not slashable")]
+    AttestationDataNotSlashable {},
+    #[error("attester slashing does not slash any validator")]
+    NoValidatorSlashed {},
+    #[error("committee index is out of range ({index} >= {committee_count})")]
+    CommitteeIndexOutOfRange {
+        index: CommitteeIndex,
+        committee_count: u64,
+    },
+    #[error("attestation target epoch is neither the previous nor the current one ({epoch})")]
+    TargetEpochOutOfRange { epoch: Epoch },
+    #[error("attestation target epoch does not match its slot ({epoch} != {slot_epoch})")]
+    TargetEpochSlotMismatch { epoch: Epoch, slot_epoch: Epoch },
+    #[error("attestation from slot {slot} cannot be included at slot {state_slot}")]
+    AttestationInclusionOutOfRange { slot: Slot, state_slot: Slot },
+    #[error("aggregation bits do not match the committee ({length} != {committee_length})")]
+    AggregationBitsLengthMismatch {
+        length: usize,
+        committee_length: usize,
+    },
+    #[error("attestation source is not the justified checkpoint")]
+    IncorrectAttestationSource {},
+    #[error("attestation beacon block root is incorrect ({root} != {expected_root})")]
+    IncorrectAttestationBlockRoot { root: Root, expected_root: Root },
+    #[error("attestation is for the wrong shard ({shard} != {expected_shard})")]
+    IncorrectAttestationShard { shard: Shard, expected_shard: Shard },
+    #[error("attestation shard transition root is incorrect ({root})")]
+    IncorrectShardTransitionRoot { root: Root },
+    #[error("late attestation from slot {slot} is not old enough for slot {state_slot}")]
+    AttestationNotLate { slot: Slot, state_slot: Slot },
+    #[error("epoch attestation list is full")]
+    EpochAttestationsFull {},
+    #[error("deposit Merkle branch is invalid")]
+    InvalidDepositBranch {},
+    #[error("validator registry is full")]
+    ValidatorRegistryFull {},
+    #[error("validator {index} is not active")]
+    ValidatorNotActive { index: ValidatorIndex },
+    #[error("validator {index} has already initiated an exit")]
+    ValidatorAlreadyExiting { index: ValidatorIndex },
+    #[error("voluntary exit is for a future epoch ({epoch} > {current_epoch})")]
+    VoluntaryExitTooEarly { epoch: Epoch, current_epoch: Epoch },
+    #[error("validator {index} has not been active long enough to exit")]
+    ValidatorTooNewToExit { index: ValidatorIndex },
+    #[error("voluntary exit signature is invalid")]
+    InvalidVoluntaryExitSignature {},
+    #[error("epoch {epoch} is neither the previous nor the current one")]
+    EpochOutOfRange { epoch: Epoch },
+    #[error("historical root list is full")]
+    HistoricalRootsFull {},
+}
@@ -0,0 +1,475 @@
+pub mod block_processing;
+pub mod epoch_processing;
+mod error;
+
+use core::convert::TryFrom as _;
+
+use anyhow::{ensure, Result};
+use block_processing::{process_block, validator};
+use epoch_processing::process_epoch;
+use error::Error;
+use helper_functions::{
+    accessors::{get_current_epoch, get_domain},
+    crypto::bls_verify,
+    misc::{compute_epoch_at_slot, compute_signing_root},
+    tree_hash_cache::BeaconStateTreeHashCache,
+};
+use ssz_new::SszHash as _;
+use typenum::Unsigned as _;
+use types::{
+    beacon_state::BeaconState,
+    chain_spec::ChainSpec,
+    config::Config,
+    containers::SignedBeaconBlock,
+    primitives::{Slot, H256},
+};
+
+// Signature verification can be turned off when replaying blocks that are already known to be
+// valid. The state root is checked regardless.
+pub fn state_transition<C: Config>(
+    state: &mut BeaconState<C>,
+    tree_hash_cache: &mut BeaconStateTreeHashCache<C>,
+    signed_block: &SignedBeaconBlock<C>,
+    verify_signatures: bool,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    let block = &signed_block.message;
+
+    // Process slots (including those with no blocks) since block
+    process_slots(state, tree_hash_cache, block.slot, chain_spec)?;
+
+    if verify_signatures {
+        ensure!(
+            verify_block_signature(state, signed_block)?,
+            Error::InvalidBlockSignature {},
+        );
+    }
+
+    process_block(state, block, verify_signatures, chain_spec)?;
+
+    let expected_root = tree_hash_cache.tree_hash_root(state);
+    ensure!(
+        block.state_root == expected_root,
+        Error::IncorrectStateRoot {
+            root: block.state_root,
+            expected_root,
+        },
+    );
+
+    Ok(())
+}
+
+pub fn verify_block_signature<C: Config>(
+    state: &BeaconState<C>,
+    signed_block: &SignedBeaconBlock<C>,
+) -> Result<bool> {
+    let proposer = validator(state, signed_block.message.proposer_index)?;
+    let domain = get_domain(state, C::DOMAIN_BEACON_PROPOSER, None);
+    let signing_root = compute_signing_root(&signed_block.message, domain);
+    bls_verify(
+        proposer.pubkey,
+        signing_root.as_bytes(),
+        signed_block.signature,
+    )
+}
+
+// `tree_hash_cache` should be kept alongside `state` between calls. Slot processing writes to
+// `state_roots` and `block_roots` through it, so hashing a state on every slot stays cheap.
+// The roots are the same as those of `beacon_state_hash_tree_root`, which rehashes every field
+// other than `validators` and `balances` on every call.
+pub fn process_slots<C: Config>(
+    state: &mut BeaconState<C>,
+    tree_hash_cache: &mut BeaconStateTreeHashCache<C>,
+    slot: Slot,
+    chain_spec: &ChainSpec,
+) -> Result<()> {
+    ensure!(
+        state.slot < slot,
+        Error::SlotNotLater {
+            slot: state.slot,
+            target_slot: slot,
+        },
+    );
+
+    while state.slot < slot {
+        process_slot(state, tree_hash_cache)?;
+
+        // Process epoch on the start slot of the next epoch
+        if compute_epoch_at_slot::<C>(state.slot + 1) > get_current_epoch(state) {
+            process_epoch(state, chain_spec)?;
+        }
+
+        state.slot += 1;
+    }
+
+    Ok(())
+}
+
+pub fn process_slot<C: Config>(
+    state: &mut BeaconState<C>,
+    tree_hash_cache: &mut BeaconStateTreeHashCache<C>,
+) -> Result<()> {
+    let index = usize::try_from(state.slot % C::SlotsPerHistoricalRoot::U64)?;
+
+    // Cache state root
+    let previous_state_root = tree_hash_cache.tree_hash_root(state);
+    tree_hash_cache.set_state_root(state, index, previous_state_root);
+
+    // Cache latest block header state root
+    if state.latest_block_header.state_root == H256::zero() {
+        state.latest_block_header.state_root = previous_state_root;
+    }
+
+    // Cache block root
+    let previous_block_root = state.latest_block_header.tree_hash_root();
+    tree_hash_cache.set_block_root(state, index, previous_block_root);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bls::{Signature, SignatureBytes};
+    use helper_functions::{
+        accessors::get_beacon_proposer_index, cache::beacon_state_hash_tree_root,
+    };
+    use spec_test_utils::testing::{self, assert_error, secret_key};
+    use ssz_new::types::VariableList;
+    use types::{
+        config::MinimalConfig,
+        containers::{BeaconBlock, BeaconBlockBody, ShardState},
+    };
+
+    use super::*;
+
+    const VALIDATOR_COUNT: usize = 64;
+
+    fn chain_spec() -> ChainSpec {
+        ChainSpec::from_config::<MinimalConfig>()
+    }
+
+    fn state() -> BeaconState<MinimalConfig> {
+        let shard_count = MinimalConfig::INITIAL_ACTIVE_SHARDS as usize;
+
+        BeaconState {
+            shard_states: Arc::new(VariableList::from(vec![ShardState::default(); shard_count])),
+            online_countdown: Arc::new(VariableList::from(vec![0; VALIDATOR_COUNT])),
+            ..testing::state(VALIDATOR_COUNT)
+        }
+    }
+
+    // Builds a valid block on top of `state` and computes its state root by applying it to a copy.
+    fn signed_block(
+        state: &BeaconState<MinimalConfig>,
+        slot: Slot,
+    ) -> SignedBeaconBlock<MinimalConfig> {
+        signed_block_with_light_client_signature(state, slot, Signature::default().into())
+    }
+
+    fn signed_block_with_light_client_signature(
+        state: &BeaconState<MinimalConfig>,
+        slot: Slot,
+        light_client_signature: SignatureBytes,
+    ) -> SignedBeaconBlock<MinimalConfig> {
+        let mut advanced_state = state.clone();
+        process_slots(
+            &mut advanced_state,
+            &mut BeaconStateTreeHashCache::new(),
+            slot,
+            &chain_spec(),
+        )
+        .expect("the target slot is later than the state slot");
+
+        let proposer_index = get_beacon_proposer_index(&advanced_state)
+            .expect("the test state has active validators");
+        let epoch = get_current_epoch(&advanced_state);
+        let randao_domain = get_domain(&advanced_state, MinimalConfig::DOMAIN_RANDAO, None);
+
+        let mut block = BeaconBlock {
+            slot,
+            proposer_index,
+            parent_root: advanced_state.latest_block_header.tree_hash_root(),
+            state_root: H256::zero(),
+            body: BeaconBlockBody {
+                randao_reveal: secret_key(proposer_index)
+                    .sign(compute_signing_root(&epoch, randao_domain))
+                    .into(),
+                light_client_signature,
+                ..BeaconBlockBody::default()
+            },
+        };
+
+        process_block(&mut advanced_state, &block, false, &chain_spec())
+            .expect("the block is valid");
+        block.state_root = beacon_state_hash_tree_root(&advanced_state);
+
+        let proposer_domain =
+            get_domain(&advanced_state, MinimalConfig::DOMAIN_BEACON_PROPOSER, None);
+        let signature = secret_key(proposer_index)
+            .sign(compute_signing_root(&block, proposer_domain))
+            .into();
+
+        SignedBeaconBlock {
+            message: block,
+            signature,
+        }
+    }
+
+    #[test]
+    fn test_process_slots_caches_roots() {
+        let mut state = state();
+        let pre_state_root = beacon_state_hash_tree_root(&state);
+
+        process_slots(
+            &mut state,
+            &mut BeaconStateTreeHashCache::new(),
+            3,
+            &chain_spec(),
+        )
+        .expect("slot 3 is later than slot 0");
+
+        assert_eq!(state.slot, 3);
+        assert_eq!(state.state_roots[0], pre_state_root);
+        assert_eq!(state.latest_block_header.state_root, pre_state_root);
+
+        // Empty slots repeat the root of the latest block.
+        let block_root = state.latest_block_header.tree_hash_root();
+        assert_eq!(state.block_roots[..3], [block_root; 3]);
+        assert_ne!(state.state_roots[1], H256::zero());
+        assert_ne!(state.state_roots[1], state.state_roots[2]);
+        assert_eq!(state.state_roots[3], H256::zero());
+    }
+
+    #[test]
+    fn test_process_slots_processes_epochs() {
+        let mut state = state();
+        state.online_countdown = Arc::new(VariableList::from(vec![1; VALIDATOR_COUNT]));
+        let slots_per_epoch = <MinimalConfig as Config>::SlotsPerEpoch::U64;
+
+        process_slots(
+            &mut state,
+            &mut BeaconStateTreeHashCache::new(),
+            slots_per_epoch + 1,
+            &chain_spec(),
+        )
+        .expect("processing the first epoch should succeed");
+
+        assert_eq!(state.slot, slots_per_epoch + 1);
+        // Validators that did not attest drop out of the online set.
+        assert_eq!(state.online_countdown[..], [0; VALIDATOR_COUNT][..]);
+    }
+
+    #[test]
+    fn test_process_slots_with_persistent_cache_matches_full_rehash() {
+        let mut state = state();
+        let mut tree_hash_cache = BeaconStateTreeHashCache::new();
+        let slots_per_epoch = <MinimalConfig as Config>::SlotsPerEpoch::U64;
+
+        // Cross an epoch boundary so that epoch processing changes fields behind the cache.
+        for slot in 1..=2 * slots_per_epoch + 1 {
+            let expected_root = state.tree_hash_root();
+
+            process_slots(&mut state, &mut tree_hash_cache, slot, &chain_spec())
+                .expect("the target slot is later than the state slot");
+
+            let index = usize::try_from(
+                (slot - 1) % <MinimalConfig as Config>::SlotsPerHistoricalRoot::U64,
+            )
+            .expect("the index fits in usize");
+            assert_eq!(state.state_roots[index], expected_root);
+        }
+
+        assert_eq!(
+            tree_hash_cache.tree_hash_root(&state),
+            state.tree_hash_root()
+        );
+    }
+
+    #[test]
+    fn test_process_slots_rejects_earlier_slot() {
+        let mut state = state();
+        state.slot = 5;
+
+        assert_error(
+            process_slots(
+                &mut state,
+                &mut BeaconStateTreeHashCache::new(),
+                5,
+                &chain_spec(),
+            ),
+            |error: &Error| {
+                matches!(
+                    error,
+                    Error::SlotNotLater {
+                        slot: 5,
+                        target_slot: 5,
+                    }
+                )
+            },
+        );
+    }
+
+    #[test]
+    fn test_state_transition() {
+        let mut state = state();
+        let signed_block = signed_block(&state, 1);
+
+        state_transition(
+            &mut state,
+            &mut BeaconStateTreeHashCache::new(),
+            &signed_block,
+            true,
+            &chain_spec(),
+        )
+        .expect("the block is valid");
+
+        assert_eq!(state.slot, 1);
+        assert_eq!(
+            state.latest_block_header.body_root,
+            signed_block.message.body.tree_hash_root(),
+        );
+        assert_eq!(state.eth1_data_votes.len(), 1);
+    }
+
+    #[test]
+    fn test_state_transition_with_invalid_block_signature() {
+        let mut state = state();
+        let mut signed_block = signed_block(&state, 1);
+        signed_block.signature = Signature::default().into();
+
+        assert_error(
+            state_transition(
+                &mut state.clone(),
+                &mut BeaconStateTreeHashCache::new(),
+                &signed_block,
+                true,
+                &chain_spec(),
+            ),
+            |error: &Error| matches!(error, Error::InvalidBlockSignature {}),
+        );
+
+        // Signatures are not checked when replaying blocks.
+        state_transition(
+            &mut state,
+            &mut BeaconStateTreeHashCache::new(),
+            &signed_block,
+            false,
+            &chain_spec(),
+        )
+        .expect("the block is valid apart from its signature");
+    }
+
+    #[test]
+    fn test_state_transition_with_invalid_light_client_signature() {
+        let mut state = state();
+        let signed_block =
+            signed_block_with_light_client_signature(&state, 1, SignatureBytes::default());
+
+        assert!(state_transition(
+            &mut state.clone(),
+            &mut BeaconStateTreeHashCache::new(),
+            &signed_block,
+            true,
+            &chain_spec()
+        )
+        .is_err());
+
+        // Signatures are not checked when replaying blocks.
+        state_transition(
+            &mut state,
+            &mut BeaconStateTreeHashCache::new(),
+            &signed_block,
+            false,
+            &chain_spec(),
+        )
+        .expect("the block is valid apart from its light client signature");
+    }
+
+    #[test]
+    fn test_state_transition_with_incorrect_state_root() {
+        let mut state = state();
+        let mut signed_block = signed_block(&state, 1);
+        signed_block.message.state_root = H256::repeat_byte(1);
+
+        assert_error(
+            state_transition(
+                &mut state,
+                &mut BeaconStateTreeHashCache::new(),
+                &signed_block,
+                false,
+                &chain_spec(),
+            ),
+            |error: &Error| matches!(error, Error::IncorrectStateRoot { .. }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod spec_tests {
+    use spec_test_utils::{testing, BlsSetting, Case};
+    use test_generator::test_resources;
+    use types::config::{MainnetConfig, MinimalConfig};
+
+    use super::*;
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/sanity/slots/*/*")]
+    fn mainnet_slots(case: Case) {
+        run_slots_case::<MainnetConfig>(case);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/sanity/slots/*/*")]
+    fn minimal_slots(case: Case) {
+        run_slots_case::<MinimalConfig>(case);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/mainnet/phase1/sanity/blocks/*/*")]
+    fn mainnet_blocks(case: Case) {
+        run_blocks_case::<MainnetConfig>(case);
+    }
+
+    #[test_resources("eth2.0-spec-tests/tests/minimal/phase1/sanity/blocks/*/*")]
+    fn minimal_blocks(case: Case) {
+        run_blocks_case::<MinimalConfig>(case);
+    }
+
+    fn run_slots_case<C: Config>(case: Case) {
+        let mut state: BeaconState<C> = case.ssz("pre");
+        let slots: u64 = case.yaml("slots");
+        let expected_post: BeaconState<C> = case.ssz("post");
+
+        let target_slot = state.slot + slots;
+
+        process_slots(
+            &mut state,
+            &mut BeaconStateTreeHashCache::new(),
+            target_slot,
+            &ChainSpec::from_config::<C>(),
+        )
+        .expect("processing slots should succeed");
+
+        assert_eq!(state, expected_post);
+    }
+
+    fn run_blocks_case<C: Config>(case: Case) {
+        let meta = case.meta();
+        let verify_signatures = meta.bls_setting != BlsSetting::Ignored;
+        let chain_spec = ChainSpec::from_config::<C>();
+
+        let mut tree_hash_cache = BeaconStateTreeHashCache::new();
+
+        testing::run_case(case, |state: &mut BeaconState<C>| {
+            case.iterator::<SignedBeaconBlock<C>>("blocks", meta.blocks_count)
+                .try_for_each(|block| {
+                    state_transition(
+                        state,
+                        &mut tree_hash_cache,
+                        &block,
+                        verify_signatures,
+                        &chain_spec,
+                    )
+                })
+        });
+    }
+}
@@ -1,2 +0,0 @@
-pub mod beacon_chain;
-pub mod shard_transition;
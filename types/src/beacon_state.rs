@@ -3,7 +3,7 @@ use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
 use ssz_new::types::{BitVector, FixedVector, VariableList};
-use ssz_new_derive::{SszDecode, SszEncode};
+use ssz_new_derive::{SszDecode, SszEncode, SszHash};
 
 use crate::{
     config::Config,
@@ -16,11 +16,22 @@ use crate::{
     primitives::{Gwei, OnlineEpochs, Shard, Slot, ValidatorIndex, H256},
 };
 
-#[cfg(test)]
-use tree_hash_derive::TreeHash;
+pub type ExposedDerivedSecrets<C> = FixedVector<
+    VariableList<ValidatorIndex, <C as Config>::MaxEarlyDerivedSecretRevealsBySlots>,
+    <C as Config>::EarlyDerivedSecretPenaltyMaxFutureEpochs,
+>;
 
-#[derive(Clone, PartialEq, Debug, SmartDefault, Serialize, Deserialize, SszDecode, SszEncode)]
-#[cfg_attr(test, derive(TreeHash))]
+/// Large fields are stored behind [`Arc`]s so that clones share them.
+///
+/// The first write to a shared field through [`ArcExt::make_mut`] copies the whole field. After a
+/// clone, `process_slot` copies `block_roots` and `state_roots` (512 KiB with the mainnet preset)
+/// once and does not copy them again on later slots. The `process_slot` benchmark in
+/// `state_transition` measures this.
+///
+/// [`ArcExt::make_mut`]: crate::arc_ext::ArcExt::make_mut
+#[derive(
+    Clone, PartialEq, Debug, SmartDefault, Serialize, Deserialize, SszDecode, SszEncode, SszHash,
+)]
 pub struct BeaconState<C: Config> {
     // Versioning
     pub genesis_time: u64,
@@ -30,10 +41,10 @@ pub struct BeaconState<C: Config> {
 
     // History
     pub latest_block_header: BeaconBlockHeader,
-    #[default(fixed_vector::default())]
-    pub block_roots: FixedVector<H256, C::SlotsPerHistoricalRoot>,
-    #[default(fixed_vector::default())]
-    pub state_roots: FixedVector<H256, C::SlotsPerHistoricalRoot>,
+    #[default(Arc::new(fixed_vector::default()))]
+    pub block_roots: Arc<FixedVector<H256, C::SlotsPerHistoricalRoot>>,
+    #[default(Arc::new(fixed_vector::default()))]
+    pub state_roots: Arc<FixedVector<H256, C::SlotsPerHistoricalRoot>>,
     pub historical_roots: VariableList<H256, C::HistoricalRootsLimit>,
 
     // Eth1
@@ -46,17 +57,18 @@ pub struct BeaconState<C: Config> {
     pub balances: Arc<VariableList<Gwei, C::ValidatorRegistryLimit>>,
 
     // Randomness
-    #[default(fixed_vector::default())]
-    pub randao_mixes: FixedVector<H256, C::EpochsPerHistoricalVector>,
+    #[default(Arc::new(fixed_vector::default()))]
+    pub randao_mixes: Arc<FixedVector<H256, C::EpochsPerHistoricalVector>>,
 
     // Slashings
-    #[default(fixed_vector::default())]
-    pub slashings: FixedVector<u64, C::EpochsPerSlashingsVector>,
+    #[default(Arc::new(fixed_vector::default()))]
+    pub slashings: Arc<FixedVector<u64, C::EpochsPerSlashingsVector>>,
 
     // Attestations
     pub previous_epoch_attestations:
-        VariableList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
-    pub current_epoch_attestations: VariableList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>,
+        Arc<VariableList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>>,
+    pub current_epoch_attestations:
+        Arc<VariableList<PendingAttestation<C>, C::MaxAttestationsPerEpoch>>,
 
     // Finality
     pub justification_bits: BitVector<JustificationBitsLength>,
@@ -66,15 +78,65 @@ pub struct BeaconState<C: Config> {
 
     // //Phase1
     pub current_epoch_start_shard: Shard,
-    pub shard_states: VariableList<ShardState, C::MaxShards>,
-    pub online_countdown: VariableList<OnlineEpochs, C::ValidatorRegistryLimit>,
+    pub shard_states: Arc<VariableList<ShardState, C::MaxShards>>,
+    pub online_countdown: Arc<VariableList<OnlineEpochs, C::ValidatorRegistryLimit>>,
     pub current_light_committee: CompactCommittee<C>,
     pub next_light_committee: CompactCommittee<C>,
-    pub exposed_derived_secrets: FixedVector<
-        VariableList<ValidatorIndex, C::MaxEarlyDerivedSecretRevealsBySlots>,
-        C::EarlyDerivedSecretPenaltyMaxFutureEpochs,
-    >,
+    #[default(Arc::new(fixed_vector::default()))]
+    pub exposed_derived_secrets: Arc<ExposedDerivedSecrets<C>>,
     pub custody_chunk_challenge_records:
         VariableList<CustodyChunkChallengeRecord, C::MaxCustodyChunkChallengeRecords>,
     pub custody_chunk_challenge_index: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use ssz_new::{SszDecode as _, SszEncode as _};
+    use typenum::Unsigned as _;
+
+    use crate::{arc_ext::ArcExt as _, config::MinimalConfig};
+
+    use super::*;
+
+    #[test]
+    fn test_clone_shares_large_fields() {
+        let state = BeaconState::<MinimalConfig>::default();
+        let mut clone = state.clone();
+
+        assert!(Arc::ptr_eq(&state.block_roots, &clone.block_roots));
+        assert!(Arc::ptr_eq(&state.randao_mixes, &clone.randao_mixes));
+        assert!(Arc::ptr_eq(
+            &state.online_countdown,
+            &clone.online_countdown
+        ));
+
+        clone.block_roots.make_mut()[0] = H256::repeat_byte(1);
+
+        assert!(!Arc::ptr_eq(&state.block_roots, &clone.block_roots));
+        assert!(Arc::ptr_eq(&state.state_roots, &clone.state_roots));
+        assert_eq!(state.block_roots[0], H256::zero());
+    }
+
+    #[test]
+    fn test_default_and_ssz_are_unaffected_by_sharing() {
+        let state = BeaconState::<MinimalConfig> {
+            online_countdown: Arc::new(VariableList::from(vec![1, 2, 3])),
+            ..BeaconState::default()
+        };
+
+        assert_eq!(
+            state.block_roots.len(),
+            <MinimalConfig as Config>::SlotsPerHistoricalRoot::USIZE,
+        );
+        assert_eq!(
+            state.randao_mixes.len(),
+            <MinimalConfig as Config>::EpochsPerHistoricalVector::USIZE,
+        );
+
+        let bytes = state.as_ssz_bytes();
+        let decoded = BeaconState::<MinimalConfig>::from_ssz_bytes(bytes.as_slice())
+            .expect("state was just encoded");
+
+        assert_eq!(decoded, state);
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use typenum::Unsigned as _;
+
+use crate::{
+    config::Config,
+    error::Error,
+    primitives::{Gwei, Slot, UnixSeconds, Version},
+};
+
+/// Configuration values that may differ between networks sharing the same preset.
+///
+/// Everything else in [`Config`] determines the sizes of types or is otherwise baked into the
+/// preset, so it cannot be changed without recompiling.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ChainSpec {
+    pub min_genesis_active_validator_count: u64,
+    pub min_genesis_time: UnixSeconds,
+    pub genesis_fork_version: Version,
+    pub genesis_delay: u64,
+    pub seconds_per_eth1_block: u64,
+    pub eth1_follow_distance: u64,
+    pub min_validator_withdrawability_delay: u64,
+    pub ejection_balance: Gwei,
+    pub min_per_epoch_churn_limit: u64,
+    pub churn_limit_quotient: u64,
+    #[serde(rename = "PHASE_1_FORK_VERSION")]
+    pub phase_1_fork_version: Version,
+    #[serde(rename = "PHASE_1_FORK_SLOT")]
+    pub phase_1_fork_slot: Slot,
+}
+
+impl ChainSpec {
+    #[must_use]
+    pub fn from_config<C: Config>() -> Self {
+        Self {
+            min_genesis_active_validator_count: C::MIN_GENESIS_ACTIVE_VALIDATOR_COUNT,
+            min_genesis_time: C::MIN_GENESIS_TIME,
+            genesis_fork_version: C::GENESIS_FORK_VERSION,
+            genesis_delay: C::GENESIS_DELAY,
+            seconds_per_eth1_block: C::SECONDS_PER_ETH1_BLOCK,
+            eth1_follow_distance: C::ETH1_FOLLOW_DISTANCE,
+            min_validator_withdrawability_delay: C::MIN_VALIDATOR_WITHDRAWABILITY_DELAY,
+            ejection_balance: C::EJECTION_BALANCE,
+            min_per_epoch_churn_limit: C::MIN_PER_EPOCH_CHURN_LIMIT,
+            churn_limit_quotient: C::CHURN_LIMIT_QUOTIENT,
+            phase_1_fork_version: C::PHASE_1_FORK_VERSION,
+            phase_1_fork_slot: C::PHASE_1_FORK_SLOT,
+        }
+    }
+
+    /// Loads a chain spec from a configuration file in the format used by the specification.
+    ///
+    /// Values missing from the file are taken from `C`.
+    pub fn from_yaml<C: Config>(yaml: &str) -> Result<Self> {
+        let mut chain_spec = Self::from_config::<C>();
+        chain_spec.apply_yaml::<C>(yaml)?;
+        Ok(chain_spec)
+    }
+
+    /// Overrides values with the ones in a configuration file.
+    ///
+    /// The specification splits configuration across several files, so this can be called once
+    /// for each of them. Preset values in the file must match the ones in `C`. Unknown names are
+    /// ignored to allow loading files for later forks.
+    pub fn apply_yaml<C: Config>(&mut self, yaml: &str) -> Result<()> {
+        let values = serde_yaml::from_str::<BTreeMap<String, Value>>(yaml)?;
+
+        for (name, expected) in preset_values::<C>() {
+            if let Some(actual) = values.get(name).cloned() {
+                ensure!(
+                    actual.as_u64() == Some(expected),
+                    Error::PresetMismatch {
+                        name,
+                        expected,
+                        actual,
+                    },
+                );
+            }
+        }
+
+        // `Value` does not preserve the representation of hexadecimal numbers like versions,
+        // so network values are deserialized from the file again.
+        let overrides = serde_yaml::from_str::<ChainSpecOverrides>(yaml)?;
+
+        macro_rules! apply_overrides {
+            ($($field: ident),+) => {
+                $(
+                    if let Some(value) = overrides.$field {
+                        self.$field = value;
+                    }
+                )+
+            };
+        }
+
+        apply_overrides!(
+            min_genesis_active_validator_count,
+            min_genesis_time,
+            genesis_fork_version,
+            genesis_delay,
+            seconds_per_eth1_block,
+            eth1_follow_distance,
+            min_validator_withdrawability_delay,
+            ejection_balance,
+            min_per_epoch_churn_limit,
+            churn_limit_quotient,
+            phase_1_fork_version,
+            phase_1_fork_slot
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "SCREAMING_SNAKE_CASE")]
+struct ChainSpecOverrides {
+    min_genesis_active_validator_count: Option<u64>,
+    min_genesis_time: Option<UnixSeconds>,
+    genesis_fork_version: Option<Version>,
+    genesis_delay: Option<u64>,
+    seconds_per_eth1_block: Option<u64>,
+    eth1_follow_distance: Option<u64>,
+    min_validator_withdrawability_delay: Option<u64>,
+    ejection_balance: Option<Gwei>,
+    min_per_epoch_churn_limit: Option<u64>,
+    churn_limit_quotient: Option<u64>,
+    #[serde(rename = "PHASE_1_FORK_VERSION")]
+    phase_1_fork_version: Option<Version>,
+    #[serde(rename = "PHASE_1_FORK_SLOT")]
+    phase_1_fork_slot: Option<Slot>,
+}
+
+fn preset_values<C: Config>() -> Vec<(&'static str, u64)> {
+    vec![
+        // phase0
+        ("MAX_COMMITTEES_PER_SLOT", C::MAX_COMMITTEES_PER_SLOT),
+        ("TARGET_COMMITTEE_SIZE", C::TARGET_COMMITTEE_SIZE),
+        (
+            "MAX_VALIDATORS_PER_COMMITTEE",
+            C::MaxValidatorsPerCommittee::U64,
+        ),
+        ("SHUFFLE_ROUND_COUNT", u64::from(C::SHUFFLE_ROUND_COUNT)),
+        ("HYSTERESIS_QUOTIENT", C::HYSTERESIS_QUOTIENT),
+        (
+            "HYSTERESIS_DOWNWARD_MULTIPLIER",
+            C::HYSTERESIS_DOWNWARD_MULTIPLIER,
+        ),
+        (
+            "HYSTERESIS_UPWARD_MULTIPLIER",
+            C::HYSTERESIS_UPWARD_MULTIPLIER,
+        ),
+        (
+            "SAFE_SLOTS_TO_UPDATE_JUSTIFIED",
+            C::SAFE_SLOTS_TO_UPDATE_JUSTIFIED,
+        ),
+        ("MAX_EFFECTIVE_BALANCE", C::MAX_EFFECTIVE_BALANCE),
+        (
+            "EFFECTIVE_BALANCE_INCREMENT",
+            C::EFFECTIVE_BALANCE_INCREMENT,
+        ),
+        (
+            "MIN_ATTESTATION_INCLUSION_DELAY",
+            C::MIN_ATTESTATION_INCLUSION_DELAY,
+        ),
+        ("SLOTS_PER_EPOCH", C::SlotsPerEpoch::U64),
+        ("MIN_SEED_LOOKAHEAD", C::MIN_SEED_LOOKAHEAD),
+        ("MAX_SEED_LOOKAHEAD", C::MAX_SEED_LOOKAHEAD),
+        (
+            "EPOCHS_PER_ETH1_VOTING_PERIOD",
+            C::EpochsPerEth1VotingPeriod::U64,
+        ),
+        ("SLOTS_PER_HISTORICAL_ROOT", C::SlotsPerHistoricalRoot::U64),
+        ("SHARD_COMMITTEE_PERIOD", C::SHARD_COMMITTEE_PERIOD),
+        (
+            "MIN_EPOCHS_TO_INACTIVITY_PENALTY",
+            C::MIN_EPOCHS_TO_INACTIVITY_PENALTY,
+        ),
+        (
+            "EPOCHS_PER_HISTORICAL_VECTOR",
+            C::EpochsPerHistoricalVector::U64,
+        ),
+        (
+            "EPOCHS_PER_SLASHINGS_VECTOR",
+            C::EpochsPerSlashingsVector::U64,
+        ),
+        ("HISTORICAL_ROOTS_LIMIT", C::HistoricalRootsLimit::U64),
+        ("VALIDATOR_REGISTRY_LIMIT", C::ValidatorRegistryLimit::U64),
+        ("BASE_REWARD_FACTOR", C::BASE_REWARD_FACTOR),
+        (
+            "WHISTLEBLOWER_REWARD_QUOTIENT",
+            C::WHISTLEBLOWER_REWARD_QUOTIENT,
+        ),
+        ("PROPOSER_REWARD_QUOTIENT", C::PROPOSER_REWARD_QUOTIENT),
+        (
+            "INACTIVITY_PENALTY_QUOTIENT",
+            C::INACTIVITY_PENALTY_QUOTIENT,
+        ),
+        (
+            "MIN_SLASHING_PENALTY_QUOTIENT",
+            C::MIN_SLASHING_PENALTY_QUOTIENT,
+        ),
+        ("MAX_PROPOSER_SLASHINGS", C::MaxProposerSlashings::U64),
+        ("MAX_ATTESTER_SLASHINGS", C::MaxAttesterSlashings::U64),
+        ("MAX_ATTESTATIONS", C::MaxAttestations::U64),
+        ("MAX_DEPOSITS", C::MaxDeposits::U64),
+        ("MAX_VOLUNTARY_EXITS", C::MaxVoluntaryExits::U64),
+        (
+            "TARGET_AGGREGATORS_PER_COMMITTEE",
+            C::TARGET_AGGREGATORS_PER_COMMITTEE,
+        ),
+        (
+            "RANDOM_SUBNETS_PER_VALIDATOR",
+            C::RANDOM_SUBNETS_PER_VALIDATOR,
+        ),
+        (
+            "EPOCHS_PER_RANDOM_SUBNET_SUBSCRIPTION",
+            C::EPOCHS_PER_RANDOM_SUBNET_SUBSCRIPTION,
+        ),
+        // phase1
+        ("INITIAL_ACTIVE_SHARDS", C::INITIAL_ACTIVE_SHARDS),
+        ("MAX_SHARDS", C::MaxShards::U64),
+        (
+            "LIGHT_CLIENT_COMMITTEE_SIZE",
+            C::LightClientCommitteeSize::U64,
+        ),
+        (
+            "GASPRICE_ADJUSTMENT_COEFFICIENT",
+            C::GASPRICE_ADJUSTMENT_COEFFICIENT,
+        ),
+        ("MAX_SHARD_BLOCK_SIZE", C::MaxShardBlockSize::U64),
+        ("TARGET_SHARD_BLOCK_SIZE", C::TARGET_SHARD_BLOCK_SIZE),
+        (
+            "MAX_SHARD_BLOCKS_PER_ATTESTATION",
+            C::MaxShardBlocksPerAttestation::U64,
+        ),
+        ("BYTES_PER_CUSTODY_CHUNK", C::BytesPerCustodyChunk::U64),
+        (
+            "CUSTODY_RESPONSE_DEPTH",
+            C::CustodyResponseDepthInc::U64 - 1,
+        ),
+        ("MAX_GASPRICE", C::MAX_GASPRICE),
+        ("MIN_GASPRICE", C::MIN_GASPRICE),
+        ("ONLINE_PERIOD", u64::from(C::ONLINE_PERIOD)),
+        (
+            "LIGHT_CLIENT_COMMITTEE_PERIOD",
+            C::LIGHT_CLIENT_COMMITTEE_PERIOD,
+        ),
+        (
+            "MAX_CUSTODY_CHUNK_CHALLENGE_RECORDS",
+            C::MaxCustodyChunkChallengeRecords::U64,
+        ),
+        ("RANDAO_PENALTY_EPOCHS", C::RANDAO_PENALTY_EPOCHS),
+        (
+            "EARLY_DERIVED_SECRET_PENALTY_MAX_FUTURE_EPOCHS",
+            C::EarlyDerivedSecretPenaltyMaxFutureEpochs::U64,
+        ),
+        ("EPOCHS_PER_CUSTODY_PERIOD", C::EPOCHS_PER_CUSTODY_PERIOD),
+        (
+            "CUSTODY_PERIOD_TO_RANDAO_PADDING",
+            C::CUSTODY_PERIOD_TO_RANDAO_PADDING,
+        ),
+        ("MAX_CHUNK_CHALLENGE_DELAY", C::MAX_CHUNK_CHALLENGE_DELAY),
+        ("MAX_CUSTODY_KEY_REVEALS", C::MaxCustodyKeyReveals::U64),
+        (
+            "MAX_EARLY_DERIVED_SECRET_REVEALS",
+            C::MaxEarlyDerivedSecretReveals::U64,
+        ),
+        (
+            "MAX_CUSTODY_CHUNK_CHALLENGES",
+            C::MaxCustodyChunkChallenges::U64,
+        ),
+        (
+            "MAX_CUSTODY_CHUNK_CHALLENGE_RESP",
+            C::MaxCustodyChunkChallengeResponses::U64,
+        ),
+        ("MAX_CUSTODY_SLASHINGS", C::MaxCustodySlashings::U64),
+        (
+            "EARLY_DERIVED_SECRET_REVEAL_SLOT_REWARD_MULTIPLE",
+            C::EARLY_DERIVED_SECRET_REVEAL_SLOT_REWARD_MULTIPLE,
+        ),
+        ("MINOR_REWARD_QUOTIENT", C::MINOR_REWARD_QUOTIENT),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+
+    use crate::config::{MainnetConfig, MinimalConfig, SpadinaConfig};
+
+    use super::*;
+
+    #[test]
+    fn test_from_yaml_loads_preset_file() {
+        let chain_spec =
+            ChainSpec::from_yaml::<MinimalConfig>(include_str!("../configs/minimal/phase1.yaml"))
+                .expect("the file matches the minimal preset");
+
+        assert_eq!(chain_spec, ChainSpec::from_config::<MinimalConfig>());
+    }
+
+    #[test]
+    fn test_from_yaml_rejects_other_preset() {
+        let error =
+            ChainSpec::from_yaml::<MainnetConfig>(include_str!("../configs/minimal/phase1.yaml"))
+                .expect_err("the file does not match the mainnet preset");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::PresetMismatch {
+                name: "INITIAL_ACTIVE_SHARDS",
+                expected: 64,
+                ..
+            }),
+        ));
+    }
+
+    #[test]
+    fn test_from_yaml_overrides_network_values() {
+        let yaml = "
+            SLOTS_PER_EPOCH: 32
+            MIN_GENESIS_ACTIVE_VALIDATOR_COUNT: 128
+            MIN_GENESIS_TIME: 1600000000
+            GENESIS_FORK_VERSION: 0x00000042
+            CHURN_LIMIT_QUOTIENT: 32
+            DEPOSIT_CHAIN_ID: 5
+        ";
+
+        let chain_spec =
+            ChainSpec::from_yaml::<SpadinaConfig>(yaml).expect("the file matches the preset");
+
+        assert_eq!(
+            chain_spec,
+            ChainSpec {
+                min_genesis_active_validator_count: 128,
+                min_genesis_time: 1_600_000_000,
+                genesis_fork_version: Version::new(hex!("00000042")),
+                churn_limit_quotient: 32,
+                ..ChainSpec::from_config::<SpadinaConfig>()
+            },
+        );
+    }
+
+    #[test]
+    fn test_from_yaml_rejects_type_level_mismatch() {
+        let error = ChainSpec::from_yaml::<MinimalConfig>("SLOTS_PER_EPOCH: 32")
+            .expect_err("the minimal preset has 8 slots per epoch");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::PresetMismatch {
+                name: "SLOTS_PER_EPOCH",
+                expected: 8,
+                ..
+            }),
+        ));
+    }
+}
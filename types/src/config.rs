@@ -10,7 +10,9 @@ use typenum::{
     U16, U16777216, U2, U2048, U256, U32, U32768, U4, U4096, U64, U65536, U8, U8192, U9,
 };
 
-use crate::primitives::{DomainType, Gwei, UnixSeconds, ValidatorIndex, Version};
+use crate::primitives::{
+    DomainType, Gwei, OnlineEpochs, Slot, UnixSeconds, ValidatorIndex, Version,
+};
 
 pub trait Config: Clone + Eq + Default + Debug + Send + Sync + Serialize + 'static {
     type EpochsPerEth1VotingPeriod: Unsigned;
@@ -63,29 +65,43 @@ pub trait Config: Clone + Eq + Default + Debug + Send + Sync + Serialize + 'stat
     const BASE_REWARD_FACTOR: u64 = 64;
     const BLS_WITHDRAWAL_PREFIX_BYTE: u8 = 0x00;
     const CHURN_LIMIT_QUOTIENT: u64 = 0x0001_0000;
+    const CUSTODY_PERIOD_TO_RANDAO_PADDING: u64 = 1 << 11;
     const DOMAIN_AGGREGATE_AND_PROOF: DomainType = 6;
     const DOMAIN_BEACON_ATTESTER: DomainType = 1;
     const DOMAIN_BEACON_PROPOSER: DomainType = 0;
+    const DOMAIN_CUSTODY_BIT_SLASHING: DomainType = 0x83;
     const DOMAIN_DEPOSIT: DomainType = 3;
+    const DOMAIN_LIGHT_AGGREGATE_AND_PROOF: DomainType = 0x85;
+    const DOMAIN_LIGHT_CLIENT: DomainType = 0x82;
+    const DOMAIN_LIGHT_SELECTION_PROOF: DomainType = 0x84;
     const DOMAIN_RANDAO: DomainType = 2;
     const DOMAIN_SELECTION_PROOF: DomainType = 5;
-    const DOMAIN_SHARD_PROPOSAL: DomainType = 81;
+    const DOMAIN_SHARD_COMMITTEE: DomainType = 0x81;
+    const DOMAIN_SHARD_PROPOSAL: DomainType = 0x80;
     const DOMAIN_VOLUNTARY_EXIT: DomainType = 4;
+    const EARLY_DERIVED_SECRET_REVEAL_SLOT_REWARD_MULTIPLE: u64 = 2;
     const EFFECTIVE_BALANCE_INCREMENT: Gwei = 1_000_000_000;
     const EJECTION_BALANCE: Gwei = 16_000_000_000;
+    const EPOCHS_PER_CUSTODY_PERIOD: u64 = 1 << 14;
     const EPOCHS_PER_RANDOM_SUBNET_SUBSCRIPTION: u64 = 16;
     const ETH1_FOLLOW_DISTANCE: u64 = 1024;
+    const GASPRICE_ADJUSTMENT_COEFFICIENT: u64 = 8;
     const GENESIS_DELAY: u64 = 172_800;
     const GENESIS_FORK_VERSION: Version = Version::new(hex!("00000000"));
     const HYSTERESIS_DOWNWARD_MULTIPLIER: u64 = 1;
     const HYSTERESIS_QUOTIENT: u64 = 4;
     const HYSTERESIS_UPWARD_MULTIPLIER: u64 = 5;
     const INACTIVITY_PENALTY_QUOTIENT: u64 = 1 << 24;
+    const INITIAL_ACTIVE_SHARDS: u64 = 64;
+    const LIGHT_CLIENT_COMMITTEE_PERIOD: u64 = 256;
+    const MAX_CHUNK_CHALLENGE_DELAY: u64 = 1 << 15;
     const MAX_COMMITTEES_PER_SLOT: u64 = 64;
     const MAX_EFFECTIVE_BALANCE: Gwei = 32_000_000_000;
+    const MAX_GASPRICE: Gwei = 1 << 14;
     const MAX_SEED_LOOKAHEAD: u64 = 4;
     const MIN_ATTESTATION_INCLUSION_DELAY: u64 = 1;
     const MIN_EPOCHS_TO_INACTIVITY_PENALTY: u64 = 4;
+    const MIN_GASPRICE: Gwei = 8;
     const MIN_GENESIS_ACTIVE_VALIDATOR_COUNT: u64 = 1 << 14;
     // Bitcoin's 11th anniversary
     // (see <https://github.com/ethereum/eth2.0-specs/issues/1129#issue-448918350>).
@@ -94,15 +110,22 @@ pub trait Config: Clone + Eq + Default + Debug + Send + Sync + Serialize + 'stat
     const MIN_SEED_LOOKAHEAD: u64 = 1;
     const MIN_SLASHING_PENALTY_QUOTIENT: u64 = 32;
     const MIN_VALIDATOR_WITHDRAWABILITY_DELAY: u64 = 256;
+    const MINOR_REWARD_QUOTIENT: u64 = 1 << 8;
+    const ONLINE_PERIOD: OnlineEpochs = 8;
+    const PHASE_1_FORK_SLOT: Slot = 0;
+    const PHASE_1_FORK_VERSION: Version = Version::new(hex!("01000000"));
     const PROPOSER_REWARD_QUOTIENT: u64 = 8;
+    const RANDAO_PENALTY_EPOCHS: u64 = 2;
     const RANDOM_SUBNETS_PER_VALIDATOR: u64 = 1;
     const SAFE_SLOTS_TO_UPDATE_JUSTIFIED: u64 = 8;
     const SECONDS_PER_ETH1_BLOCK: u64 = 14;
+    const SHARD_BLOCK_OFFSETS: &'static [Slot] = &[1, 2, 3, 5, 8, 13, 21, 34, 55, 89, 144, 233];
     const SHARD_COMMITTEE_PERIOD: u64 = 256;
     const SHUFFLE_ROUND_COUNT: u8 = 90;
-    const SLOTS_PER_EPOCH: u64 = 8;
+    const SLOTS_PER_EPOCH: u64 = Self::SlotsPerEpoch::U64;
     const TARGET_AGGREGATORS_PER_COMMITTEE: u64 = 16;
     const TARGET_COMMITTEE_SIZE: u64 = 128;
+    const TARGET_SHARD_BLOCK_SIZE: u64 = 1 << 18;
     const WHISTLEBLOWER_REWARD_QUOTIENT: u64 = 512;
 
     // COMMENT(Sifrai Team): `slot_timer::next_tick_with_instant` relies on this being nonzero.
@@ -203,11 +226,17 @@ impl Config for MinimalConfig {
 
     const THIRD_OF_SLOT: NonZeroU64 = nonzero!(2_u64);
 
+    const CUSTODY_PERIOD_TO_RANDAO_PADDING: u64 = 8;
+    const EPOCHS_PER_CUSTODY_PERIOD: u64 = 8;
     const ETH1_FOLLOW_DISTANCE: u64 = 16;
     const GENESIS_DELAY: u64 = 300;
     const GENESIS_FORK_VERSION: Version = Version::new(hex!("00000001"));
+    const INITIAL_ACTIVE_SHARDS: u64 = 2;
+    const LIGHT_CLIENT_COMMITTEE_PERIOD: u64 = 8;
+    const MAX_CHUNK_CHALLENGE_DELAY: u64 = 64;
     const MAX_COMMITTEES_PER_SLOT: u64 = 4;
     const MIN_GENESIS_ACTIVE_VALIDATOR_COUNT: ValidatorIndex = 64;
+    const PHASE_1_FORK_VERSION: Version = Version::new(hex!("01000001"));
     const SAFE_SLOTS_TO_UPDATE_JUSTIFIED: u64 = 2;
     const SHARD_COMMITTEE_PERIOD: u64 = 64;
     const SHUFFLE_ROUND_COUNT: u8 = 10;
@@ -303,3 +332,204 @@ impl Config for SpadinaConfig {
     const MIN_GENESIS_ACTIVE_VALIDATOR_COUNT: u64 = 1024;
     const MIN_GENESIS_TIME: UnixSeconds = 1_601_380_800;
 }
+
+#[cfg(test)]
+mod tests {
+    use ethereum_types::{H32, U256};
+
+    use crate::consts::{BYTES_PER_CUSTODY_ATOM, CUSTODY_PROBABILITY_EXPONENT, CUSTODY_SECRETS};
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields, rename_all = "SCREAMING_SNAKE_CASE")]
+    struct Phase1Preset {
+        #[serde(rename = "PHASE_1_FORK_VERSION")]
+        phase_1_fork_version: Version,
+        #[serde(rename = "PHASE_1_FORK_SLOT")]
+        phase_1_fork_slot: Slot,
+        initial_active_shards: u64,
+        max_shards: u64,
+        light_client_committee_size: u64,
+        gasprice_adjustment_coefficient: u64,
+        max_shard_block_size: u64,
+        target_shard_block_size: u64,
+        shard_block_offsets: Vec<Slot>,
+        max_shard_blocks_per_attestation: u64,
+        bytes_per_custody_chunk: u64,
+        custody_response_depth: u64,
+        max_gasprice: Gwei,
+        min_gasprice: Gwei,
+        online_period: OnlineEpochs,
+        light_client_committee_period: u64,
+        max_custody_chunk_challenge_records: u64,
+        domain_shard_proposal: H32,
+        domain_shard_committee: H32,
+        domain_light_client: H32,
+        domain_custody_bit_slashing: H32,
+        domain_light_selection_proof: H32,
+        domain_light_aggregate_and_proof: H32,
+        randao_penalty_epochs: u64,
+        early_derived_secret_penalty_max_future_epochs: u64,
+        epochs_per_custody_period: u64,
+        custody_period_to_randao_padding: u64,
+        max_chunk_challenge_delay: u64,
+        custody_prime: String,
+        custody_secrets: usize,
+        bytes_per_custody_atom: usize,
+        custody_probability_exponent: u64,
+        max_custody_key_reveals: u64,
+        max_early_derived_secret_reveals: u64,
+        max_custody_chunk_challenges: u64,
+        max_custody_chunk_challenge_resp: u64,
+        max_custody_slashings: u64,
+        early_derived_secret_reveal_slot_reward_multiple: u64,
+        minor_reward_quotient: u64,
+    }
+
+    // Domain types are written as 4 bytes but stored in `Config` as little-endian integers.
+    fn domain_type(bytes: H32) -> DomainType {
+        DomainType::from_le_bytes(bytes.to_fixed_bytes())
+    }
+
+    fn assert_matches_phase1_preset<C: Config>(yaml: &str) {
+        let preset = serde_yaml::from_str::<Phase1Preset>(yaml)
+            .expect("the file should contain every phase 1 preset value");
+
+        assert_eq!(preset.phase_1_fork_version, C::PHASE_1_FORK_VERSION);
+        assert_eq!(preset.phase_1_fork_slot, C::PHASE_1_FORK_SLOT);
+        assert_eq!(preset.initial_active_shards, C::INITIAL_ACTIVE_SHARDS);
+        assert_eq!(preset.max_shards, C::MaxShards::U64);
+        assert_eq!(
+            preset.light_client_committee_size,
+            C::LightClientCommitteeSize::U64,
+        );
+        assert_eq!(
+            preset.gasprice_adjustment_coefficient,
+            C::GASPRICE_ADJUSTMENT_COEFFICIENT,
+        );
+        assert_eq!(preset.max_shard_block_size, C::MaxShardBlockSize::U64);
+        assert_eq!(preset.target_shard_block_size, C::TARGET_SHARD_BLOCK_SIZE);
+        assert_eq!(preset.shard_block_offsets, C::SHARD_BLOCK_OFFSETS);
+        assert_eq!(
+            preset.max_shard_blocks_per_attestation,
+            C::MaxShardBlocksPerAttestation::U64,
+        );
+        assert_eq!(preset.bytes_per_custody_chunk, C::BytesPerCustodyChunk::U64);
+        assert_eq!(
+            preset.custody_response_depth + 1,
+            C::CustodyResponseDepthInc::U64,
+        );
+        assert_eq!(preset.max_gasprice, C::MAX_GASPRICE);
+        assert_eq!(preset.min_gasprice, C::MIN_GASPRICE);
+        assert_eq!(preset.online_period, C::ONLINE_PERIOD);
+        assert_eq!(
+            preset.light_client_committee_period,
+            C::LIGHT_CLIENT_COMMITTEE_PERIOD,
+        );
+        assert_eq!(
+            preset.max_custody_chunk_challenge_records,
+            C::MaxCustodyChunkChallengeRecords::U64,
+        );
+        assert_eq!(
+            domain_type(preset.domain_shard_proposal),
+            C::DOMAIN_SHARD_PROPOSAL,
+        );
+        assert_eq!(
+            domain_type(preset.domain_shard_committee),
+            C::DOMAIN_SHARD_COMMITTEE,
+        );
+        assert_eq!(
+            domain_type(preset.domain_light_client),
+            C::DOMAIN_LIGHT_CLIENT,
+        );
+        assert_eq!(
+            domain_type(preset.domain_custody_bit_slashing),
+            C::DOMAIN_CUSTODY_BIT_SLASHING,
+        );
+        assert_eq!(
+            domain_type(preset.domain_light_selection_proof),
+            C::DOMAIN_LIGHT_SELECTION_PROOF,
+        );
+        assert_eq!(
+            domain_type(preset.domain_light_aggregate_and_proof),
+            C::DOMAIN_LIGHT_AGGREGATE_AND_PROOF,
+        );
+        assert_eq!(preset.randao_penalty_epochs, C::RANDAO_PENALTY_EPOCHS);
+        assert_eq!(
+            preset.early_derived_secret_penalty_max_future_epochs,
+            C::EarlyDerivedSecretPenaltyMaxFutureEpochs::U64,
+        );
+        assert_eq!(
+            preset.epochs_per_custody_period,
+            C::EPOCHS_PER_CUSTODY_PERIOD
+        );
+        assert_eq!(
+            preset.custody_period_to_randao_padding,
+            C::CUSTODY_PERIOD_TO_RANDAO_PADDING,
+        );
+        assert_eq!(
+            preset.max_chunk_challenge_delay,
+            C::MAX_CHUNK_CHALLENGE_DELAY
+        );
+        // 2**256 - 189
+        assert_eq!(
+            U256::from_dec_str(&preset.custody_prime),
+            Ok(U256::MAX - 188),
+        );
+        assert_eq!(preset.custody_secrets, CUSTODY_SECRETS);
+        assert_eq!(preset.bytes_per_custody_atom, BYTES_PER_CUSTODY_ATOM);
+        assert_eq!(
+            preset.custody_probability_exponent,
+            CUSTODY_PROBABILITY_EXPONENT,
+        );
+        assert_eq!(preset.max_custody_key_reveals, C::MaxCustodyKeyReveals::U64);
+        assert_eq!(
+            preset.max_early_derived_secret_reveals,
+            C::MaxEarlyDerivedSecretReveals::U64,
+        );
+        assert_eq!(
+            preset.max_custody_chunk_challenges,
+            C::MaxCustodyChunkChallenges::U64,
+        );
+        assert_eq!(
+            preset.max_custody_chunk_challenge_resp,
+            C::MaxCustodyChunkChallengeResponses::U64,
+        );
+        assert_eq!(preset.max_custody_slashings, C::MaxCustodySlashings::U64);
+        assert_eq!(
+            preset.early_derived_secret_reveal_slot_reward_multiple,
+            C::EARLY_DERIVED_SECRET_REVEAL_SLOT_REWARD_MULTIPLE,
+        );
+        assert_eq!(preset.minor_reward_quotient, C::MINOR_REWARD_QUOTIENT);
+    }
+
+    #[test]
+    fn test_mainnet_phase1_preset() {
+        assert_matches_phase1_preset::<MainnetConfig>(include_str!(
+            "../configs/mainnet/phase1.yaml"
+        ));
+    }
+
+    #[test]
+    fn test_minimal_phase1_preset() {
+        assert_matches_phase1_preset::<MinimalConfig>(include_str!(
+            "../configs/minimal/phase1.yaml"
+        ));
+    }
+
+    // Medalla and Spadina are phase 0 testnets that use the mainnet preset.
+    #[test]
+    fn test_medalla_phase1_preset() {
+        assert_matches_phase1_preset::<MedallaConfig>(include_str!(
+            "../configs/mainnet/phase1.yaml"
+        ));
+    }
+
+    #[test]
+    fn test_spadina_phase1_preset() {
+        assert_matches_phase1_preset::<SpadinaConfig>(include_str!(
+            "../configs/mainnet/phase1.yaml"
+        ));
+    }
+}
@@ -3,6 +3,9 @@ use typenum::{U32, U4, U64};
 use crate::primitives::{Epoch, Slot};
 
 pub const BASE_REWARDS_PER_EPOCH: u64 = 4;
+pub const BYTES_PER_CUSTODY_ATOM: usize = 32;
+pub const CUSTODY_PROBABILITY_EXPONENT: u64 = 10;
+pub const CUSTODY_SECRETS: usize = 3;
 pub const FAR_FUTURE_EPOCH: u64 = u64::MAX;
 pub const GENESIS_EPOCH: Epoch = 0;
 pub const GENESIS_SLOT: Slot = 0;
@@ -1,10 +1,8 @@
-//temporary Lighthouse SSZ and hashing implementation
 use anyhow::Result;
 use bls::{PublicKeyBytes, SignatureBytes};
 use serde::{Deserialize, Serialize};
 use ssz_new::types::{BitList, BitVector, ByteList, ByteVector, FixedVector, VariableList};
-use ssz_new_derive::{SszDecode, SszEncode};
-use tree_hash_derive::TreeHash;
+use ssz_new_derive::{SszDecode, SszEncode, SszHash};
 
 use crate::{
     config::Config,
@@ -14,14 +12,14 @@ use crate::{
     },
 };
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct AggregateAndProof<C: Config> {
     pub aggregator_index: ValidatorIndex,
     pub aggregate: Attestation<C>,
     pub selection_proof: SignatureBytes,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct Attestation<C: Config> {
     pub aggregation_bits: BitList<C::MaxValidatorsPerCommittee>,
     pub data: AttestationData,
@@ -55,7 +53,7 @@ impl<C: Config> Attestation<C> {
     Serialize,
     SszEncode,
     SszDecode,
-    TreeHash,
+    SszHash,
 )]
 pub struct AttestationData {
     pub slot: Slot,
@@ -69,14 +67,14 @@ pub struct AttestationData {
     pub shard_transition_root: H256,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct AttesterSlashing<C: Config> {
     pub attestation_1: IndexedAttestation<C>,
     pub attestation_2: IndexedAttestation<C>,
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct BeaconBlock<C: Config> {
     pub slot: Slot,
@@ -85,7 +83,7 @@ pub struct BeaconBlock<C: Config> {
     pub state_root: H256,
     pub body: BeaconBlockBody<C>,
 }
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct CustodyChunkChallenge<C: Config> {
     pub responder_index: ValidatorIndex,
     pub shard_transition: ShardTransition<C>,
@@ -93,7 +91,7 @@ pub struct CustodyChunkChallenge<C: Config> {
     pub data_index: u64,
     pub chunk_index: u64,
 }
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct CustodyChunkResponse<C: Config> {
     pub challenge_index: u64,
     pub chunk_index: u64,
@@ -102,7 +100,7 @@ pub struct CustodyChunkResponse<C: Config> {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct CustodyChunkChallengeRecord {
     pub challenge_index: u64,
@@ -114,7 +112,7 @@ pub struct CustodyChunkChallengeRecord {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct CustodyKeyReveal {
     pub revealer_index: ValidatorIndex,
@@ -122,7 +120,7 @@ pub struct CustodyKeyReveal {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct EarlyDerivedSecretReveal {
     pub revealed_index: ValidatorIndex,
@@ -132,14 +130,14 @@ pub struct EarlyDerivedSecretReveal {
     pub mask: H256,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct FullAttestation<C: Config> {
     pub aggregation_bits: BitList<C::MaxValidatorsPerCommittee>,
     pub data: FullAttestationData<C>,
     pub signature: SignatureBytes,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct FullAttestationData<C: Config> {
     pub slot: Slot,
     pub index: CommitteeIndex,
@@ -150,19 +148,19 @@ pub struct FullAttestationData<C: Config> {
     pub shard_transition: ShardTransition<C>,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct SignedCustodySlashing<C: Config> {
     pub message: CustodySlashing<C>,
     pub signature: SignatureBytes,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct SignedLightAggregateAndProof<C: Config> {
     pub message: LightAggregateAndProof<C>,
     pub signature: SignatureBytes,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct CustodySlashing<C: Config> {
     pub data_index: u64,
     pub malefactor_index: ValidatorIndex,
@@ -174,7 +172,7 @@ pub struct CustodySlashing<C: Config> {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct ShardState {
     pub slot: Slot,
@@ -183,7 +181,7 @@ pub struct ShardState {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct ShardTransition<C: Config> {
     pub start_slot: Slot,
@@ -194,14 +192,14 @@ pub struct ShardTransition<C: Config> {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct CompactCommittee<C: Config> {
     pub pubkeys: VariableList<PublicKeyBytes, C::MaxValidatorsPerCommittee>,
     pub compact_validators: VariableList<u64, C::MaxValidatorsPerCommittee>,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct ShardBlock<C: Config> {
     pub shard_parent_root: H256,
     pub beacon_parent_root: H256,
@@ -225,7 +223,7 @@ impl<C: Config> Default for ShardBlock<C> {
 }
 
 #[derive(
-    Clone, PartialEq, Debug, Default, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Debug, Default, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct SignedShardBlock<C: Config> {
     pub message: ShardBlock<C>,
@@ -233,7 +231,7 @@ pub struct SignedShardBlock<C: Config> {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct ShardBlockHeader {
     pub shard_parent_root: H256,
@@ -245,7 +243,7 @@ pub struct ShardBlockHeader {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct LightClientVote<C: Config> {
     pub data: LightClientVoteData,
@@ -254,7 +252,7 @@ pub struct LightClientVote<C: Config> {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct LightAggregateAndProof<C: Config> {
     pub aggregator_index: ValidatorIndex,
@@ -263,7 +261,7 @@ pub struct LightAggregateAndProof<C: Config> {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct LightClientVoteData {
     pub slot: Slot,
@@ -271,7 +269,7 @@ pub struct LightClientVoteData {
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct BeaconBlockBody<C: Config> {
     pub randao_reveal: SignatureBytes,
@@ -306,7 +304,7 @@ pub struct BeaconBlockBody<C: Config> {
     Serialize,
     SszEncode,
     SszDecode,
-    TreeHash,
+    SszHash,
 )]
 pub struct BeaconBlockHeader {
     pub slot: Slot,
@@ -328,14 +326,14 @@ pub struct BeaconBlockHeader {
     Serialize,
     SszEncode,
     SszDecode,
-    TreeHash,
+    SszHash,
 )]
 pub struct Checkpoint {
     pub epoch: Epoch,
     pub root: H256,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct Deposit {
     pub proof: DepositProof,
     pub data: DepositData,
@@ -352,7 +350,7 @@ pub struct Deposit {
     Serialize,
     SszEncode,
     SszDecode,
-    TreeHash,
+    SszHash,
 )]
 pub struct DepositData {
     pub pubkey: PublicKeyBytes,
@@ -362,7 +360,7 @@ pub struct DepositData {
 }
 
 #[derive(
-    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct DepositMessage {
     pub pubkey: PublicKeyBytes,
@@ -382,7 +380,7 @@ pub struct DepositMessage {
     Serialize,
     SszEncode,
     SszDecode,
-    TreeHash,
+    SszHash,
 )]
 pub struct Eth1Data {
     pub deposit_root: H256,
@@ -401,7 +399,7 @@ pub struct Eth1Data {
     Serialize,
     SszEncode,
     SszDecode,
-    TreeHash,
+    SszHash,
 )]
 pub struct Fork {
     pub previous_version: Version,
@@ -410,21 +408,21 @@ pub struct Fork {
 }
 
 #[derive(
-    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct ForkData {
     pub current_version: Version,
     pub genesis_validators_root: H256,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct HistoricalBatch<C: Config> {
     pub block_roots: FixedVector<H256, C::SlotsPerHistoricalRoot>,
     pub state_roots: FixedVector<H256, C::SlotsPerHistoricalRoot>,
 }
 
 #[derive(
-    Clone, PartialEq, Eq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Eq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct IndexedAttestation<C: Config> {
     pub attesting_indices: VariableList<u64, C::MaxValidatorsPerCommittee>,
@@ -432,7 +430,7 @@ pub struct IndexedAttestation<C: Config> {
     pub signature: AggregateSignatureBytes,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct PendingAttestation<C: Config> {
     pub aggregation_bits: BitList<C::MaxValidatorsPerCommittee>,
     pub data: AttestationData,
@@ -443,21 +441,21 @@ pub struct PendingAttestation<C: Config> {
 }
 
 #[derive(
-    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct ProposerSlashing {
     pub signed_header_1: SignedBeaconBlockHeader,
     pub signed_header_2: SignedBeaconBlockHeader,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash)]
 pub struct SignedAggregateAndProof<C: Config> {
     pub message: AggregateAndProof<C>,
     pub signature: SignatureBytes,
 }
 
 #[derive(
-    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, PartialEq, Default, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct SignedBeaconBlock<C: Config> {
     pub message: BeaconBlock<C>,
@@ -465,7 +463,7 @@ pub struct SignedBeaconBlock<C: Config> {
 }
 
 #[derive(
-    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct SignedBeaconBlockHeader {
     pub message: BeaconBlockHeader,
@@ -473,7 +471,7 @@ pub struct SignedBeaconBlockHeader {
 }
 
 #[derive(
-    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct SignedVoluntaryExit {
     pub message: VoluntaryExit,
@@ -491,7 +489,7 @@ pub struct SignedVoluntaryExit {
     Serialize,
     SszEncode,
     SszDecode,
-    TreeHash,
+    SszHash,
 )]
 pub struct SigningData {
     pub object_root: H256,
@@ -509,7 +507,7 @@ pub struct SigningData {
     Serialize,
     SszEncode,
     SszDecode,
-    TreeHash,
+    SszHash,
 )]
 pub struct Validator {
     pub pubkey: PublicKeyBytes,
@@ -526,7 +524,7 @@ pub struct Validator {
 }
 
 #[derive(
-    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, TreeHash,
+    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, SszEncode, SszDecode, SszHash,
 )]
 pub struct VoluntaryExit {
     pub epoch: Epoch,
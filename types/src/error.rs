@@ -0,0 +1,12 @@
+use serde_yaml::Value;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{name} does not match the compiled preset ({actual:?} != {expected})")]
+    PresetMismatch {
+        name: &'static str,
+        expected: u64,
+        actual: Value,
+    },
+}
@@ -1,20 +1,22 @@
 pub mod arc_ext;
 pub mod beacon_state;
+pub mod chain_spec;
 pub mod config;
 pub mod consts;
 pub mod containers;
 pub mod fixed_vector;
 pub mod primitives;
 
+mod error;
+
 #[cfg(test)]
 mod spec_tests {
     use core::fmt::Debug;
 
     use serde::{de::DeserializeOwned, Deserialize};
     use spec_test_utils::Case;
-    use ssz_new::{SszDecode, SszEncode};
+    use ssz_new::{SszDecode, SszEncode, SszHash};
     use test_generator::test_resources;
-    use tree_hash::TreeHash;
 
     use crate::{
         config::{MainnetConfig, MinimalConfig},
@@ -328,7 +330,7 @@ mod spec_tests {
 
     fn run_case<D>(case: Case)
     where
-        D: PartialEq + Debug + DeserializeOwned + SszDecode + SszEncode + TreeHash,
+        D: PartialEq + Debug + DeserializeOwned + SszDecode + SszEncode + SszHash,
     {
         let ssz_bytes = case.bytes("serialized.ssz");
         let yaml_value = case.yaml("value");
@@ -3,8 +3,7 @@ use derive_more::{DebugCustom, From, Into};
 use ethereum_types::H32;
 use serde::{Deserialize, Serialize};
 use ssz_new::types::FixedVector;
-use ssz_new_derive::{SszDecode, SszEncode};
-use tree_hash_derive::TreeHash;
+use ssz_new_derive::{SszDecode, SszEncode, SszHash};
 use typenum::{Sum, U1};
 
 use crate::consts::DepositContractTreeDepth;
@@ -48,7 +47,7 @@ pub type Root = H256;
     Serialize,
     SszEncode,
     SszDecode,
-    TreeHash,
+    SszHash,
 )]
 #[debug(fmt = "{:?}", "H32(*bytes)")]
 // Specification tests represent `Version` with strings of the form "0x…". `H32` has the
@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use ethereum_types::H256;
+use hashing::{concatenate_and_hash, hash_from_u64, ZERO_HASHES};
+
+use crate::{SszHash, BYTES_PER_CHUNK};
+
+// Pads `bytes` with zeros to fill at least `minimum_chunk_count` chunks and merkleizes them.
+#[must_use]
+pub fn merkle_root(bytes: &[u8], minimum_chunk_count: usize) -> H256 {
+    let chunk_count = chunk_count(bytes.len()).max(minimum_chunk_count).max(1);
+    let depth = chunk_count.next_power_of_two().trailing_zeros() as usize;
+
+    let mut layer = bytes
+        .chunks(BYTES_PER_CHUNK)
+        .map(|bytes| {
+            let mut chunk = H256::zero();
+            chunk[..bytes.len()].copy_from_slice(bytes);
+            chunk
+        })
+        .collect::<Vec<_>>();
+
+    // Subtrees past the end of `bytes` consist entirely of padding. Their roots are zero hashes.
+    for zero_hash in ZERO_HASHES.iter().take(depth) {
+        layer = layer
+            .chunks(2)
+            .map(|pair| concatenate_and_hash(pair[0], pair.get(1).unwrap_or(zero_hash)))
+            .collect();
+    }
+
+    layer.first().copied().unwrap_or(ZERO_HASHES[depth])
+}
+
+#[must_use]
+pub fn mix_in_length(root: H256, length: usize) -> H256 {
+    concatenate_and_hash(root, hash_from_u64(length as u64))
+}
+
+pub fn chunk_count(byte_count: usize) -> usize {
+    byte_count.saturating_add(BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK
+}
+
+// Number of chunks needed to hold `limit` elements of type `T`.
+pub fn chunk_limit<T: SszHash>(limit: usize) -> usize {
+    let packing_factor = T::tree_hash_packing_factor();
+    limit.saturating_add(packing_factor - 1) / packing_factor
+}
+
+pub fn packed_encoding<'values, T: SszHash + 'values>(
+    values: impl IntoIterator<Item = &'values T>,
+) -> Vec<u8> {
+    values
+        .into_iter()
+        .flat_map(SszHash::tree_hash_packed_encoding)
+        .collect()
+}
+
+macro_rules! hash_for_uintn {
+    ($($type: ty),*) => { $(
+        impl SszHash for $type {
+            fn tree_hash_packing_factor() -> usize {
+                BYTES_PER_CHUNK / core::mem::size_of::<Self>()
+            }
+
+            fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+                self.to_le_bytes().to_vec()
+            }
+
+            fn tree_hash_root(&self) -> H256 {
+                merkle_root(&self.to_le_bytes(), 0)
+            }
+        }
+    )* };
+}
+
+hash_for_uintn!(u8, u16, u32, u64);
+
+macro_rules! hash_for_u8_array {
+    ($($size: expr),*) => { $(
+        impl SszHash for [u8; $size] {
+            fn tree_hash_root(&self) -> H256 {
+                merkle_root(self, 0)
+            }
+        }
+    )* };
+}
+
+hash_for_u8_array!(4, 48, 96);
+
+impl SszHash for bool {
+    fn tree_hash_packing_factor() -> usize {
+        BYTES_PER_CHUNK
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        vec![u8::from(*self)]
+    }
+
+    fn tree_hash_root(&self) -> H256 {
+        merkle_root(&[u8::from(*self)], 0)
+    }
+}
+
+impl<T: SszHash> SszHash for Arc<T> {
+    fn tree_hash_packing_factor() -> usize {
+        T::tree_hash_packing_factor()
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        T::tree_hash_packed_encoding(self)
+    }
+
+    fn tree_hash_root(&self) -> H256 {
+        T::tree_hash_root(self)
+    }
+}
+
+impl SszHash for H256 {
+    fn tree_hash_root(&self) -> H256 {
+        *self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(bytes: &[u8]) -> H256 {
+        let mut chunk = H256::zero();
+        chunk[..bytes.len()].copy_from_slice(bytes);
+        chunk
+    }
+
+    #[test]
+    fn merkle_root_of_nothing_is_a_zero_chunk() {
+        assert_eq!(merkle_root(&[], 0), H256::zero());
+        assert_eq!(merkle_root(&[], 1), H256::zero());
+    }
+
+    #[test]
+    fn merkle_root_pads_to_minimum_chunk_count() {
+        assert_eq!(merkle_root(&[], 4), ZERO_HASHES[2]);
+        assert_eq!(
+            merkle_root(&[1], 3),
+            concatenate_and_hash(
+                concatenate_and_hash(chunk(&[1]), H256::zero()),
+                ZERO_HASHES[1]
+            ),
+        );
+        assert_eq!(merkle_root(&[0; 40], 0), ZERO_HASHES[1]);
+        assert_eq!(merkle_root(&[], 1 << 40), ZERO_HASHES[40]);
+    }
+
+    #[test]
+    fn mix_in_length_hashes_length_as_chunk() {
+        assert_eq!(
+            mix_in_length(H256::zero(), 258),
+            concatenate_and_hash(H256::zero(), chunk(&[2, 1])),
+        );
+    }
+
+    #[test]
+    fn basic_values_are_packed() {
+        assert_eq!(u16::tree_hash_packing_factor(), 16);
+        assert_eq!(u64::tree_hash_packing_factor(), 4);
+        assert_eq!(bool::tree_hash_packing_factor(), 32);
+        assert_eq!(H256::tree_hash_packing_factor(), 1);
+
+        assert_eq!(0x0102_u16.tree_hash_root(), chunk(&[2, 1]));
+        assert_eq!(true.tree_hash_root(), chunk(&[1]));
+        assert_eq!(
+            packed_encoding(&[1_u64, 2]),
+            [1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0],
+        );
+    }
+
+    #[test]
+    fn byte_arrays_are_vectors() {
+        assert_eq!([1, 2, 3, 4].tree_hash_root(), chunk(&[1, 2, 3, 4]));
+        assert_eq!(
+            [1; 48].tree_hash_root(),
+            concatenate_and_hash(H256::repeat_byte(1), chunk(&[1; 16])),
+        );
+    }
+
+    #[test]
+    fn h256_is_its_own_root() {
+        let hash = H256::repeat_byte(7);
+        assert_eq!(hash.tree_hash_root(), hash);
+        assert_eq!(hash.tree_hash_packed_encoding(), hash.as_bytes());
+    }
+}
@@ -5,15 +5,20 @@ use thiserror::Error;
 #[cfg(test)]
 use ssz_new_derive as _;
 
+pub use ethereum_types::H256;
+pub use hash::{merkle_root, mix_in_length};
 pub use utils::{encode_items_from_parts, Decoder};
 
+pub mod merkleization;
 pub mod types;
 
 mod decode;
 mod encode;
+mod hash;
 mod utils;
 
 pub const BYTES_PER_LENGTH_OFFSET: usize = 4;
+pub const BYTES_PER_CHUNK: usize = 32;
 
 pub trait SszEncode {
     fn as_ssz_bytes(&self) -> Vec<u8>;
@@ -32,6 +37,20 @@ pub trait SszDecode: Sized {
     }
 }
 
+pub trait SszHash {
+    // Basic values are packed together into chunks when they are elements of vectors or lists.
+    // Composite values are represented by their roots and take up a whole chunk.
+    fn tree_hash_packing_factor() -> usize {
+        1
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        self.tree_hash_root().as_bytes().to_vec()
+    }
+
+    fn tree_hash_root(&self) -> H256;
+}
+
 #[derive(Debug, Error)]
 #[error("{self:?}")]
 pub enum SszDecodeError {
@@ -0,0 +1,381 @@
+//! Navigation of the Merkle trees SSZ values are hashed into.
+//!
+//! Paths are defined in the
+//! [specification](https://github.com/ethereum/eth2.0-specs/blob/dev/ssz/merkle-proofs.md).
+//! `Merkleized` is implemented for containers by `#[derive(SszHash)]`.
+
+use std::sync::Arc;
+
+use ethereum_types::H256;
+use thiserror::Error;
+use typenum::Unsigned;
+
+use crate::{
+    types::{
+        length::{Fixed, Variable},
+        Bitfield, Bytefield, FixedVector, VariableList,
+    },
+    SszHash,
+};
+
+pub type GeneralizedIndex = u64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PathElement {
+    Field(&'static str),
+    Index(usize),
+    Length,
+}
+
+#[derive(Debug, Error)]
+pub enum PathError {
+    #[error("generalized index does not fit in 64 bits")]
+    GeneralizedIndexTooLarge,
+    #[error("index is out of bounds")]
+    IndexOutOfBounds,
+    #[error("path does not lead to a node in the Merkle tree")]
+    PathInvalid,
+}
+
+/// SSZ values whose Merkle trees can be navigated with paths.
+///
+/// The provided methods treat values as leaves. Paths cannot go inside them.
+pub trait Merkleized {
+    fn generalized_index(path: &[PathElement]) -> Result<GeneralizedIndex, PathError>
+    where
+        Self: Sized,
+    {
+        if path.is_empty() {
+            Ok(1)
+        } else {
+            Err(PathError::PathInvalid)
+        }
+    }
+
+    fn root(&self) -> H256;
+
+    // The leaves of the tree `self` is merkleized into, without padding or the length mix-in.
+    fn chunks(&self) -> Vec<H256> {
+        vec![self.root()]
+    }
+
+    fn depth(&self) -> usize {
+        0
+    }
+
+    // Lists are the only values that have their length mixed into their roots.
+    fn length(&self) -> Option<usize> {
+        None
+    }
+
+    // Returns the position of the chunk that `element` points to and the value the chunk is the
+    // root of, unless the chunk contains packed basic values.
+    fn child(&self, _element: PathElement) -> Result<(usize, Option<&dyn Merkleized>), PathError> {
+        Err(PathError::PathInvalid)
+    }
+}
+
+pub fn concat_generalized_indices(
+    outer: GeneralizedIndex,
+    inner: GeneralizedIndex,
+) -> Result<GeneralizedIndex, PathError> {
+    let inner_depth = floor_log2(inner);
+
+    if floor_log2(outer) + inner_depth >= 64 {
+        return Err(PathError::GeneralizedIndexTooLarge);
+    }
+
+    Ok(outer << inner_depth | inner ^ (1 << inner_depth))
+}
+
+#[must_use]
+pub fn floor_log2(generalized_index: GeneralizedIndex) -> usize {
+    63 - generalized_index.leading_zeros() as usize
+}
+
+#[must_use]
+pub fn chunk_generalized_index(depth: usize, is_list: bool, position: usize) -> GeneralizedIndex {
+    let root = if is_list { 2 } else { 1 };
+    root << depth | position as GeneralizedIndex
+}
+
+#[must_use]
+pub fn packing_factor<T: SszHash>() -> usize {
+    T::tree_hash_packing_factor()
+}
+
+#[must_use]
+pub fn chunk_count(length: usize, packing_factor: usize) -> usize {
+    length.saturating_add(packing_factor - 1) / packing_factor
+}
+
+/// Returns the depth of a tree that can hold `limit` elements of type `T`.
+#[must_use]
+pub fn depth<T: SszHash>(limit: usize) -> usize {
+    let chunk_limit = chunk_count(limit, packing_factor::<T>());
+    chunk_limit.max(1).next_power_of_two().trailing_zeros() as usize
+}
+
+/// Returns the leaf with index `chunk_index` in the tree of a homogeneous sequence.
+#[must_use]
+pub fn chunk<T: SszHash>(values: &[T], chunk_index: usize) -> H256 {
+    if packing_factor::<T>() == 1 {
+        return values[chunk_index].tree_hash_root();
+    }
+
+    let start = chunk_index * packing_factor::<T>();
+    let end = (start + packing_factor::<T>()).min(values.len());
+    let mut chunk = H256::zero();
+    let mut offset = 0;
+
+    for value in &values[start..end] {
+        let bytes = value.tree_hash_packed_encoding();
+        chunk[offset..offset + bytes.len()].copy_from_slice(bytes.as_slice());
+        offset += bytes.len();
+    }
+
+    chunk
+}
+
+/// Returns all leaves in the tree of a homogeneous sequence, excluding padding.
+#[must_use]
+pub fn chunks<T: SszHash>(values: &[T]) -> Vec<H256> {
+    (0..chunk_count(values.len(), packing_factor::<T>()))
+        .map(|chunk_index| chunk(values, chunk_index))
+        .collect()
+}
+
+#[must_use]
+pub fn container_depth(field_count: usize) -> usize {
+    field_count.next_power_of_two().trailing_zeros() as usize
+}
+
+// Called by code generated by `#[derive(SszHash)]`.
+// The closure accessing the field lets its type be inferred.
+#[doc(hidden)]
+pub fn field_generalized_index<S, T: Merkleized>(
+    field_count: usize,
+    position: usize,
+    _field: fn(&S) -> &T,
+    path: &[PathElement],
+) -> Result<GeneralizedIndex, PathError> {
+    concat_generalized_indices(
+        chunk_generalized_index(container_depth(field_count), false, position),
+        T::generalized_index(path)?,
+    )
+}
+
+fn is_packed<T: SszHash>() -> bool {
+    packing_factor::<T>() > 1
+}
+
+fn sequence_generalized_index<T: Merkleized + SszHash>(
+    limit: usize,
+    is_list: bool,
+    path: &[PathElement],
+) -> Result<GeneralizedIndex, PathError> {
+    let (element, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Ok(1),
+    };
+
+    match *element {
+        PathElement::Index(index) => {
+            if index >= limit {
+                return Err(PathError::IndexOutOfBounds);
+            }
+
+            let position = index / packing_factor::<T>();
+
+            let inner = if is_packed::<T>() {
+                if !rest.is_empty() {
+                    return Err(PathError::PathInvalid);
+                }
+                1
+            } else {
+                T::generalized_index(rest)?
+            };
+
+            concat_generalized_indices(
+                chunk_generalized_index(depth::<T>(limit), is_list, position),
+                inner,
+            )
+        }
+        PathElement::Length if is_list && rest.is_empty() => Ok(3),
+        _ => Err(PathError::PathInvalid),
+    }
+}
+
+fn sequence_child<T: Merkleized + SszHash>(
+    values: &[T],
+    element: PathElement,
+) -> Result<(usize, Option<&dyn Merkleized>), PathError> {
+    match element {
+        PathElement::Index(index) => {
+            let value = values.get(index).ok_or(PathError::IndexOutOfBounds)?;
+            let position = index / packing_factor::<T>();
+
+            if is_packed::<T>() {
+                Ok((position, None))
+            } else {
+                Ok((position, Some(value)))
+            }
+        }
+        _ => Err(PathError::PathInvalid),
+    }
+}
+
+macro_rules! merkleized_for_leaves {
+    ($($type: ty),*) => { $(
+        impl Merkleized for $type {
+            fn root(&self) -> H256 {
+                self.tree_hash_root()
+            }
+        }
+    )* };
+}
+
+merkleized_for_leaves!(bool, u8, u16, u32, u64, H256, [u8; 4], [u8; 48], [u8; 96]);
+
+impl<N: Unsigned + Clone> Merkleized for Bitfield<Variable<N>> {
+    fn root(&self) -> H256 {
+        self.tree_hash_root()
+    }
+}
+
+impl<N: Unsigned + Clone> Merkleized for Bitfield<Fixed<N>> {
+    fn root(&self) -> H256 {
+        self.tree_hash_root()
+    }
+}
+
+impl<N: Unsigned + Clone> Merkleized for Bytefield<Variable<N>> {
+    fn root(&self) -> H256 {
+        self.tree_hash_root()
+    }
+}
+
+impl<N: Unsigned + Clone> Merkleized for Bytefield<Fixed<N>> {
+    fn root(&self) -> H256 {
+        self.tree_hash_root()
+    }
+}
+
+impl<T: Merkleized + SszHash, N: Unsigned> Merkleized for VariableList<T, N> {
+    fn generalized_index(path: &[PathElement]) -> Result<GeneralizedIndex, PathError> {
+        sequence_generalized_index::<T>(N::USIZE, true, path)
+    }
+
+    fn root(&self) -> H256 {
+        self.tree_hash_root()
+    }
+
+    fn chunks(&self) -> Vec<H256> {
+        chunks(self)
+    }
+
+    fn depth(&self) -> usize {
+        depth::<T>(N::USIZE)
+    }
+
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn child(&self, element: PathElement) -> Result<(usize, Option<&dyn Merkleized>), PathError> {
+        sequence_child(self, element)
+    }
+}
+
+impl<T: Merkleized + SszHash, N: Unsigned> Merkleized for FixedVector<T, N> {
+    fn generalized_index(path: &[PathElement]) -> Result<GeneralizedIndex, PathError> {
+        sequence_generalized_index::<T>(N::USIZE, false, path)
+    }
+
+    fn root(&self) -> H256 {
+        self.tree_hash_root()
+    }
+
+    fn chunks(&self) -> Vec<H256> {
+        chunks(self)
+    }
+
+    fn depth(&self) -> usize {
+        depth::<T>(N::USIZE)
+    }
+
+    fn child(&self, element: PathElement) -> Result<(usize, Option<&dyn Merkleized>), PathError> {
+        sequence_child(self, element)
+    }
+}
+
+impl<T: Merkleized> Merkleized for Arc<T> {
+    fn generalized_index(path: &[PathElement]) -> Result<GeneralizedIndex, PathError> {
+        T::generalized_index(path)
+    }
+
+    fn root(&self) -> H256 {
+        self.as_ref().root()
+    }
+
+    fn chunks(&self) -> Vec<H256> {
+        self.as_ref().chunks()
+    }
+
+    fn depth(&self) -> usize {
+        self.as_ref().depth()
+    }
+
+    fn length(&self) -> Option<usize> {
+        self.as_ref().length()
+    }
+
+    fn child(&self, element: PathElement) -> Result<(usize, Option<&dyn Merkleized>), PathError> {
+        self.as_ref().child(element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typenum::U8;
+
+    use super::*;
+
+    use PathElement::{Index, Length};
+
+    #[test]
+    fn sequence_generalized_indices() {
+        assert_eq!(
+            VariableList::<u64, U8>::generalized_index(&[Length]).expect("path is valid"),
+            3,
+        );
+        // Four `u64`s fit in a chunk. 8 `u64`s take up 2 chunks.
+        assert_eq!(
+            VariableList::<u64, U8>::generalized_index(&[Index(5)]).expect("path is valid"),
+            0b101,
+        );
+        assert_eq!(
+            FixedVector::<H256, U8>::generalized_index(&[Index(5)]).expect("path is valid"),
+            0b1101,
+        );
+        assert!(VariableList::<u64, U8>::generalized_index(&[Index(8)]).is_err());
+        assert!(FixedVector::<u64, U8>::generalized_index(&[Length]).is_err());
+        assert!(VariableList::<u64, U8>::generalized_index(&[Index(0), Index(0)]).is_err());
+    }
+
+    #[test]
+    fn leaves_cannot_be_entered() {
+        assert_eq!(u64::generalized_index(&[]).expect("path is valid"), 1);
+        assert!(u64::generalized_index(&[Index(0)]).is_err());
+        assert!(H256::zero().child(Index(0)).is_err());
+    }
+
+    #[test]
+    fn generalized_indices_must_fit_in_64_bits() {
+        assert_eq!(
+            concat_generalized_indices(0b10, 0b101).expect("index fits"),
+            0b1001,
+        );
+        assert!(concat_generalized_indices(1 << 40, 1 << 30).is_err());
+    }
+}
@@ -1,10 +1,265 @@
-use ssz_types::{
-    length::{Fixed, Variable},
-    Bitfield,
-};
+use core::marker::PhantomData;
+
+use ethereum_types::H256;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use typenum::Unsigned;
 
-use crate::{SszDecode, SszDecodeError, SszEncode};
+use crate::{
+    hash,
+    types::{
+        length::{Fixed, Variable},
+        Error,
+    },
+    SszDecode, SszDecodeError, SszEncode, SszHash, BYTES_PER_CHUNK,
+};
+
+pub type BitList<N> = Bitfield<Variable<N>>;
+pub type BitVector<N> = Bitfield<Fixed<N>>;
+
+// Bits are stored in little-endian order. Bits past `len` are always zero.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Bitfield<T> {
+    bytes: Vec<u8>,
+    len: usize,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Bitfield<T> {
+    fn with_len(len: usize) -> Self {
+        Self {
+            bytes: vec![0; storage_len(len)],
+            len,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn get(&self, i: usize) -> Result<bool, Error> {
+        if i < self.len {
+            Ok(self.bytes[i / 8] & (1 << (i % 8)) != 0)
+        } else {
+            Err(Error::OutOfBounds { i, len: self.len })
+        }
+    }
+
+    pub fn set(&mut self, i: usize, value: bool) -> Result<(), Error> {
+        if i >= self.len {
+            return Err(Error::OutOfBounds { i, len: self.len });
+        }
+        if value {
+            self.bytes[i / 8] |= 1 << (i % 8);
+        } else {
+            self.bytes[i / 8] &= !(1 << (i % 8));
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.bytes.iter().all(|byte| *byte == 0)
+    }
+
+    #[must_use]
+    pub fn num_set_bits(&self) -> usize {
+        self.bytes
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    #[must_use]
+    pub fn highest_set_bit(&self) -> Option<usize> {
+        self.bytes
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, byte)| **byte != 0)
+            .map(|(index, byte)| index * 8 + 7 - byte.leading_zeros() as usize)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |i| self.bytes[i / 8] & (1 << (i % 8)) != 0)
+    }
+
+    // The result is as long as the longer operand.
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        let (mut result, shorter) = if self.len >= other.len {
+            (self.clone_bits(), other)
+        } else {
+            (other.clone_bits(), self)
+        };
+        for (byte, other_byte) in result.bytes.iter_mut().zip(&shorter.bytes) {
+            *byte |= other_byte;
+        }
+        result
+    }
+
+    // The result is as long as `self`.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut result = self.clone_bits();
+        for (index, byte) in result.bytes.iter_mut().enumerate() {
+            *byte &= other.bytes.get(index).copied().unwrap_or_default();
+        }
+        result
+    }
+
+    // Unlike `Clone::clone`, this does not require `T: Clone`.
+    fn clone_bits(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            len: self.len,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<N: Unsigned> Bitfield<Variable<N>> {
+    pub fn with_capacity(len: usize) -> Result<Self, Error> {
+        if len <= N::USIZE {
+            Ok(Self::with_len(len))
+        } else {
+            Err(Error::OutOfBounds {
+                i: len,
+                len: N::USIZE,
+            })
+        }
+    }
+
+    #[must_use]
+    pub fn max_len() -> usize {
+        N::USIZE
+    }
+
+    // Appends the length delimiter bit used by the serialization of bitlists.
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = self.bytes;
+        bytes.resize(self.len / 8 + 1, 0);
+        bytes[self.len / 8] |= 1 << (self.len % 8);
+        bytes
+    }
+
+    pub fn from_bytes(mut bytes: Vec<u8>) -> Result<Self, Error> {
+        let last_byte = match bytes.last() {
+            Some(byte) if *byte != 0 => *byte,
+            _ => return Err(Error::MissingLengthInformation),
+        };
+
+        let len = (bytes.len() - 1) * 8 + 7 - last_byte.leading_zeros() as usize;
+
+        if len > N::USIZE {
+            return Err(Error::OutOfBounds {
+                i: len,
+                len: N::USIZE,
+            });
+        }
+
+        bytes[len / 8] &= !(1 << (len % 8));
+        bytes.truncate(storage_len(len));
+
+        Ok(Self {
+            bytes,
+            len,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<N: Unsigned> Bitfield<Fixed<N>> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_len(N::USIZE)
+    }
+
+    #[must_use]
+    pub fn capacity() -> usize {
+        N::USIZE
+    }
+
+    // Bitvectors are serialized to at least one byte even when they are empty.
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = self.bytes;
+        if bytes.is_empty() {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(mut bytes: Vec<u8>) -> Result<Self, Error> {
+        let expected = bit_len_in_bytes_len(N::USIZE);
+
+        if bytes.len() != expected {
+            return Err(Error::InvalidByteCount {
+                given: bytes.len(),
+                expected,
+            });
+        }
+
+        let excess_bits = (N::USIZE..expected * 8).any(|i| bytes[i / 8] & (1 << (i % 8)) != 0);
+
+        if excess_bits {
+            return Err(Error::ExcessBits);
+        }
+
+        bytes.truncate(storage_len(N::USIZE));
+
+        Ok(Self {
+            bytes,
+            len: N::USIZE,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<N: Unsigned> Default for Bitfield<Variable<N>> {
+    fn default() -> Self {
+        Self::with_len(0)
+    }
+}
+
+impl<N: Unsigned> Default for Bitfield<Fixed<N>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Unsigned> Serialize for Bitfield<Variable<N>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        impl_serde::serialize::serialize(&self.clone_bits().into_bytes(), serializer)
+    }
+}
+
+impl<'de, N: Unsigned> Deserialize<'de> for Bitfield<Variable<N>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = impl_serde::serialize::deserialize(deserializer)?;
+        Self::from_bytes(bytes).map_err(D::Error::custom)
+    }
+}
+
+impl<N: Unsigned> Serialize for Bitfield<Fixed<N>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        impl_serde::serialize::serialize(&self.clone_bits().into_bytes(), serializer)
+    }
+}
+
+impl<'de, N: Unsigned> Deserialize<'de> for Bitfield<Fixed<N>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = impl_serde::serialize::deserialize(deserializer)?;
+        Self::from_bytes(bytes).map_err(D::Error::custom)
+    }
+}
 
 impl<N: Unsigned + Clone> SszEncode for Bitfield<Variable<N>> {
     fn as_ssz_bytes(&self) -> Vec<u8> {
@@ -54,10 +309,32 @@ impl<N: Unsigned + Clone> SszDecode for Bitfield<Fixed<N>> {
     }
 }
 
+impl<N: Unsigned + Clone> SszHash for Bitfield<Variable<N>> {
+    fn tree_hash_root(&self) -> H256 {
+        let root = hash::merkle_root(&self.bytes, bit_chunk_limit(N::USIZE));
+        hash::mix_in_length(root, self.len())
+    }
+}
+
+impl<N: Unsigned + Clone> SszHash for Bitfield<Fixed<N>> {
+    fn tree_hash_root(&self) -> H256 {
+        hash::merkle_root(&self.bytes, bit_chunk_limit(N::USIZE))
+    }
+}
+
+fn bit_chunk_limit(bit_len: usize) -> usize {
+    let bits_per_chunk = BYTES_PER_CHUNK * 8;
+    bit_len.saturating_add(bits_per_chunk - 1) / bits_per_chunk
+}
+
 fn bit_len_in_bytes_len(bit_len: usize) -> usize {
     std::cmp::max(1, (bit_len + 7) / 8)
 }
 
+fn storage_len(bit_len: usize) -> usize {
+    bit_len.div_ceil(8)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -217,7 +494,6 @@ mod tests {
     }
 
     mod bitvector {
-        use ssz_types::BitVector;
         use typenum::{U0, U1, U16, U4, U8};
 
         use super::*;
@@ -313,6 +589,16 @@ mod tests {
             }
             assert_round_trip(&b);
         }
+
+        #[test]
+        fn tree_hash_root() {
+            let mut b = BitVector16::new();
+            b.set(1, true).expect("Test");
+            b.set(9, true).expect("Test");
+            let mut chunk = H256::zero();
+            chunk[..2].copy_from_slice(&[0b0000_0010, 0b0000_0010]);
+            assert_eq!(b.tree_hash_root(), chunk);
+        }
     }
 
     fn assert_round_trip<T: SszEncode + SszDecode + PartialEq + std::fmt::Debug>(t: &T) {
@@ -1,10 +1,118 @@
-use ssz_types::{
-    length::{Fixed, Variable},
-    Bytefield,
-};
+use core::marker::PhantomData;
+
+use ethereum_types::H256;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use typenum::Unsigned;
 
-use crate::{SszDecode, SszDecodeError, SszEncode};
+use crate::{
+    hash,
+    types::{
+        length::{Fixed, Variable},
+        Error,
+    },
+    SszDecode, SszDecodeError, SszEncode, SszHash,
+};
+
+pub type ByteList<N> = Bytefield<Variable<N>>;
+pub type ByteVector<N> = Bytefield<Fixed<N>>;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Bytefield<T> {
+    bytes: Vec<u8>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Bytefield<T> {
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+impl<N: Unsigned> Bytefield<Variable<N>> {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        if bytes.len() <= N::USIZE {
+            Ok(Self {
+                bytes,
+                phantom: PhantomData,
+            })
+        } else {
+            Err(Error::OutOfBounds {
+                i: bytes.len(),
+                len: N::USIZE,
+            })
+        }
+    }
+}
+
+impl<N: Unsigned> Bytefield<Fixed<N>> {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        if bytes.len() == N::USIZE {
+            Ok(Self {
+                bytes,
+                phantom: PhantomData,
+            })
+        } else {
+            Err(Error::InvalidByteCount {
+                given: bytes.len(),
+                expected: N::USIZE,
+            })
+        }
+    }
+}
+
+impl<N: Unsigned> Default for Bytefield<Variable<N>> {
+    fn default() -> Self {
+        Self {
+            bytes: vec![],
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<N: Unsigned> Default for Bytefield<Fixed<N>> {
+    fn default() -> Self {
+        Self {
+            bytes: vec![0; N::USIZE],
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Serialize for Bytefield<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        impl_serde::serialize::serialize(&self.bytes, serializer)
+    }
+}
+
+impl<'de, N: Unsigned> Deserialize<'de> for Bytefield<Variable<N>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = impl_serde::serialize::deserialize(deserializer)?;
+        Self::from_bytes(bytes).map_err(D::Error::custom)
+    }
+}
+
+impl<'de, N: Unsigned> Deserialize<'de> for Bytefield<Fixed<N>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = impl_serde::serialize::deserialize(deserializer)?;
+        Self::from_bytes(bytes).map_err(D::Error::custom)
+    }
+}
 
 impl<N: Unsigned + Clone> SszEncode for Bytefield<Variable<N>> {
     fn as_ssz_bytes(&self) -> Vec<u8> {
@@ -53,3 +161,59 @@ impl<N: Unsigned + Clone> SszDecode for Bytefield<Fixed<N>> {
         N::USIZE
     }
 }
+
+impl<N: Unsigned + Clone> SszHash for Bytefield<Variable<N>> {
+    fn tree_hash_root(&self) -> H256 {
+        let root = hash::merkle_root(&self.bytes, hash::chunk_count(N::USIZE));
+        hash::mix_in_length(root, self.len())
+    }
+}
+
+impl<N: Unsigned + Clone> SszHash for Bytefield<Fixed<N>> {
+    fn tree_hash_root(&self) -> H256 {
+        hash::merkle_root(&self.bytes, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typenum::U4;
+
+    use super::*;
+
+    #[test]
+    fn byte_list_rejects_too_many_bytes() {
+        assert!(<ByteList<U4>>::from_bytes(vec![1, 2, 3, 4]).is_ok());
+        assert_eq!(
+            <ByteList<U4>>::from_bytes(vec![1, 2, 3, 4, 5]),
+            Err(Error::OutOfBounds { i: 5, len: 4 }),
+        );
+    }
+
+    #[test]
+    fn byte_vector_requires_exact_length() {
+        assert!(<ByteVector<U4>>::from_bytes(vec![1, 2, 3, 4]).is_ok());
+        assert_eq!(
+            <ByteVector<U4>>::from_bytes(vec![1, 2, 3]),
+            Err(Error::InvalidByteCount {
+                given: 3,
+                expected: 4,
+            }),
+        );
+    }
+
+    #[test]
+    fn ssz_round_trip() {
+        let list = <ByteList<U4>>::from_bytes(vec![1, 2]).expect("Test");
+        assert_eq!(
+            <ByteList<U4>>::from_ssz_bytes(&list.as_ssz_bytes()).expect("Test"),
+            list,
+        );
+
+        let vector = <ByteVector<U4>>::from_bytes(vec![1, 2, 3, 4]).expect("Test");
+        assert_eq!(
+            <ByteVector<U4>>::from_ssz_bytes(&vector.as_ssz_bytes()).expect("Test"),
+            vector,
+        );
+    }
+}
@@ -1,11 +1,125 @@
-use ssz_types::FixedVector;
+use core::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut, Index, IndexMut},
+    slice::SliceIndex,
+};
+
+use ethereum_types::H256;
+use serde::{Deserialize, Serialize};
 use typenum::Unsigned;
 
 use crate::{
+    hash,
+    types::Error,
     utils::{decode_variable_sized_items, encode_offset},
-    SszDecode, SszDecodeError, SszEncode, BYTES_PER_LENGTH_OFFSET,
+    SszDecode, SszDecodeError, SszEncode, SszHash, BYTES_PER_LENGTH_OFFSET,
 };
 
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FixedVector<T, N> {
+    vec: Vec<T>,
+    #[serde(skip)]
+    phantom: PhantomData<N>,
+}
+
+impl<T, N: Unsigned> FixedVector<T, N> {
+    pub fn new(vec: Vec<T>) -> Result<Self, Error> {
+        if vec.len() == N::USIZE {
+            Ok(Self {
+                vec,
+                phantom: PhantomData,
+            })
+        } else {
+            Err(Error::OutOfBounds {
+                i: vec.len(),
+                len: N::USIZE,
+            })
+        }
+    }
+
+    #[must_use]
+    pub fn from_elem(element: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            vec: vec![element; N::USIZE],
+            phantom: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn capacity() -> usize {
+        N::USIZE
+    }
+}
+
+// This returns an empty vector rather than one with `N` default elements.
+// Code relying on that is hard to find, so the behavior is preserved.
+impl<T, N> Default for FixedVector<T, N> {
+    fn default() -> Self {
+        Self {
+            vec: vec![],
+            phantom: PhantomData,
+        }
+    }
+}
+
+// Short vectors are padded with default elements. Long ones are truncated.
+impl<T: Default, N: Unsigned> From<Vec<T>> for FixedVector<T, N> {
+    fn from(mut vec: Vec<T>) -> Self {
+        vec.resize_with(N::USIZE, T::default);
+        Self {
+            vec,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, N> From<FixedVector<T, N>> for Vec<T> {
+    fn from(vector: FixedVector<T, N>) -> Self {
+        vector.vec
+    }
+}
+
+impl<T, N, I: SliceIndex<[T]>> Index<I> for FixedVector<T, N> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        Index::index(&self.vec, index)
+    }
+}
+
+impl<T, N, I: SliceIndex<[T]>> IndexMut<I> for FixedVector<T, N> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        IndexMut::index_mut(&mut self.vec, index)
+    }
+}
+
+impl<T, N> Deref for FixedVector<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.vec
+    }
+}
+
+impl<T, N> DerefMut for FixedVector<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.vec
+    }
+}
+
+impl<'vector, T, N> IntoIterator for &'vector FixedVector<T, N> {
+    type Item = &'vector T;
+    type IntoIter = core::slice::Iter<'vector, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vec.iter()
+    }
+}
+
 impl<T: SszEncode, N: Unsigned> SszEncode for FixedVector<T, N> {
     fn as_ssz_bytes(&self) -> Vec<u8> {
         let mut result = vec![];
@@ -99,12 +213,40 @@ impl<T: SszDecode + Default, N: Unsigned> SszDecode for FixedVector<T, N> {
     }
 }
 
+impl<T: SszHash, N: Unsigned> SszHash for FixedVector<T, N> {
+    fn tree_hash_root(&self) -> H256 {
+        let bytes = hash::packed_encoding(self.iter());
+        hash::merkle_root(&bytes, hash::chunk_limit::<T>(N::USIZE))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use ssz_types::VariableList;
     use typenum::{U3, U4, U5};
 
     use super::*;
+    use crate::types::VariableList;
+
+    #[test]
+    fn new_requires_exact_length() {
+        assert!(<FixedVector<u16, U3>>::new(vec![1, 2, 3]).is_ok());
+        assert_eq!(
+            <FixedVector<u16, U3>>::new(vec![1, 2]),
+            Err(Error::OutOfBounds { i: 2, len: 3 }),
+        );
+    }
+
+    #[test]
+    fn from_vec_pads_and_truncates() {
+        assert_eq!(
+            <FixedVector<u16, U3>>::from(vec![1]).to_vec(),
+            vec![1, 0, 0]
+        );
+        assert_eq!(
+            <FixedVector<u16, U3>>::from(vec![1, 2, 3, 4]).to_vec(),
+            vec![1, 2, 3],
+        );
+    }
 
     mod serialize {
         use super::*;
@@ -196,4 +338,19 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn tree_hash_root() {
+        let vec: FixedVector<u16, U3> = FixedVector::from(vec![1, 2, 3]);
+        let mut chunk = H256::zero();
+        chunk[..6].copy_from_slice(&[1, 0, 2, 0, 3, 0]);
+        assert_eq!(vec.tree_hash_root(), chunk);
+
+        let vec: FixedVector<H256, U3> = FixedVector::from(vec![H256::repeat_byte(1)]);
+        let root = hashing::concatenate_and_hash(
+            hashing::concatenate_and_hash(H256::repeat_byte(1), H256::zero()),
+            hashing::ZERO_HASHES[1],
+        );
+        assert_eq!(vec.tree_hash_root(), root);
+    }
 }
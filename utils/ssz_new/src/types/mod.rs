@@ -1,6 +1,35 @@
-pub use ssz_types::{BitList, BitVector, ByteList, ByteVector, Error, FixedVector, VariableList};
+use thiserror::Error;
+
+pub use bitfield::{BitList, BitVector, Bitfield};
+pub use byte_field::{ByteList, ByteVector, Bytefield};
+pub use fixed_vector::FixedVector;
+pub use variable_list::VariableList;
 
 mod bitfield;
 mod byte_field;
 mod fixed_vector;
 mod variable_list;
+
+// Marker types that distinguish the list and vector flavors of `Bitfield` and `Bytefield`.
+pub mod length {
+    use core::marker::PhantomData;
+
+    #[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+    pub struct Fixed<N> {
+        phantom: PhantomData<N>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+    pub struct Variable<N> {
+        phantom: PhantomData<N>,
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Error)]
+#[error("{self:?}")]
+pub enum Error {
+    OutOfBounds { i: usize, len: usize },
+    MissingLengthInformation,
+    ExcessBits,
+    InvalidByteCount { given: usize, expected: usize },
+}
@@ -1,7 +1,123 @@
-use ssz_types::VariableList;
+use core::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut, Index, IndexMut},
+    slice::SliceIndex,
+};
+
+use ethereum_types::H256;
+use serde::{Deserialize, Serialize};
 use typenum::Unsigned;
 
-use crate::{utils, SszDecode, SszDecodeError, SszEncode};
+use crate::{hash, types::Error, utils, SszDecode, SszDecodeError, SszEncode, SszHash};
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VariableList<T, N> {
+    vec: Vec<T>,
+    #[serde(skip)]
+    phantom: PhantomData<N>,
+}
+
+impl<T, N: Unsigned> VariableList<T, N> {
+    pub fn new(vec: Vec<T>) -> Result<Self, Error> {
+        if vec.len() <= N::USIZE {
+            Ok(Self {
+                vec,
+                phantom: PhantomData,
+            })
+        } else {
+            Err(Error::OutOfBounds {
+                i: vec.len(),
+                len: N::USIZE,
+            })
+        }
+    }
+
+    #[must_use]
+    pub fn empty() -> Self {
+        Self {
+            vec: vec![],
+            phantom: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn max_len() -> usize {
+        N::USIZE
+    }
+
+    pub fn push(&mut self, value: T) -> Result<(), Error> {
+        if self.vec.len() < N::USIZE {
+            self.vec.push(value);
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds {
+                i: self.vec.len() + 1,
+                len: N::USIZE,
+            })
+        }
+    }
+}
+
+impl<T, N: Unsigned> Default for VariableList<T, N> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+// Elements past the maximum length are silently dropped.
+impl<T, N: Unsigned> From<Vec<T>> for VariableList<T, N> {
+    fn from(mut vec: Vec<T>) -> Self {
+        vec.truncate(N::USIZE);
+        Self {
+            vec,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, N> From<VariableList<T, N>> for Vec<T> {
+    fn from(list: VariableList<T, N>) -> Self {
+        list.vec
+    }
+}
+
+impl<T, N, I: SliceIndex<[T]>> Index<I> for VariableList<T, N> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        Index::index(&self.vec, index)
+    }
+}
+
+impl<T, N, I: SliceIndex<[T]>> IndexMut<I> for VariableList<T, N> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        IndexMut::index_mut(&mut self.vec, index)
+    }
+}
+
+impl<T, N> Deref for VariableList<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.vec
+    }
+}
+
+impl<T, N> DerefMut for VariableList<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.vec
+    }
+}
+
+impl<'list, T, N> IntoIterator for &'list VariableList<T, N> {
+    type Item = &'list T;
+    type IntoIter = core::slice::Iter<'list, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vec.iter()
+    }
+}
 
 impl<T: SszEncode + Clone, N: Unsigned> SszEncode for VariableList<T, N> {
     fn as_ssz_bytes(&self) -> Vec<u8> {
@@ -60,6 +176,14 @@ impl<T: SszDecode, N: Unsigned> SszDecode for VariableList<T, N> {
     }
 }
 
+impl<T: SszHash, N: Unsigned> SszHash for VariableList<T, N> {
+    fn tree_hash_root(&self) -> H256 {
+        let bytes = hash::packed_encoding(self.iter());
+        let root = hash::merkle_root(&bytes, hash::chunk_limit::<T>(N::USIZE));
+        hash::mix_in_length(root, self.len())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use typenum::{U1, U1024, U20, U3, U4};
@@ -85,4 +209,49 @@ mod tests {
 
         assert!(<VariableList<u8, U1>>::from_ssz_bytes(&[1, 2, 3]).is_err())
     }
+
+    #[test]
+    fn new_rejects_too_many_elements() {
+        assert!(<VariableList<u16, U3>>::new(vec![1, 2, 3]).is_ok());
+        assert_eq!(
+            <VariableList<u16, U3>>::new(vec![1, 2, 3, 4]),
+            Err(Error::OutOfBounds { i: 4, len: 3 }),
+        );
+    }
+
+    #[test]
+    fn push_stops_at_max_len() {
+        let mut list = <VariableList<u16, U1>>::empty();
+        assert_eq!(list.push(1), Ok(()));
+        assert_eq!(list.push(2), Err(Error::OutOfBounds { i: 2, len: 1 }));
+        assert_eq!(list.to_vec(), vec![1]);
+    }
+
+    #[test]
+    fn from_vec_truncates() {
+        let list = <VariableList<u16, U3>>::from(vec![1, 2, 3, 4]);
+        assert_eq!(list.to_vec(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn tree_hash_root() {
+        let list = <VariableList<u16, U20>>::new(vec![1, 2]).expect("Test");
+        let mut chunk = H256::zero();
+        chunk[..4].copy_from_slice(&[1, 0, 2, 0]);
+        let root = hashing::concatenate_and_hash(chunk, H256::zero());
+        assert_eq!(list.tree_hash_root(), hash::mix_in_length(root, 2));
+
+        let list = <VariableList<H256, U3>>::new(vec![H256::repeat_byte(1)]).expect("Test");
+        let root = hashing::concatenate_and_hash(
+            hashing::concatenate_and_hash(H256::repeat_byte(1), H256::zero()),
+            hashing::ZERO_HASHES[1],
+        );
+        assert_eq!(list.tree_hash_root(), hash::mix_in_length(root, 1));
+
+        let empty = <VariableList<u64, U1024>>::empty();
+        assert_eq!(
+            empty.tree_hash_root(),
+            hash::mix_in_length(hashing::ZERO_HASHES[8], 0),
+        );
+    }
 }
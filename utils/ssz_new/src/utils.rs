@@ -216,10 +216,10 @@ mod tests {
     }
 
     mod decoder {
-        use ssz_types::VariableList;
         use typenum::U4;
 
         use super::*;
+        use crate::types::VariableList;
 
         #[test]
         fn only_fixed() {
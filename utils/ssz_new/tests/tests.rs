@@ -1,6 +1,6 @@
 use ethereum_types::U256;
-use ssz_new_derive::{SszDecode, SszEncode};
-use ssz_types::VariableList;
+use ssz_new::types::VariableList;
+use ssz_new_derive::{SszDecode, SszEncode, SszHash};
 use typenum::U5;
 
 // The `unused_extern_crates` lint checks every crate in a package separately.
@@ -8,26 +8,26 @@ use typenum::U5;
 #[cfg(test)]
 use thiserror as _;
 
-#[derive(SszEncode, SszDecode, PartialEq, Debug)]
+#[derive(SszEncode, SszDecode, SszHash, PartialEq, Debug)]
 struct Fixed {
     a: u16,
     b: bool,
 }
 
-#[derive(SszEncode, SszDecode, PartialEq, Debug)]
+#[derive(SszEncode, SszDecode, SszHash, PartialEq, Debug)]
 struct Variable {
     a: u16,
     b: VariableList<u8, U5>,
     c: bool,
 }
 
-#[derive(SszEncode, SszDecode, PartialEq, Debug)]
+#[derive(SszEncode, SszDecode, SszHash, PartialEq, Debug)]
 struct Nested {
     fixed: Fixed,
     variable: Variable,
 }
 
-#[derive(SszEncode, SszDecode, PartialEq, Debug)]
+#[derive(SszEncode, SszDecode, SszHash, PartialEq, Debug)]
 struct Skippable {
     stay_1: [u8; 4],
 
@@ -165,6 +165,162 @@ mod deserialize_derive {
     }
 }
 
+mod hash_derive {
+    use ethereum_types::H256;
+    use hashing::concatenate_and_hash;
+    use ssz_new::SszHash as _;
+
+    use crate::{Fixed, Nested, Skippable, Variable};
+
+    #[test]
+    fn hash_fixed_struct() {
+        let fixed = Fixed { a: 22, b: true };
+
+        assert_eq!(
+            fixed.tree_hash_root(),
+            concatenate_and_hash(22_u16.tree_hash_root(), true.tree_hash_root()),
+        );
+    }
+
+    #[test]
+    fn hash_nested_struct() {
+        let variable = Variable {
+            a: 80,
+            b: vec![1, 2, 3, 4].into(),
+            c: true,
+        };
+        let nested = Nested {
+            fixed: Fixed { a: 5, b: false },
+            variable: Variable {
+                a: 80,
+                b: vec![1, 2, 3, 4].into(),
+                c: true,
+            },
+        };
+
+        // Containers with 3 fields are padded to 4 chunks.
+        let variable_root = concatenate_and_hash(
+            concatenate_and_hash(variable.a.tree_hash_root(), variable.b.tree_hash_root()),
+            concatenate_and_hash(variable.c.tree_hash_root(), H256::zero()),
+        );
+
+        assert_eq!(variable.tree_hash_root(), variable_root);
+        assert_eq!(
+            nested.tree_hash_root(),
+            concatenate_and_hash(nested.fixed.tree_hash_root(), variable_root),
+        );
+    }
+
+    #[test]
+    fn skipped_fields_are_not_hashed() {
+        let skippable = Skippable {
+            stay_1: [1, 2, 3, 4],
+            stay_2: vec![1, 2, 3, 4, 5].into(),
+            skip_1: 42,
+            skip_2: vec![6, 7, 8, 9, 10],
+        };
+
+        assert_eq!(
+            skippable.tree_hash_root(),
+            concatenate_and_hash(
+                skippable.stay_1.tree_hash_root(),
+                skippable.stay_2.tree_hash_root(),
+            ),
+        );
+    }
+}
+
+mod merkleized_derive {
+    use ssz_new::{
+        merkle_root,
+        merkleization::{
+            Merkleized as _,
+            PathElement::{Field, Index, Length},
+        },
+        SszHash as _,
+    };
+
+    use crate::{Fixed, Nested, Skippable, Variable};
+
+    fn nested() -> Nested {
+        Nested {
+            fixed: Fixed { a: 5, b: false },
+            variable: Variable {
+                a: 80,
+                b: vec![1, 2, 3, 4].into(),
+                c: true,
+            },
+        }
+    }
+
+    #[test]
+    fn chunks_are_field_roots_in_order() {
+        let variable = nested().variable;
+        let chunks = variable.chunks();
+
+        assert_eq!(
+            chunks,
+            vec![
+                variable.a.tree_hash_root(),
+                variable.b.tree_hash_root(),
+                variable.c.tree_hash_root(),
+            ],
+        );
+
+        let bytes = chunks
+            .iter()
+            .flat_map(|chunk| chunk.as_bytes().to_vec())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            merkle_root(&bytes, 1 << variable.depth()),
+            variable.tree_hash_root(),
+        );
+    }
+
+    #[test]
+    fn generalized_indices_follow_field_order() {
+        assert_eq!(Nested::generalized_index(&[]).expect("Test"), 1);
+        assert_eq!(
+            Nested::generalized_index(&[Field("fixed")]).expect("Test"),
+            0b10,
+        );
+        // `Variable` has 3 fields, so its tree has a depth of 2.
+        assert_eq!(
+            Nested::generalized_index(&[Field("variable"), Field("c")]).expect("Test"),
+            0b1110,
+        );
+        assert_eq!(
+            Nested::generalized_index(&[Field("variable"), Field("b"), Length]).expect("Test"),
+            0b11011,
+        );
+        assert!(Nested::generalized_index(&[Field("a")]).is_err());
+        assert!(Nested::generalized_index(&[Index(0)]).is_err());
+    }
+
+    #[test]
+    fn children_are_fields() {
+        let nested = nested();
+        let (position, child) = nested.child(Field("variable")).expect("Test");
+
+        assert_eq!(position, 1);
+        assert_eq!(
+            child.map(|child| child.root()),
+            Some(nested.variable.tree_hash_root()),
+        );
+        assert!(nested.child(Length).is_err());
+    }
+
+    #[test]
+    fn skipped_fields_are_not_merkleized() {
+        assert_eq!(
+            Skippable::generalized_index(&[Field("stay_2")]).expect("Test"),
+            0b11,
+        );
+        assert!(Skippable::generalized_index(&[Field("skip_1")]).is_err());
+    }
+}
+
 mod round_trips {
     use ethereum_types::U256;
     use ssz_new::{SszDecode, SszEncode};
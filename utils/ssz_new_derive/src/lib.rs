@@ -158,6 +158,117 @@ pub fn decode_derive(input: TokenStream) -> TokenStream {
     generated.into()
 }
 
+#[proc_macro_derive(SszHash, attributes(ssz))]
+pub fn hash_derive(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).expect("AST should be correct");
+
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = &ast.generics.split_for_impl();
+    let fields = get_serializable_fields(&ast.data);
+
+    let fields_count = fields.iter().len();
+
+    let mut root_pushes = Vec::with_capacity(fields_count);
+    let mut generalized_index_arms = Vec::with_capacity(fields_count);
+    let mut chunks = Vec::with_capacity(fields_count);
+    let mut child_arms = Vec::with_capacity(fields_count);
+    for (position, field) in fields.into_iter().enumerate() {
+        let field_name = match &field.ident {
+            Some(ident) => ident,
+            _ => panic!("All fields must have names"),
+        };
+        let path_element = field_name.to_string();
+
+        root_pushes.push(quote! {
+            bytes.extend_from_slice(ssz_new::SszHash::tree_hash_root(&self.#field_name).as_bytes());
+        });
+
+        generalized_index_arms.push(quote! {
+            ssz_new::merkleization::PathElement::Field(#path_element) => {
+                ssz_new::merkleization::field_generalized_index(
+                    #fields_count,
+                    #position,
+                    |container: &Self| &container.#field_name,
+                    rest,
+                )
+            }
+        });
+
+        chunks.push(quote! {
+            ssz_new::SszHash::tree_hash_root(&self.#field_name)
+        });
+
+        child_arms.push(quote! {
+            ssz_new::merkleization::PathElement::Field(#path_element) => {
+                Ok((#position, Some(&self.#field_name)))
+            }
+        });
+    }
+
+    let generated = quote! {
+        impl #impl_generics ssz_new::SszHash for #name #ty_generics #where_clause {
+            fn tree_hash_root(&self) -> ssz_new::H256 {
+                let mut bytes = Vec::with_capacity(#fields_count * ssz_new::BYTES_PER_CHUNK);
+                #(
+                    #root_pushes
+                )*
+
+                ssz_new::merkle_root(&bytes, #fields_count)
+            }
+        }
+
+        impl #impl_generics ssz_new::merkleization::Merkleized for #name #ty_generics #where_clause {
+            fn generalized_index(
+                path: &[ssz_new::merkleization::PathElement],
+            ) -> Result<
+                ssz_new::merkleization::GeneralizedIndex,
+                ssz_new::merkleization::PathError,
+            > {
+                let (element, rest) = match path.split_first() {
+                    Some(split) => split,
+                    None => return Ok(1),
+                };
+
+                match element {
+                    #(
+                        #generalized_index_arms
+                    )*
+                    _ => Err(ssz_new::merkleization::PathError::PathInvalid),
+                }
+            }
+
+            fn root(&self) -> ssz_new::H256 {
+                ssz_new::SszHash::tree_hash_root(self)
+            }
+
+            fn chunks(&self) -> Vec<ssz_new::H256> {
+                vec![#(#chunks),*]
+            }
+
+            fn depth(&self) -> usize {
+                ssz_new::merkleization::container_depth(#fields_count)
+            }
+
+            fn child(
+                &self,
+                element: ssz_new::merkleization::PathElement,
+            ) -> Result<
+                (usize, Option<&dyn ssz_new::merkleization::Merkleized>),
+                ssz_new::merkleization::PathError,
+            > {
+                match element {
+                    #(
+                        #child_arms
+                    )*
+                    _ => Err(ssz_new::merkleization::PathError::PathInvalid),
+                }
+            }
+        }
+    };
+
+    generated.into()
+}
+
 fn get_serializable_fields(data: &Data) -> Vec<&Field> {
     extract_fields(data)
         .iter()